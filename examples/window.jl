@@ -0,0 +1,37 @@
+using WGPUFontRenderer
+using WGPUFontRenderer: attachSurface, colorAttachmentOptions, currentTextureView, present
+using WGPUCore
+using GLFW
+
+function main()
+    canvas = WGPUCore.getCanvas(:GLFW)
+    device = WGPUCore.getDefaultDevice()
+    surface = attachSurface(device, canvas)
+    renderer = build(TextRendererBuilder(; format=surface.format), device)
+
+    style = TextStyle(loadFont(); size=20)
+
+    try
+        while !GLFW.WindowShouldClose(canvas.windowRef[])
+            view = currentTextureView(surface)
+            queue!(renderer, Section(WGPUFontRenderer.str, (16, 16), style))
+            prepare!(renderer, surface.size)
+
+            encoder = WGPUCore.createCommandEncoder(device, "frame encoder")
+            renderPass = WGPUCore.beginRenderPass(
+                encoder,
+                colorAttachmentOptions(view, (0.1, 0.1, 0.1, 1.0)) |> Ref;
+                label="text pass"
+            )
+            draw!(renderer, renderPass)
+            WGPUCore.endEncoder(renderPass)
+            WGPUCore.submit(device.queue, [WGPUCore.finish(encoder),])
+            present(surface)
+            GLFW.PollEvents()
+        end
+    finally
+        WGPUCore.destroyWindow(canvas)
+    end
+end
+
+main()
@@ -3,6 +3,128 @@ module WGPUFontRenderer
 include("cUtils.jl")
 using .CUtils
 export cStruct, ptr
+include("errors.jl")
+include("features.jl")
+include("instrument.jl")
+include("provider.jl")
+include("opentype.jl")
 include("font.jl")
+include("synthetic.jl")
+include("hexbox.jl")
+include("outline.jl")
+include("missingglyphs.jl")
+include("shaping.jl")
+include("linebreak.jl")
+include("totalfit.jl")
+include("style.jl")
+include("transform2d.jl")
+include("animation.jl")
+include("layout.jl")
+include("halo.jl")
+include("monospace.jl")
+include("inline.jl")
+include("highlight.jl")
+include("kashida.jl")
+include("vertical.jl")
+include("ruby.jl")
+include("projection.jl")
+include("lod.jl")
+include("interop.jl")
+include("textpath.jl")
+include("flow.jl")
+include("caret.jl")
+include("accessibility.jl")
+include("renderer.jl")
+include("curveencoding.jl")
+include("gpulayout.jl")
+include("hotreload.jl")
+include("shaderhook.jl")
+include("shelfpacker.jl")
+include("atlas.jl")
+include("msdf.jl")
+include("glyphatlas.jl")
+include("pathpolicy.jl")
+include("tessellation.jl")
+include("labelcache.jl")
+include("stereo.jl")
+include("adapter.jl")
+include("textrenderer.jl")
+include("atlasdebug.jl")
+include("config.jl")
+include("scene.jl")
+include("terminal.jl")
+include("maplabels.jl")
+include("subtitles.jl")
+include("labelbatch.jl")
+include("textpool.jl")
+include("instancedtext.jl")
+include("curvedebug.jl")
+include("loopblinn.jl")
+include("stenciltext.jl")
+include("headless.jl")
+include("computeraster.jl")
+include("surface.jl")
+include("multisurface.jl")
+include("export.jl")
+
+export features, setFeature!
+export reloadShader!
+export FontRenderError, FontRenderErrorKind, ioError, fontParseError, deviceLimitError, shaderCompileError, glyphMissingError, adapterError
+export FontProvider, FontMetrics, GlyphMetrics, FreeTypeProvider, fontMetrics, glyphIndex, loadGlyph!
+export PathSegment, SegmentKind, segmentMove, segmentLine, segmentQuad, segmentCubic, glyphOutline
+export FontFace, loadFont, fetchFont, loadFontAsync, fetchFontAsync, registerGlyph!, TextStyle
+export FigureStyle, figuresDefault, figuresLining, figuresOldstyle
+export FontVariantCaps, capsNormal, capsSmall, capsAllSmall
+export VerticalPosition, positionBaseline, positionSuper, positionSub
+export TextTransform, transformNone, transformUppercase, transformLowercase, transformCapitalize
+export ControlPolicy, controlStrip, controlPictures, controlHexBoxes
+export WritingMode, horizontalTopToBottom, verticalRightToLeft
+export GridOverflow, gridNextLine, gridOffGrid
+export availableStylisticSets, stylisticSetMask
+export VariationAxis, variationAxes, opticalSizeFace, emboldenedFace
+export MissingGlyph, onMissingGlyph, offMissingGlyph, missingGlyphChannel
+export validText, lineBreaks, LineBreakStrictness, lineBreakLoose, lineBreakNormal, lineBreakStrict, LineBreaker, greedyBreaking, optimalBreaking, TextAlign, alignLeft, alignCenter, alignRight, alignJustify, layoutText, layoutSpans, layoutParagraphs, transformLayout, transformGlyphs, GlyphRun, RunGlyph, layoutFromRuns
+export TextPath, quadraticPath, cubicPath, layoutOnPath
+export Exclusion, TextContainer, LineBox, flowText, ColumnLayout, layoutColumns, Page, paginate
+export caretPosition, hitTest
+export CharacterBox, AccessibleRun, accessibleRuns, selectionBoxes
+export InlineObject, inlineObjectBoxes
+export HighlightSpan, highlightLayout
+export Ruby, layoutRuby
+export GlyphAnimation, animateWave, animateShake, animateFadeIn, animateGlyphs, staggerAnimation
+export Affine2, translation, scaling, rotation, skewing
+export Projection, orthographic, fromCamera, textPlane
+export Billboard, BillboardMode, billboardConstantSize, billboardDistanceScaled
+export DistanceLOD, projectedEmSize, applyLOD
+export DepthConvention, standardDepth, reverseDepth
+export AntiAliasingMode, antiAliasingIsotropic, antiAliasingAnisotropic
+export RenderOptions, ColorSpace, colorSpaceSRGB, colorSpaceLinear, BlendMode, blendPremultiplied, blendAdditive
+export DebugView, debugViewNone, debugViewHeatmap, debugViewQuads, debugViewOverdraw
+export TessellationMode, tessellationAuto, tessellationOn, tessellationOff
+export AtlasFormat, atlasR8, atlasRGBA8
+export RenderPath, pathCurves, pathSDF, pathBitmap, PathStats, pathStats
+export DrawStats, drawStats
+export atlasStats, queueAtlasDebug!
+export CurveDebug, debugLayout!
+export LoopBlinnText
+export StencilText, FillRule, fillNonZero, fillEvenOdd
+export TextRendererBuilder, setFormat!, setSampleCount!, setAntiAliasing!, setColorSpace!, setBlendMode!, setDebugView!, setFragmentHook!, setDepth!
+export AdapterOptions, Backend, backendAny, backendVulkan, backendMetal, backendDX12, backendGL, setAdapter!, requestRenderDevice
+export TextRenderer, Section, build, queue!, queueStatic!, prepare!, draw!, recreate!, reconfigure!
+export bindGroupLayout, renderPipeline, pipelineLayout, fontBuffers, glyphBuffer, curveBuffer, getBindingLayouts, getBindings, getShaderCode
+export BufferGlyph, BufferCurve, HalfCurve, gpuLayoutVersion
+export CurveEncoding, curveFloat32, curveFloat16, curveUnorm16, setCurveEncoding!
+export SurfaceView
+export TextScene, TextHandle, update!
+export Halo, haloLayout, MapLabel, LabelPlacer, LabelPlacement, greedyPlacement, annealingPlacement, PlacedLabel, setLabel!, removeLabel!, placeLabels!, queueLabels!
+export LabelBatch
+export TextPool, TextEasing, easeLinear, easeOut, easeIn, spawn!
+export InstancedText, GlyphInstance, fontIndex!
+export SubtitleTrack, SubtitlePreset, SubtitleCue, KaraokeSyllable, setPreset!, addCue!, queueSubtitles!
+export TerminalGrid, TerminalCell, setCell!, setCells!, clear!, cellUnderline, cellStrikethrough, cellBold, cellDim, cellReverse, cellBlink
+export CursorShape, cursorBlock, cursorUnderline, cursorBar, cursorHollowBlock, setCursor!, hideCursor!
+export Theme, loadTheme, saveTheme, watchTheme
+export renderToTexture, exportPNG, exportSVG
+export ComputeRaster, RasterTarget, rasterize!, rasterizeText
 
 end # module WGPUFontRenderer
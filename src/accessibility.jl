@@ -0,0 +1,114 @@
+# Geometry for screen readers and focus or caret overlays, e.g. text run
+# nodes of AccessKit or platform accessibility APIs.
+# Runs are the lines of a layout in reading order, their characters cover
+# the glyph cells of caret.jl split evenly between the characters of a
+# cluster, so boxes match the carets and the rendered text. Boxes are axis
+# aligned bounds in the space of `project`, which maps layout points to the
+# screen, e.g. `p -> p .+ origin` for text drawn at an offset.
+#
+#     runs = accessibleRuns(layout, text; project=p -> p ./ scaleFactor)
+#     for run in runs
+#         node = textRunNode(run.text, [c.box for c in run.characters])
+#     end
+
+struct CharacterBox
+    index::Int                      # string index of the character
+    box::NTuple{4, Float32}         # x, y, width, height
+end
+
+struct AccessibleRun
+    text::String
+    range::UnitRange{Int}           # string indices of the run's characters
+    line::Int
+    box::NTuple{4, Float32}
+    characters::Vector{CharacterBox}
+end
+
+function boundingBox(points)
+    (xs, ys) = (first.(points), last.(points))
+    (x0, y0) = (minimum(xs), minimum(ys))
+    return (x0, y0, maximum(xs) - x0, maximum(ys) - y0)
+end
+
+unionBox(boxes) = boundingBox([p for (x, y, w, h) in boxes for p in ((x, y), (x + w, y + h))])
+
+# Glyph indices of every line in layout order, a line ends where the pen
+# moves back or leaves the baseline by more than half the line box.
+function layoutLines(glyphs)
+    lines = Vector{Int}[]
+    for (i, pg) in enumerate(glyphs)
+        if isempty(lines)
+            push!(lines, [i])
+            continue
+        end
+        previous = glyphs[last(lines[end])]
+        (x, y) = inv(previous.transform)*((pg.x, pg.y) .- (previous.x, previous.y))
+        tolerance = sum(glyphExtent(previous))/2
+        x < -tolerance || abs(y) > tolerance ? push!(lines, [i]) : push!(lines[end], i)
+    end
+    return lines
+end
+
+"""
+    accessibleRuns(layout, text; project=identity) -> Vector{AccessibleRun}
+
+Lines of `layout` in reading order with the screen box of every character
+of `text` they show. Line breaks belong to no run.
+"""
+function accessibleRuns(layout::TextLayout, text::TextInput; project=identity)
+    text = validText(text)
+    glyphs = layout.glyphs
+    lines = layoutLines(glyphs)
+    starts = [minimum(i -> glyphs[i].cluster, line) for line in lines]
+    runs = AccessibleRun[]
+    for (l, line) in enumerate(lines)
+        # the widest glyph of a cluster carries it, marks and halos add no width
+        carriers = Dict{Int, PositionedGlyph}()
+        for i in line
+            pg = glyphs[i]
+            carrier = get(carriers, pg.cluster, nothing)
+            (carrier === nothing || glyphAdvance(pg) > glyphAdvance(carrier)) && (carriers[pg.cluster] = pg)
+        end
+        clusters = sort!(collect(keys(carriers)))
+        following = filter(>(last(clusters)), starts)
+        stop = isempty(following) ? nextind(text, lastindex(text)) : minimum(following)
+        characters = CharacterBox[]
+        for (k, cluster) in enumerate(clusters)
+            next = k < length(clusters) ? clusters[k + 1] : stop
+            indices = Int[]
+            i = cluster
+            while i < next && i <= lastindex(text)
+                text[i] == '\n' || push!(indices, i)
+                i = nextind(text, i)
+            end
+            isempty(indices) && continue
+            pg = carriers[cluster]
+            advance = glyphAdvance(pg)/length(indices)
+            (ascent, descent) = glyphExtent(pg)
+            for (j, index) in enumerate(indices)
+                (x0, x1) = ((j - 1)*advance, j*advance)
+                corners = [project((pg.x, pg.y) .+ pg.transform*c) for c in ((x0, -ascent), (x1, -ascent), (x1, descent), (x0, descent))]
+                push!(characters, CharacterBox(index, boundingBox(corners)))
+            end
+        end
+        isempty(characters) && continue
+        range = first(characters).index:last(characters).index
+        push!(runs, AccessibleRun(text[range], range, l, unionBox(c.box for c in characters), characters))
+    end
+    return sort!(runs; by=run -> first(run.range))
+end
+
+"""
+    selectionBoxes(runs, range) -> Vector{NTuple{4, Float32}}
+
+One box per run around its characters in the string range `range`, for
+focus rings and selection highlights.
+"""
+function selectionBoxes(runs, range)
+    boxes = NTuple{4, Float32}[]
+    for run in runs
+        selected = [c.box for c in run.characters if c.index in range]
+        isempty(selected) || push!(boxes, unionBox(selected))
+    end
+    return boxes
+end
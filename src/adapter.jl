@@ -0,0 +1,87 @@
+# Where the renderer runs. By default WGPUCore picks a high performance
+# adapter on any backend, embedders and CI machines narrow that down.
+#
+#     builder = TextRendererBuilder()
+#     setAdapter!(builder; backend=backendVulkan, powerPreference=WGPUCore.WGPUPowerPreference_LowPower)
+#     renderer = build(builder)
+
+@enum Backend begin
+    backendAny
+    backendVulkan
+    backendMetal
+    backendDX12
+    backendGL
+end
+
+Base.@kwdef struct AdapterOptions
+    powerPreference = WGPUCore.WGPUPowerPreference_HighPerformance
+    backend::Backend = backendAny
+    # retry with the software fallback adapter (lavapipe, WARP, llvmpipe) when no adapter matched
+    allowSoftwareFallback::Bool = true
+    # half precision curve buffers where the adapter has shader-f16, see curveencoding.jl
+    halfPrecisionCurves::Bool = true
+    # curves as 16 bit fractions of glyph boxes on any adapter, takes precedence
+    quantizedCurves::Bool = false
+    # float curves shared between the glyphs repeating them
+    deduplicateCurves::Bool = false
+end
+
+const backendNames = Dict(
+    backendVulkan => "vulkan",
+    backendMetal => "metal",
+    backendDX12 => "dx12",
+    backendGL => "gl",
+)
+
+# Backend types wgpu reports for every backend.
+const backendTypes = Dict(
+    backendVulkan => (WGPUCore.WGPUBackendType_Vulkan,),
+    backendMetal => (WGPUCore.WGPUBackendType_Metal,),
+    backendDX12 => (WGPUCore.WGPUBackendType_D3D12,),
+    backendGL => (WGPUCore.WGPUBackendType_OpenGL, WGPUCore.WGPUBackendType_OpenGLES),
+)
+
+# wgpu reads the backend selection from the environment when the instance is
+# created, so this only narrows instances created afterwards, `checkAdapter`
+# catches the rest.
+function selectBackend(backend::Backend)
+    backend == backendAny && return
+    ENV["WGPU_BACKEND"] = backendNames[backend]
+end
+
+# Adapters on another backend than requested, or fallbacks running on real
+# hardware, are rejected instead of silently rendering elsewhere.
+function checkAdapter(adapter, options::AdapterOptions; fallback=false)
+    hasproperty(adapter, :properties) || return adapter
+    properties = adapter.properties
+    options.backend == backendAny || properties.backendType in backendTypes[options.backend] || throw(FontRenderError(
+        adapterError, "Requested $(options.backend) but the adapter runs on $(properties.backendType)"
+    ))
+    fallback && properties.adapterType != WGPUCore.WGPUAdapterType_CPU && throw(FontRenderError(
+        adapterError, "The fallback adapter is no software renderer but $(properties.adapterType)"
+    ))
+    return adapter
+end
+
+function requestRenderDevice(options::AdapterOptions=AdapterOptions(); canvas=nothing)
+    selectBackend(options.backend)
+    adapter = try
+        checkAdapter(WGPUCore.requestAdapter(; canvas=canvas, powerPreference=options.powerPreference), options)
+    catch err
+        options.allowSoftwareFallback || throw(FontRenderError(adapterError, "No adapter for $options : $err"))
+        @warn "No adapter matched, falling back to the software adapter" exception=err
+        fallback = try
+            WGPUCore.requestAdapter(; canvas=canvas, powerPreference=WGPUCore.WGPUPowerPreference_LowPower, forceFallbackAdapter=true)
+        catch err
+            throw(FontRenderError(adapterError, "No fallback adapter : $err"))
+        end
+        checkAdapter(fallback, options; fallback=true)
+    end
+    device = WGPUCore.requestDevice(adapter; requiredFeatures=deviceFeatures(adapter, options))
+    encoding = options.quantizedCurves ? curveUnorm16 : curveEncoding(device)
+    setCurveEncoding!(device, encoding; deduplicate=options.deduplicateCurves)
+    return device
+end
+
+deviceFeatures(adapter, options::AdapterOptions) =
+    options.halfPrecisionCurves && !options.quantizedCurves && hasFeature(adapter, shaderF16) ? [shaderF16] : []
@@ -0,0 +1,20 @@
+# Per glyph animation channels.
+# Every glyph carries a time offset, an amplitude and effect flags in its
+# vertices, the vertex shader animates them against the global time uniform
+# so effects run without relayout on the CPU. Effects see the local time
+# `time - timeOffset`:
+#   animateWave     vertical sine offset of `amplitude` pixels
+#   animateShake    random jitter of up to `amplitude` pixels
+#   animateFadeIn   opacity ramps from 0 to 1 over the fade duration
+
+struct GlyphAnimation
+    timeOffset::Float32
+    amplitude::Float32
+    flags::UInt32
+end
+
+const animateWave = UInt32(1)
+const animateShake = UInt32(2)
+const animateFadeIn = UInt32(4)
+
+const noAnimation = GlyphAnimation(0, 0, 0)
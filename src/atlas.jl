@@ -0,0 +1,448 @@
+# Distance field atlas for small text.
+# Below `RenderOptions.smallTextThreshold` pixels per em exact curves do not
+# pay off, such glyphs are drawn as textured quads sampling a signed distance
+# field instead. Fields are generated once per glyph from the same em space
+# curves the analytic path uses, 0.5 is the outline and values grow towards
+# the inside. Single channel fields are built on the cpu, multi channel
+# fields by the compute pass in msdf.jl.
+#
+# Atlases are texture arrays. When every page is full another layer is added
+# instead of evicting glyphs, quads carry their layer in the vertex
+# `bufferIndex`, so sessions going through many sizes and fonts keep their
+# cells until `maxPages` is reached.
+#
+# Cpu backed pages carry `atlasMipLevels` box filtered mips sampled
+# trilinearly, so atlas text shrinking in a zoom animation does not alias
+# before the curve path takes over. Cells are padded by the footprint of the
+# last level to keep neighbours from bleeding in. Flushes downsample and
+# upload only the rectangles of the pages that changed.
+#
+# Every entry remembers the atlas frame it was last drawn in. A full atlas
+# first drops entries unused for `atlasStaleFrames` frames, then grows, and
+# at `maxPages` evicts least recently used entries until the new cell fits.
+# Pages left without entries are repacked from scratch.
+
+const sdfShaderSource = embedShader("sdf.wgsl")
+
+const sdfTexelsPerEm = 32
+const sdfSpread = 4         # texels of distance encoded on each side of the outline
+const atlasMipLevels = 4
+const atlasStaleFrames = 120
+
+struct AtlasEntry
+    x::Int                  # texel rectangle in the atlas, zero based
+    y::Int
+    layer::Int
+    width::Int
+    height::Int
+    left::Float32           # em space rectangle covered by the texels, y up
+    bottom::Float32
+    right::Float32
+    top::Float32
+    colored::Bool           # rgba bitmap drawn without the text color
+end
+
+mutable struct SDFAtlas
+    device
+    size::Int
+    msdf::Bool
+    texture
+    view
+    sampler
+    # cpu copy per page, row major with the top row first
+    texels::Vector{Matrix{UInt8}}
+    entries::Dict{Tuple{FontFace, FT_UInt}, AtlasEntry}
+    lastUse::Dict{Tuple{FontFace, FT_UInt}, Int}
+    frame::Int                      # advanced by every prepare! drawing from the atlas
+    packers::Vector{ShelfPacker}
+    maxPages::Int
+    evictions::Int
+    # texels per page changed since the last flush, (x0, y0, x1, y1) zero based
+    # and exclusive, nothing for clean pages
+    dirtyRects::Vector{Union{Nothing, NTuple{4, Int}}}
+    bindGroups::IdDict{Any, Any}    # per uniform buffer
+    # msdf cells allocated but not generated yet
+    pending::Vector{Tuple{FontFace, Glyph, AtlasEntry}}
+    generator                       # msdf compute pipeline, built on first use
+end
+
+function createAtlasTexture(
+        device, size;
+        layers=1, label="sdf atlas", mipLevels=1,
+        format=WGPUCore.WGPUTextureFormat_R8Unorm, usage=["TextureBinding", "CopyDst"]
+    )
+    WGPUCore.createTexture(
+        device, label,
+        (size, size, layers),
+        mipLevels, 1,
+        WGPUCore.WGPUTextureDimension_2D,
+        format,
+        WGPUCore.getEnum(WGPUCore.WGPUTextureUsage, usage)
+    )
+end
+
+# Msdf cells are written by the compute pass instead of uploaded, without mips.
+atlasTextureOptions(atlas::SDFAtlas) = atlasTextureOptions(SDFAtlas; msdf=atlas.msdf)
+atlasTextureOptions(::Type{SDFAtlas}; msdf=false) = msdf ?
+    (label="msdf atlas", mipLevels=1, format=WGPUCore.WGPUTextureFormat_RGBA8Unorm, usage=["TextureBinding", "StorageBinding"]) :
+    (label="sdf atlas", mipLevels=atlasMipLevels, format=WGPUCore.WGPUTextureFormat_R8Unorm, usage=["TextureBinding", "CopyDst"])
+
+atlasPacker(size, mipLevels) = ShelfPacker(size, size; padding=1 << (mipLevels - 1))
+
+createAtlasSampler(device) = WGPUCore.createSampler(
+    device;
+    magFilter=WGPUCore.WGPUFilterMode_Linear,
+    minFilter=WGPUCore.WGPUFilterMode_Linear,
+    mipmapFilter=WGPUCore.WGPUMipmapFilterMode_Linear
+)
+
+# Views always cover all layers, also while the atlas has a single page.
+createArrayView(texture) = WGPUCore.createView(texture; dimension=WGPUCore.WGPUTextureViewDimension_2DArray)
+
+function SDFAtlas(device; size=1024, msdf=false, maxPages=8)
+    textureOptions = atlasTextureOptions(SDFAtlas; msdf=msdf)
+    texture = createAtlasTexture(device, size; textureOptions...)
+    return SDFAtlas(
+        device, size, msdf,
+        texture, createArrayView(texture), createAtlasSampler(device),
+        [zeros(UInt8, size, size)],
+        Dict{Tuple{FontFace, FT_UInt}, AtlasEntry}(),
+        Dict{Tuple{FontFace, FT_UInt}, Int}(),
+        0,
+        [atlasPacker(size, textureOptions.mipLevels)],
+        maxPages,
+        0,
+        Union{Nothing, NTuple{4, Int}}[nothing],
+        IdDict{Any, Any}(),
+        Tuple{FontFace, Glyph, AtlasEntry}[],
+        nothing
+    )
+end
+
+# Distance from `p` to a quadratic, approximated by its chord polyline.
+function curveDistance((px, py), curve::BufferCurve; steps=8)
+    distance = Inf32
+    (ax, ay) = (curve.x0, curve.y0)
+    for i in 1:steps
+        t = Float32(i/steps)
+        s = 1 - t
+        bx = s*s*curve.x0 + 2*s*t*curve.x1 + t*t*curve.x2
+        by = s*s*curve.y0 + 2*s*t*curve.y1 + t*t*curve.y2
+        (dx, dy) = (bx - ax, by - ay)
+        h = clamp(((px - ax)*dx + (py - ay)*dy)/max(dx*dx + dy*dy, 1f-12), 0f0, 1f0)
+        distance = min(distance, hypot(px - ax - h*dx, py - ay - h*dy))
+        (ax, ay) = (bx, by)
+    end
+    return distance
+end
+
+# Nonzero winding of the outline around `p`, from a ray towards +x.
+function winding((px, py), curves)
+    total = 0
+    for curve in curves
+        a = curve.y0 - 2*curve.y1 + curve.y2
+        b = 2*(curve.y1 - curve.y0)
+        c = curve.y0 - py
+        roots = if abs(a) < 1f-8
+            abs(b) < 1f-8 ? () : (-c/b,)
+        else
+            radicand = b*b - 4*a*c
+            radicand < 0 ? () : ((-b - sqrt(radicand))/(2a), (-b + sqrt(radicand))/(2a))
+        end
+        for t in roots
+            0 <= t < 1 || continue
+            s = 1 - t
+            x = s*s*curve.x0 + 2*s*t*curve.x1 + t*t*curve.x2
+            x > px || continue
+            total += sign(2*a*t + b)
+        end
+    end
+    return total
+end
+
+# Texel grid of a glyph cell, the outline bounds padded by the spread.
+function sdfCell(font::FontFace, glyph::Glyph)
+    emSize = font.emSize
+    texel = 1f0/sdfTexelsPerEm
+    left = Float32(glyph.bearingX/emSize) - sdfSpread*texel
+    top = Float32(glyph.bearingY/emSize) + sdfSpread*texel
+    width = ceil(Int, glyph.width/emSize*sdfTexelsPerEm) + 2*sdfSpread
+    height = ceil(Int, glyph.height/emSize*sdfTexelsPerEm) + 2*sdfSpread
+    return (left, top, width, height)
+end
+
+function renderSDF(font::FontFace, glyph::Glyph)
+    curves = glyphCurves(font, glyph)
+    texel = 1f0/sdfTexelsPerEm
+    (left, top, width, height) = sdfCell(font, glyph)
+    field = Matrix{UInt8}(undef, height, width)
+    for row in 1:height, col in 1:width
+        p = (left + (col - 0.5f0)*texel, top - (row - 0.5f0)*texel)
+        distance = minimum(curve -> curveDistance(p, curve), curves; init=Inf32)
+        signed = winding(p, curves) != 0 ? distance : -distance
+        field[row, col] = round(UInt8, 255*clamp(0.5f0 + signed/(2*sdfSpread*texel), 0f0, 1f0))
+    end
+    bounds = (left, top - height*texel, left + width*texel, top)
+    return (field, bounds)
+end
+
+# Replaces the texture by one with another layer. Textures cannot grow in
+# place, the cpu pages are uploaded again on the next flush and msdf cells
+# are generated again.
+function addPage!(atlas)
+    pages = length(atlas.packers)
+    pages < atlas.maxPages || throw(FontRenderError(deviceLimitError, "atlas of $pages pages with $(atlas.size) texels is full"))
+    textureOptions = atlasTextureOptions(atlas)
+    push!(atlas.packers, atlasPacker(atlas.size, textureOptions.mipLevels))
+    push!(atlas.texels, zero(atlas.texels[1]))
+    atlas.texture = createAtlasTexture(atlas.device, atlas.size; layers=pages + 1, textureOptions...)
+    atlas.view = createArrayView(atlas.texture)
+    empty!(atlas.bindGroups)
+    # the new texture starts out empty on every layer
+    atlas.dirtyRects = fill((0, 0, atlas.size, atlas.size), pages + 1)
+    return pageAdded!(atlas)
+end
+
+pageAdded!(atlas) = atlas
+
+function pageAdded!(atlas::SDFAtlas)
+    atlas.msdf && (atlas.pending = [(font, font.glyphs[idx], entry) for ((font, idx), entry) in atlas.entries])
+    return atlas
+end
+
+entryEvicted!(atlas, entry::AtlasEntry) = atlas
+
+function entryEvicted!(atlas::SDFAtlas, entry::AtlasEntry)
+    atlas.msdf && filter!(((_, _, pending),) -> pending !== entry, atlas.pending)
+    return atlas
+end
+
+function markDirty!(atlas, layer, x, y, width, height)
+    rect = atlas.dirtyRects[layer + 1]
+    atlas.dirtyRects[layer + 1] = rect === nothing ? (x, y, x + width, y + height) :
+        (min(rect[1], x), min(rect[2], y), max(rect[3], x + width), max(rect[4], y + height))
+    return atlas
+end
+
+function touch!(atlas, key)
+    atlas.lastUse[key] = atlas.frame
+    return atlas.entries[key]
+end
+
+function evict!(atlas, key)
+    entry = pop!(atlas.entries, key)
+    delete!(atlas.lastUse, key)
+    packer = atlas.packers[entry.layer + 1]
+    release!(packer, entry.x, entry.y, entry.width)
+    isEmpty(packer) && reset!(packer)
+    channels = bytesPerTexel(atlas)
+    atlas.texels[entry.layer + 1][(entry.y + 1):(entry.y + entry.height), (channels*entry.x + 1):(channels*(entry.x + entry.width))] .= 0
+    markDirty!(atlas, entry.layer, entry.x, entry.y, entry.width, entry.height)
+    atlas.evictions += 1
+    return entryEvicted!(atlas, entry)
+end
+
+# Least recently used first, entries drawn at or after frame `before` stay.
+function evictUnused!(atlas, before; until=() -> false)
+    for key in sort!(collect(keys(atlas.lastUse)); by=key -> atlas.lastUse[key])
+        atlas.lastUse[key] < before || break
+        evict!(atlas, key)
+        until() && return true
+    end
+    return false
+end
+
+# First page with room for the cell, zero based layer.
+function packAny!(atlas, width, height)
+    for (layer, packer) in enumerate(atlas.packers)
+        position = pack!(packer, width, height)
+        position === nothing || return (position..., layer - 1)
+    end
+    return nothing
+end
+
+function allocate!(atlas, width, height)
+    position = packAny!(atlas, width, height)
+    position === nothing || return position
+    evictUnused!(atlas, atlas.frame - atlasStaleFrames)
+    position = packAny!(atlas, width, height)
+    position === nothing || return position
+    if length(atlas.packers) < atlas.maxPages
+        addPage!(atlas)
+        position = pack!(atlas.packers[end], width, height)
+        position === nothing && throw(FontRenderError(deviceLimitError, "glyph cell of $(width)x$(height) texels exceeds the atlas"))
+        return (position..., length(atlas.packers) - 1)
+    end
+    # only glyphs of the current and the previous frame are kept, other targets may still draw those
+    evictUnused!(atlas, atlas.frame - 1; until=() -> (position = packAny!(atlas, width, height)) !== nothing)
+    position === nothing && throw(FontRenderError(deviceLimitError, "atlas of $(atlas.maxPages) pages is full with glyphs in use"))
+    return position
+end
+
+function atlasEntry!(atlas::SDFAtlas, font::FontFace, glyph::Glyph)
+    key = (font, glyph.index)
+    get!(atlas.entries, key) do
+        atlas.msdf && return pendingEntry!(atlas, font, glyph)
+        (field, (left, bottom, right, top)) = renderSDF(font, glyph)
+        (height, width) = size(field)
+        (x, y, layer) = allocate!(atlas, width, height)
+        atlas.texels[layer + 1][(y + 1):(y + height), (x + 1):(x + width)] .= field
+        markDirty!(atlas, layer, x, y, width, height)
+        AtlasEntry(x, y, layer, width, height, left, bottom, right, top, false)
+    end
+    return touch!(atlas, key)
+end
+
+# Msdf cells are only allocated here, `flush!` generates them on the gpu.
+function pendingEntry!(atlas::SDFAtlas, font::FontFace, glyph::Glyph)
+    texel = 1f0/sdfTexelsPerEm
+    (left, top, width, height) = sdfCell(font, glyph)
+    (x, y, layer) = allocate!(atlas, width, height)
+    entry = AtlasEntry(x, y, layer, width, height, left, top - height*texel, left + width*texel, top, false)
+    push!(atlas.pending, (font, glyph, entry))
+    return entry
+end
+
+# Channels are interleaved along the rows of the cpu copy.
+bytesPerTexel(atlas) = size(atlas.texels[1], 2) ÷ atlas.size
+
+# Next mip level, averaging 2x2 texels per channel.
+function downsample(texels::Matrix{UInt8}, channels)
+    (height, width) = size(texels) .÷ (2, 2)
+    level = Matrix{UInt8}(undef, height, width)
+    for row in 1:height, col in 1:width
+        (texel, c) = divrem(col - 1, channels)
+        (r, x) = (2row - 1, 2channels*texel + c + 1)
+        total = Int(texels[r, x]) + texels[r, x + channels] + texels[r + 1, x] + texels[r + 1, x + channels]
+        level[row, col] = (total + 2) ÷ 4
+    end
+    return level
+end
+
+# Uploads the changed rectangle of every dirty page. Rectangles are widened
+# to the footprint of the last mip level, so each level downsamples whole
+# texel blocks of the one above.
+function flush!(atlas)
+    channels = bytesPerTexel(atlas)
+    mipLevels = atlasTextureOptions(atlas).mipLevels
+    block = 1 << (mipLevels - 1)
+    for (layer, rect) in enumerate(atlas.dirtyRects)
+        rect === nothing && continue
+        (x0, y0) = (fld(rect[1], block)*block, fld(rect[2], block)*block)
+        (x1, y1) = (min(cld(rect[3], block)*block, atlas.size), min(cld(rect[4], block)*block, atlas.size))
+        level = atlas.texels[layer][(y0 + 1):y1, (channels*x0 + 1):(channels*x1)]
+        for mipLevel in 0:(mipLevels - 1)
+            mipLevel > 0 && (level = downsample(level, channels))
+            (height, width) = (size(level, 1), size(level, 2) ÷ channels)
+            @span "atlas upload" WGPUCore.writeTexture(
+                atlas.device.queue,
+                [
+                    :texture => atlas.texture,
+                    :mipLevel => mipLevel,
+                    :origin => ((x0 >> mipLevel, y0 >> mipLevel, layer - 1) .|> Float32)
+                ],
+                permutedims(level) |> vec,
+                [
+                    :offset => 0,
+                    :bytesPerRow => width*channels,
+                    :rowsPerImage => height
+                ],
+                [
+                    :width => width,
+                    :height => height,
+                    :depthOrArrayLayers => 1
+                ]
+            )
+        end
+        atlas.dirtyRects[layer] = nothing
+    end
+    return atlas
+end
+
+atlasEntry!(atlas::SDFAtlas, pg::PositionedGlyph) = atlasEntry!(atlas, pg.font, pg.glyph)
+
+# Vertex flag next to the animation bits of font.wgsl.
+const colorBitmapFlag = UInt32(0x100)
+
+# Textured quads for any atlas whose entries cover an em space rectangle.
+# Layout pixels per em of atlas quads, distance fields scale freely.
+quadSize(atlas, pg::PositionedGlyph) = pg.size
+
+function appendAtlasVertices!(vertices::Vector{BufferVertex}, indices::Vector{UInt32}, layout::TextLayout, atlas)
+    for pg in layout.glyphs
+        pg.glyph.curveCount == 0 && continue
+        entry = atlasEntry!(atlas, pg)
+        emPixels = quadSize(atlas, pg)
+        color = packColor(pg.color)
+        flags = entry.colored ? pg.animation.flags | colorBitmapFlag : pg.animation.flags
+        base = UInt32(length(vertices))
+        (u0, v0) = (entry.x/atlas.size, (entry.y + entry.height)/atlas.size)
+        (u1, v1) = ((entry.x + entry.width)/atlas.size, entry.y/atlas.size)
+        for (ex, ey, u, v) in (
+                (entry.left, entry.bottom, u0, v0),
+                (entry.right, entry.bottom, u1, v0),
+                (entry.right, entry.top, u1, v1),
+                (entry.left, entry.top, u0, v1),
+            )
+            (x, y) = pg.transform*(ex*emPixels, -ey*emPixels)
+            push!(vertices, BufferVertex(
+                pg.x + x, pg.y + y, u, v, entry.layer,
+                pg.animation.timeOffset, pg.animation.amplitude, flags,
+                color
+            ))
+        end
+        append!(indices, base .+ UInt32[0, 1, 2, 2, 3, 0])
+    end
+end
+
+function getAtlasBindingLayouts(; binding=0)
+    [
+        WGPUCore.WGPUBufferEntry => [
+            :binding => binding,
+            :visibility => ["Vertex", "Fragment"],
+            :type => "Uniform"
+        ],
+        WGPUCore.WGPUTextureEntry => [
+            :binding => binding + 1,
+            :visibility => ["Fragment"],
+            :sampleType => "Float",
+            :viewDimension => "2DArray",
+            :multisampled => false
+        ],
+        WGPUCore.WGPUSamplerEntry => [
+            :binding => binding + 2,
+            :visibility => ["Fragment"],
+            :type => "Filtering"
+        ],
+    ]
+end
+
+function getAtlasBindings(atlas, uniformBuffer; binding=0)
+    [
+        WGPUCore.GPUBuffer => [
+            :binding => binding,
+            :buffer  => uniformBuffer,
+            :offset  => 0,
+            :size    => uniformBuffer.size
+        ],
+        WGPUCore.GPUTextureView => [
+            :binding => binding + 1,
+            :textureView => atlas.view
+        ],
+        WGPUCore.GPUSampler => [
+            :binding => binding + 2,
+            :sampler => atlas.sampler
+        ],
+    ]
+end
+
+const atlasEntryPoints = Dict(
+    :sdf => "fs_main", :msdf => "fs_msdf", :alpha => "fs_alpha", :color => "fs_color", :debug => "fs_debug"
+)
+
+atlasPipelineOptions(kind::Symbol) = (
+    label=String(kind),
+    shaderSource=sdfShaderSource,
+    bindingLayouts=getAtlasBindingLayouts(),
+    fragmentEntryPoint=atlasEntryPoints[kind],
+)
@@ -0,0 +1,81 @@
+# Debug view of the atlases and caches.
+# `queueAtlasDebug!` queues every atlas page as a quad plus a text block with
+# occupancy, entry and eviction counts, drawn by the next prepare! and draw!
+# like any other section. Meant to find out why text goes through the atlas
+# path, or why the atlases keep growing.
+#
+#     queueAtlasDebug!(renderer, debugStyle; origin=(8, 8), pageSize=256)
+
+"""
+    atlasStats(renderer) -> Vector{NamedTuple}
+
+Pages, entries, occupancy of the shelf packers and evictions per atlas.
+"""
+function atlasStats(renderer::TextRenderer)
+    stats = NamedTuple[]
+    for (name, atlas) in (("distance field atlas", renderer.atlas), ("glyph atlas", renderer.glyphAtlas))
+        atlas === nothing && continue
+        push!(stats, (
+            name=name,
+            pages=length(atlas.packers),
+            maxPages=atlas.maxPages,
+            entries=length(atlas.entries),
+            occupancy=sum(occupancy, atlas.packers)/length(atlas.packers),
+            evictions=atlas.evictions,
+        ))
+    end
+    return stats
+end
+
+function debugText(target::TextTarget)
+    renderer = sharedRenderer(target)
+    lines = String[]
+    for s in atlasStats(renderer)
+        push!(lines, "$(s.name): $(s.pages)/$(s.maxPages) pages, $(s.entries) entries, " *
+            "$(round(Int, 100*s.occupancy))% packed, $(s.evictions) evicted")
+    end
+    push!(lines, "static labels: $(length(renderer.labelCache.labels))")
+    push!(lines, string(target.pathStats))
+    push!(lines, string(target.drawStats))
+    return join(lines, "\n")
+end
+
+function queueAtlasDebug!(target::TextTarget, style::TextStyle; origin=(8f0, 8f0), pageSize=256f0)
+    renderer = sharedRenderer(target)
+    (x, y) = Float32.(origin)
+    queue!(target, debugText(target), (x, y), style)
+    y += 5*style.size*style.lineHeight
+    for (kind, atlas) in ((atlasKind(renderer), renderer.atlas), (atlasKind(renderer.options.atlasFormat), renderer.glyphAtlas))
+        atlas === nothing && continue
+        for layer in 0:(length(atlas.packers) - 1)
+            push!(target.debugPages, (kind, layer, x + layer*(pageSize + 4), y, Float32(pageSize)))
+        end
+        y += pageSize + 4
+    end
+    return target
+end
+
+function appendDebugPages!(target::TextTarget, vertices::Vector{BufferVertex}, indices::Vector{UInt32}, uniformBuffer)
+    isempty(target.debugPages) && return target
+    renderer = sharedRenderer(target)
+    color = packColor((1f0, 1f0, 1f0, 1f0))
+    for (kind, layer, x, y, size) in target.debugPages
+        atlas = atlasFor(renderer, kind)
+        flags = bytesPerTexel(atlas) == 1 && !(atlas isa SDFAtlas && atlas.msdf) ? UInt32(0) : colorBitmapFlag
+        first = length(indices)
+        base = UInt32(length(vertices))
+        for (dx, dy) in ((0, 0), (1, 0), (1, 1), (0, 1))
+            push!(vertices, BufferVertex(x + dx*size, y + dy*size, dx, dy, layer, 0, 0, flags, color))
+        end
+        append!(indices, base .+ UInt32[0, 1, 2, 2, 3, 0])
+        # not cached, the debug pipeline layout differs from the atlas kinds
+        bindGroup = WGPUCore.createBindGroup(
+            "atlas debug bind group", renderer.device,
+            pipelineVariant(renderer, target.options; kind=:debug).bindGroupLayout,
+            getAtlasBindings(atlas, uniformBuffer)
+        )
+        push!(target.atlasDraws, AtlasDraw(:debug, bindGroup, first, 6))
+    end
+    empty!(target.debugPages)
+    return target
+end
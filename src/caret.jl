@@ -0,0 +1,50 @@
+# Carets and hit testing over positioned glyphs.
+# Both work in the local space of each glyph transform, so carets lean with
+# synthesized obliques and turn with text on paths, and a point is matched
+# against the slanted glyph cell it visually falls into.
+
+# Ascent and descent of the line box of `pg` in pixels.
+glyphExtent(pg::PositionedGlyph) =
+    (pg.font.metrics.ascender*pg.size/pg.font.emSize, -pg.font.metrics.descender*pg.size/pg.font.emSize)
+
+glyphAdvance(pg::PositionedGlyph) = pg.glyph.advance*pg.size/pg.font.emSize
+
+"""
+    caretPosition(layout, cluster) -> ((x0, y0), (x1, y1))
+
+Caret before the glyph of `cluster` from the descent to the ascent of its
+line in layout space, after the last glyph for clusters past the end.
+"""
+function caretPosition(layout::TextLayout, cluster)
+    isempty(layout.glyphs) && return ((0f0, 0f0), (0f0, 0f0))
+    i = findfirst(pg -> pg.cluster >= cluster, layout.glyphs)
+    pg = layout.glyphs[something(i, lastindex(layout.glyphs))]
+    x = i === nothing ? glyphAdvance(pg) : 0f0
+    (ascent, descent) = glyphExtent(pg)
+    place(offset) = (pg.x, pg.y) .+ pg.transform*offset
+    return (place((x, descent)), place((x, -ascent)))
+end
+
+"""
+    hitTest(layout, text, (x, y)) -> cluster or nothing
+
+String index of the caret position closest to a point in layout space,
+`nextind(text, lastindex(text))` right of the last glyph and `nothing` when
+the point lies outside every line.
+"""
+function hitTest(layout::TextLayout, text::TextInput, point)
+    text = validText(text)
+    best = nothing
+    bestDistance = Inf32
+    for pg in layout.glyphs
+        (x, y) = inv(pg.transform)*(point .- (pg.x, pg.y))
+        (ascent, descent) = glyphExtent(pg)
+        -ascent <= y <= descent || continue
+        advance = glyphAdvance(pg)
+        distance = x < 0 ? -x : x > advance ? x - advance : 0f0
+        distance < bestDistance || continue
+        bestDistance = distance
+        best = x < advance/2 ? pg.cluster : nextind(text, pg.cluster)
+    end
+    return best
+end
@@ -0,0 +1,291 @@
+# Text rasterized by a compute pass straight into a storage texture, without
+# a render pass or surface. Useful in compute only and headless contexts, and
+# for filling glyph textures off the render loop: nothing waits on the
+# queue, and `encoder` lets the work go into a command buffer the caller
+# submits whenever it suits.
+#
+#     raster = ComputeRaster(renderer)
+#     target = RasterTarget(renderer.device, (256, 64))
+#     rasterize!(raster, target, layoutText("Hello", style; origin=(4, 40)))
+#     # target.view can be sampled now, or read back
+#     pixels = rasterizeText(raster, "Hello", style, (256, 64); origin=(4, 40))
+#
+# Glyphs are binned into 16x16 tiles and every tile composites its glyphs
+# in layout order. Fonts and curve chunks each take one dispatch that
+# composites over a copy of the target, so glyphs of different faces stack
+# in order of their first appearance. Pixels are rgba8 with premultiplied
+# alpha, like `renderToTexture`.
+
+const computeRasterShaderSource = embedShader("computeraster.wgsl"; appendedTo=fontShaderSource)
+
+const rasterTileSize = 16
+
+# Layout of `RasterUniforms` in computeraster.wgsl, padded to 16 bytes.
+struct RasterUniforms
+    clearColor::NTuple{4, Float32}
+    width::UInt32
+    height::UInt32
+    antiAliasingWindowSize::Float32
+    enableSuperSamplingAntiAliasing::UInt32
+    useBase::UInt32
+    padding::NTuple{3, UInt32}
+end
+
+# Layout of `RasterJob` in computeraster.wgsl.
+struct RasterJob
+    toEm::NTuple{4, Float32}
+    penX::Float32
+    penY::Float32
+    pixelsPerEm::Float32
+    bufferIndex::UInt32
+    color::NTuple{4, Float32}
+end
+
+Base.zero(::Type{RasterJob}) = RasterJob((0, 0, 0, 0), 0, 0, 0, 0, (0, 0, 0, 0))
+
+# Layout of `RasterTile` in computeraster.wgsl.
+struct RasterTile
+    x::UInt32
+    y::UInt32
+    start::UInt32
+    count::UInt32
+end
+
+mutable struct RasterTarget
+    texture
+    view
+    # what earlier dispatches wrote, read by later ones
+    scratch
+    scratchView
+    size::Tuple{Int, Int}
+end
+
+"""
+    RasterTarget(device, (width, height))
+
+Rgba8 storage texture for `rasterize!`, also usable as a texture binding.
+"""
+function RasterTarget(device, (width, height))
+    createTexture(label, usage) = WGPUCore.createTexture(
+        device, label,
+        (width, height, 1),
+        1, 1,
+        WGPUCore.WGPUTextureDimension_2D,
+        WGPUCore.WGPUTextureFormat_RGBA8Unorm,
+        WGPUCore.getEnum(WGPUCore.WGPUTextureUsage, usage)
+    )
+    texture = createTexture("raster target", ["StorageBinding", "TextureBinding", "CopySrc"])
+    scratch = createTexture("raster scratch", ["TextureBinding", "CopyDst"])
+    return RasterTarget(texture, WGPUCore.createView(texture), scratch, WGPUCore.createView(scratch), (width, height))
+end
+
+mutable struct ComputeRaster
+    renderer::TextRenderer
+    bindGroupLayout
+    pipeline
+    # bound when a frame has no glyphs and only clears
+    emptyGlyphs
+    emptyCurves
+end
+
+function getRasterBindingLayouts()
+    [
+        (WGPUCore.WGPUBufferEntry => [
+            :binding => i,
+            :visibility => ["Compute"],
+            :type => "ReadOnlyStorage"
+        ] for i in 1:2)...,
+        WGPUCore.WGPUBufferEntry => [
+            :binding => 3,
+            :visibility => ["Compute"],
+            :type => "Uniform"
+        ],
+        (WGPUCore.WGPUBufferEntry => [
+            :binding => i,
+            :visibility => ["Compute"],
+            :type => "ReadOnlyStorage"
+        ] for i in 4:6)...,
+        WGPUCore.WGPUTextureEntry => [
+            :binding => 7,
+            :visibility => ["Compute"],
+            :sampleType => "Float",
+            :viewDimension => "2D",
+            :multisampled => false
+        ],
+        WGPUCore.WGPUStorageTextureEntry => [
+            :binding => 8,
+            :visibility => ["Compute"],
+            :access => "WriteOnly",
+            :format => WGPUCore.WGPUTextureFormat_RGBA8Unorm,
+            :viewDimension => "2D"
+        ],
+    ]
+end
+
+"""
+    ComputeRaster(renderer)
+
+Compute pass rasterizing text of `renderer`'s fonts into `RasterTarget`s,
+see computeraster.jl.
+"""
+function ComputeRaster(renderer::TextRenderer)
+    device = renderer.device
+    shader = compileShader(device, "raster shader", getShaderCode() * computeRasterShaderSource)
+    bindGroupLayout = WGPUCore.createBindGroupLayout(device, "raster bind group layout", getRasterBindingLayouts())
+    pipelineLayout = WGPUCore.createPipelineLayout(device, "raster pipeline layout", bindGroupLayout)
+    pipeline = WGPUCore.createComputePipeline(device, "raster pipeline", pipelineLayout, shader, "cs_raster")
+    return ComputeRaster(
+        renderer, bindGroupLayout, pipeline,
+        createStorageBuffer(device, "raster empty glyphs", nonEmpty(BufferGlyph[])),
+        createStorageBuffer(device, "raster empty curves", nonEmpty(BufferCurve[]))
+    )
+end
+
+function rasterJob(pg::PositionedGlyph)
+    m = linearPart(pg.transform)
+    toEm = inv(m)
+    # pixel offsets are y down, em units y up
+    return RasterJob(
+        (toEm.a/pg.size, -toEm.b/pg.size, toEm.c/pg.size, -toEm.d/pg.size),
+        pg.x, pg.y,
+        pg.size*sqrt(abs(m.a*m.d - m.b*m.c)),
+        pg.glyph.bufferIndex,
+        pg.color
+    )
+end
+
+# Texel box (x0, y0, x1, y1) of the glyph metrics, one texel wider for anti-aliasing.
+function rasterBox(pg::PositionedGlyph)
+    glyph = pg.glyph
+    emSize = pg.font.emSize
+    (left, top) = (glyph.bearingX/emSize, glyph.bearingY/emSize)
+    (right, bottom) = (left + glyph.width/emSize, top - glyph.height/emSize)
+    corners = [(pg.x, pg.y) .+ pg.transform*(x*pg.size, -y*pg.size) for (x, y) in ((left, top), (right, top), (right, bottom), (left, bottom))]
+    return (
+        floor(Int, minimum(first, corners)) - 1, floor(Int, minimum(last, corners)) - 1,
+        ceil(Int, maximum(first, corners)) + 1, ceil(Int, maximum(last, corners)) + 1,
+    )
+end
+
+# Tiles of a dispatch and the jobs binned into each, every tile when `allTiles`.
+function binJobs(boxes, (width, height); allTiles=false)
+    (tilesX, tilesY) = cld.((width, height), rasterTileSize)
+    bins = [UInt32[] for _ in 1:tilesX*tilesY]
+    for (k, (x0, y0, x1, y1)) in enumerate(boxes)
+        (tx0, ty0) = fld.((max(x0, 0), max(y0, 0)), rasterTileSize)
+        (tx1, ty1) = fld.((min(x1, width - 1), min(y1, height - 1)), rasterTileSize)
+        for ty in ty0:ty1, tx in tx0:tx1
+            push!(bins[ty*tilesX + tx + 1], k - 1)
+        end
+    end
+    tiles = RasterTile[]
+    tileJobs = UInt32[]
+    for ty in 0:(tilesY - 1), tx in 0:(tilesX - 1)
+        bin = bins[ty*tilesX + tx + 1]
+        allTiles || !isempty(bin) || continue
+        push!(tiles, RasterTile(tx, ty, length(tileJobs), length(bin)))
+        append!(tileJobs, bin)
+    end
+    return (tiles, tileJobs)
+end
+
+function rasterBindGroup(raster::ComputeRaster, target::RasterTarget, glyphBuffer, curveBuffer, buffers)
+    WGPUCore.createBindGroup(
+        "raster bind group", raster.renderer.device,
+        raster.bindGroupLayout,
+        [
+            (WGPUCore.GPUBuffer => [
+                :binding => i,
+                :buffer  => buffer,
+                :offset  => 0,
+                :size    => buffer.size
+            ] for (i, buffer) in zip(1:6, (glyphBuffer, curveBuffer, buffers...)))...,
+            WGPUCore.GPUTextureView => [
+                :binding => 7,
+                :textureView => target.scratchView
+            ],
+            WGPUCore.GPUTextureView => [
+                :binding => 8,
+                :textureView => target.view
+            ],
+        ]
+    )
+end
+
+"""
+    rasterize!(raster, target, layout; clearColor=(0, 0, 0, 0), encoder=nothing)
+
+Fills `target` with `layout` drawn over the premultiplied `clearColor`. The
+work is submitted right away unless it is recorded into `encoder`.
+"""
+function rasterize!(
+        raster::ComputeRaster, target::RasterTarget, layout::TextLayout;
+        clearColor=(0f0, 0f0, 0f0, 0f0),
+        encoder=nothing
+    )
+    renderer = raster.renderer
+    device = renderer.device
+    options = renderer.options
+    (width, height) = target.size
+    groups = [
+        (fontBuffersFor(renderer, font), chunk, filter(pg -> pg.glyph.curveCount > 0, chunkLayout.glyphs))
+        for (font, fontLayout) in splitByFont(layout)
+        for (chunk, chunkLayout) in splitByChunk(fontLayout, fontBuffersFor(renderer, font))
+    ]
+    # an empty layout still clears the target
+    isempty(groups) && push!(groups, (nothing, 1, PositionedGlyph[]))
+    submit = encoder === nothing
+    submit && (encoder = WGPUCore.createCommandEncoder(device, "raster encoder"))
+    @span "raster" for (k, (fontBuffers, chunk, glyphs)) in enumerate(groups)
+        (tiles, tileJobs) = binJobs(rasterBox.(glyphs), target.size; allTiles=k == 1)
+        isempty(tiles) && continue
+        if k > 1
+            WGPUCore.copyTextureToTexture(
+                encoder,
+                [:texture => target.texture, :mipLevel => 0, :origin => ((0, 0, 0) .|> Float32)],
+                [:texture => target.scratch, :mipLevel => 0, :origin => ((0, 0, 0) .|> Float32)],
+                [:width => width, :height => height, :depthOrArrayLayers => 1]
+            )
+        end
+        uniforms = [RasterUniforms(
+            Float32.(clearColor), width, height,
+            options.antiAliasingWindowSize, options.enableSuperSamplingAntiAliasing,
+            k > 1, (0, 0, 0)
+        )]
+        (uniformBuffer, _) = WGPUCore.createBufferWithData(device, "raster uniform buffer", uniforms, ["Uniform", "CopyDst"])
+        buffers = (
+            uniformBuffer,
+            createStorageBuffer(device, "raster jobs", nonEmpty(rasterJob.(glyphs))),
+            createStorageBuffer(device, "raster tiles", tiles),
+            createStorageBuffer(device, "raster tile jobs", nonEmpty(tileJobs)),
+        )
+        bindGroup = fontBuffers === nothing ?
+            rasterBindGroup(raster, target, raster.emptyGlyphs, raster.emptyCurves, buffers) :
+            rasterBindGroup(raster, target, fontBuffers.glyphBuffer, fontBuffers.curveBuffers[chunk], buffers)
+        pass = WGPUCore.beginComputePass(encoder)
+        WGPUCore.setPipeline(pass, raster.pipeline)
+        WGPUCore.setBindGroup(pass, 0, bindGroup, UInt32[], 0, 99)
+        WGPUCore.dispatchWorkgroups(pass, length(tiles), 1, 1)
+        WGPUCore.endComputePass(pass)
+    end
+    submit && WGPUCore.submit(device.queue, [WGPUCore.finish(encoder),])
+    return target
+end
+
+"""
+    rasterizeText(raster, text, style, (width, height); origin=(0, 0), clearColor=(0, 0, 0, 0)) -> Vector{UInt8}
+
+Rasterizes `text` with the compute pass and reads it back, pixels are row
+major, top row first, with premultiplied alpha.
+"""
+function rasterizeText(
+        raster::ComputeRaster, text::TextInput, style::TextStyle, targetSize;
+        origin=(0f0, 0f0),
+        clearColor=(0f0, 0f0, 0f0, 0f0)
+    )
+    device = raster.renderer.device
+    target = RasterTarget(device, targetSize)
+    encoder = WGPUCore.createCommandEncoder(device, "raster readback encoder")
+    rasterize!(raster, target, layoutText(text, style; origin=Float32.(origin)); clearColor=clearColor, encoder=encoder)
+    return readTexture(device, encoder, target.texture, targetSize)
+end
@@ -0,0 +1,192 @@
+# Render options and text styles as TOML themes.
+#
+#     [render]
+#     format = "BGRA8UnormSrgb"
+#     sampleCount = 4
+#     antiAliasingMode = "antiAliasingAnisotropic"
+#
+#     [fonts]
+#     body = "fonts/JuliaMono-Light.ttf"
+#
+#     [styles.title]
+#     font = "body"
+#     size = 48
+#     color = [1.0, 0.8, 0.2, 1.0]
+#
+# Every key is optional and falls back to the struct defaults, font paths are
+# relative to the theme file and styles without a font use the bundled face.
+
+using TOML
+using FileWatching
+
+struct Theme
+    options::RenderOptions
+    fontPaths::Dict{String, String}
+    fonts::Dict{String, FontFace}
+    styles::Dict{String, TextStyle}
+end
+
+function enumValue(::Type{T}, name::AbstractString) where T <: Enum
+    for value in instances(T)
+        string(value) == name && return value
+    end
+    throw(ArgumentError("unknown $T value $name"))
+end
+
+textureFormat(name::AbstractString) = getfield(WGPUCore, Symbol("WGPUTextureFormat_", name))
+textureFormatName(format) = replace(string(format), "WGPUTextureFormat_" => "")
+
+depthConventionName(convention::DepthConvention) = convention === reverseDepth ? "reverse" : "standard"
+depthConvention(name::AbstractString) =
+    name == "reverse" ? reverseDepth :
+    name == "standard" ? standardDepth :
+    throw(ArgumentError("unknown depth convention $name"))
+
+function toDict(options::RenderOptions)
+    dict = Dict{String, Any}(
+        "format" => textureFormatName(options.format),
+        "sampleCount" => options.sampleCount,
+        "antiAliasingWindowSize" => options.antiAliasingWindowSize,
+        "enableSuperSamplingAntiAliasing" => options.enableSuperSamplingAntiAliasing,
+        "antiAliasingMode" => string(options.antiAliasingMode),
+        "colorSpace" => string(options.colorSpace),
+        "blendMode" => string(options.blendMode),
+        "framesInFlight" => options.framesInFlight,
+        "smallTextThreshold" => options.smallTextThreshold,
+        "msdfAtlas" => options.msdfAtlas,
+        "rasterTextThreshold" => options.rasterTextThreshold,
+        "atlasFormat" => string(options.atlasFormat),
+        "depthConvention" => depthConventionName(options.depthConvention),
+        "notdefHexBoxes" => options.notdefHexBoxes,
+        "debugView" => string(options.debugView),
+        "heatmapScale" => options.heatmapScale,
+        "tessellation" => string(options.tessellation),
+        "tessellationTolerance" => options.tessellationTolerance,
+        "batchByFont" => options.batchByFont,
+    )
+    options.depthFormat === nothing || (dict["depthFormat"] = textureFormatName(options.depthFormat))
+    options.fragmentHook === nothing || (dict["fragmentHook"] = options.fragmentHook)
+    return dict
+end
+
+function fromDict(::Type{RenderOptions}, dict::AbstractDict)
+    kwargs = Dict{Symbol, Any}()
+    for (key, value) in dict
+        kwargs[Symbol(key)] =
+            key in ("format", "depthFormat") ? textureFormat(value) :
+            key == "antiAliasingMode" ? enumValue(AntiAliasingMode, value) :
+            key == "colorSpace" ? enumValue(ColorSpace, value) :
+            key == "blendMode" ? enumValue(BlendMode, value) :
+            key == "atlasFormat" ? enumValue(AtlasFormat, value) :
+            key == "depthConvention" ? depthConvention(value) :
+            key == "debugView" ? enumValue(DebugView, value) :
+            key == "tessellation" ? enumValue(TessellationMode, value) :
+            value
+    end
+    return RenderOptions(; kwargs...)
+end
+
+function toDict(style::TextStyle, fontName::AbstractString)
+    dict = Dict{String, Any}(
+        "font" => fontName,
+        "size" => style.size,
+        "color" => collect(style.color),
+        "lineHeight" => style.lineHeight,
+        "tabularFigures" => style.tabularFigures,
+        "figureStyle" => string(style.figureStyle),
+        "slashedZero" => style.slashedZero,
+        "fractions" => style.fractions,
+        "fontVariantCaps" => string(style.fontVariantCaps),
+        "verticalPosition" => string(style.verticalPosition),
+        "stylisticSets" => stylisticSetNumbers(style.stylisticSets),
+        "contextualAlternates" => style.contextualAlternates,
+        "requiredLigatures" => style.requiredLigatures,
+        "autoOpticalSize" => style.autoOpticalSize,
+        "emboldening" => style.emboldening,
+        "oblique" => style.oblique,
+        "obliqueAngle" => style.obliqueAngle,
+        "textTransform" => string(style.textTransform),
+        "language" => style.language,
+        "controlCharacters" => string(style.controlCharacters),
+        "notdefHexBoxes" => style.notdefHexBoxes,
+        "lineBreak" => string(style.lineBreak),
+        "lineBreaker" => string(style.lineBreaker),
+        "writingMode" => string(style.writingMode),
+        "kashida" => style.kashida,
+        "justifyLastLine" => style.justifyLastLine,
+        "maxWordSpacing" => style.maxWordSpacing,
+        "maxLetterSpacing" => style.maxLetterSpacing,
+        "baselineGrid" => style.baselineGrid,
+        "baselineGridOffset" => style.baselineGridOffset,
+        "gridOverflow" => string(style.gridOverflow),
+        "monospaceAdvance" => style.monospaceAdvance,
+    )
+    style.opticalSize === nothing || (dict["opticalSize"] = style.opticalSize)
+    return dict
+end
+
+themeFont(fonts::AbstractDict, name::AbstractString) = get!(fonts, name) do
+    name == "default" || throw(ArgumentError("unknown font $name, declare it in the fonts table"))
+    loadFont()
+end
+
+function fromDict(::Type{TextStyle}, dict::AbstractDict, fonts::AbstractDict)
+    kwargs = Dict{Symbol, Any}()
+    for (key, value) in dict
+        kwargs[Symbol(key)] =
+            key == "font" ? themeFont(fonts, value) :
+            key == "color" ? NTuple{4, Float32}(value) :
+            key == "figureStyle" ? enumValue(FigureStyle, value) :
+            key == "fontVariantCaps" ? enumValue(FontVariantCaps, value) :
+            key == "verticalPosition" ? enumValue(VerticalPosition, value) :
+            key == "stylisticSets" ? stylisticSetMask(value...) :
+            key == "textTransform" ? enumValue(TextTransform, value) :
+            key == "controlCharacters" ? enumValue(ControlPolicy, value) :
+            key == "lineBreak" ? enumValue(LineBreakStrictness, value) :
+            key == "lineBreaker" ? enumValue(LineBreaker, value) :
+            key == "writingMode" ? enumValue(WritingMode, value) :
+            key == "gridOverflow" ? enumValue(GridOverflow, value) :
+            value
+    end
+    haskey(kwargs, :font) || (kwargs[:font] = themeFont(fonts, "default"))
+    return TextStyle(; kwargs...)
+end
+
+function loadTheme(path::AbstractString)
+    dict = try
+        TOML.parsefile(path)
+    catch err
+        throw(FontRenderError(ioError, "Could not read theme at $path : $err"))
+    end
+    root = dirname(abspath(path))
+    fontPaths = Dict{String, String}(name => file for (name, file) in get(dict, "fonts", Dict()))
+    fonts = Dict{String, FontFace}(name => loadFont(joinpath(root, file)) for (name, file) in fontPaths)
+    styles = Dict{String, TextStyle}(
+        name => fromDict(TextStyle, table, fonts) for (name, table) in get(dict, "styles", Dict())
+    )
+    options = fromDict(RenderOptions, get(dict, "render", Dict()))
+    return Theme(options, fontPaths, fonts, styles)
+end
+
+function saveTheme(path::AbstractString, theme::Theme)
+    fontNames = IdDict{FontFace, String}(font => name for (name, font) in theme.fonts)
+    dict = Dict{String, Any}(
+        "render" => toDict(theme.options),
+        "fonts" => theme.fontPaths,
+        "styles" => Dict(name => toDict(style, get(fontNames, style.font, "default")) for (name, style) in theme.styles),
+    )
+    open(io -> TOML.print(io, dict; sorted=true), path, "w")
+    return path
+end
+
+# Calls `f(theme)` every time the file changes, broken edits are logged and skipped.
+function watchTheme(f, path::AbstractString)
+    @async while true
+        watch_file(path)
+        try
+            f(loadTheme(path))
+        catch err
+            @warn "Could not reload theme" path exception=err
+        end
+    end
+end
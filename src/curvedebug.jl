@@ -0,0 +1,205 @@
+# Debug overlay of the curves behind rendered text, for winding and
+# conversion bugs with new fonts.
+# Every glyph of a layout gets its curves sampled as lines, colored by the
+# winding of their contour, an arrow halfway along each curve pointing in
+# its direction, the control polygon with on curve points in white and
+# control points in magenta, and the bounding box of its metrics. Draw it
+# after the text with the same projection and transform.
+#
+#     overlay = CurveDebug(renderer)
+#     debugLayout!(overlay, layout)
+#     prepare!(overlay, surface.size)
+#     draw!(overlay, renderPass)
+
+# Layout of `VertexInput` in curvedebug.wgsl.
+struct DebugVertex
+    x::Float32
+    y::Float32
+    color::UInt32       # rgba8, red in the lowest byte
+end
+
+function getVertexBufferLayout(::Type{DebugVertex}; offset=0)
+    WGPUCore.GPUVertexBufferLayout => [
+        :arrayStride => sizeof(DebugVertex),
+        :stepMode => "Vertex",
+        :attributes => [
+            :attribute => [
+                :format => "Float32x2",
+                :offset => fieldoffset(DebugVertex, 1),
+                :shaderLocation => offset + 0
+            ],
+            :attribute => [
+                :format => "Unorm8x4",
+                :offset => fieldoffset(DebugVertex, 3),
+                :shaderLocation => offset + 1
+            ],
+        ]
+    ]
+end
+
+# Layout of `DebugUniforms` in curvedebug.wgsl, padded to 16 bytes.
+struct DebugUniforms
+    projection::Mat4
+    transform::Mat4
+    linearizeColors::UInt32
+    padding::NTuple{3, UInt32}
+end
+
+const curveDebugShaderSource = embedShader("curvedebug.wgsl")
+
+const counterClockwiseColor = packColor((0.2f0, 0.9f0, 0.3f0, 1f0))
+const clockwiseColor = packColor((1f0, 0.55f0, 0.1f0, 1f0))
+const controlPolygonColor = packColor((0.6f0, 0.6f0, 0.6f0, 0.6f0))
+const onCurveColor = packColor((1f0, 1f0, 1f0, 1f0))
+const controlPointColor = packColor((1f0, 0.2f0, 0.9f0, 1f0))
+const glyphBoxColor = packColor((0.3f0, 0.5f0, 1f0, 0.8f0))
+
+mutable struct CurveDebug
+    renderer::TextRenderer
+    pipeline::FontPipeline
+    lineWidth::Float32          # pixels
+    pointSize::Float32
+    # line segments per curve
+    samples::Int
+    vertices::Vector{DebugVertex}
+    vertexBuffer
+    vertexCapacity::Int
+    vertexCount::Int
+    uniformBuffer
+    bindGroup
+end
+
+"""
+    CurveDebug(renderer; lineWidth=1, pointSize=4, samples=8)
+
+Overlay drawing into the targets of `renderer`.
+"""
+function CurveDebug(renderer::TextRenderer; lineWidth=1, pointSize=4, samples=8)
+    pipeline = rendererPipeline(
+        renderer;
+        blendMode=blendPremultiplied,
+        label="curve debug",
+        shaderSource=curveDebugShaderSource,
+        bindingLayouts=getBindingLayouts(FontFace)[1:1],
+        vertexBuffers=[getVertexBufferLayout(DebugVertex)]
+    )
+    return CurveDebug(renderer, pipeline, lineWidth, pointSize, samples, DebugVertex[], nothing, 0, 0, nothing, nothing)
+end
+
+function pushTriangle!(vertices, a, b, c, color)
+    push!(vertices, DebugVertex(a..., color), DebugVertex(b..., color), DebugVertex(c..., color))
+end
+
+function pushLine!(vertices, a, b, width, color)
+    (dx, dy) = unitVector(b .- a)
+    n = (-dy, dx).*(width/2)
+    pushTriangle!(vertices, a .+ n, b .+ n, b .- n, color)
+    pushTriangle!(vertices, b .- n, a .- n, a .+ n, color)
+end
+
+function pushPoint!(vertices, p, size, color)
+    h = size/2
+    pushTriangle!(vertices, p .+ (-h, -h), p .+ (h, -h), p .+ (h, h), color)
+    pushTriangle!(vertices, p .+ (h, h), p .+ (-h, h), p .+ (-h, -h), color)
+end
+
+function pushBox!(vertices, corners, width, color)
+    for k in 1:4
+        pushLine!(vertices, corners[k], corners[mod1(k + 1, 4)], width, color)
+    end
+end
+
+bezier(p0, p1, p2, t) = (1 - t)^2 .* p0 .+ 2*(1 - t)*t .* p1 .+ t^2 .* p2
+
+"""
+    debugLayout!(overlay, layout; curves=true, directions=true, controlPoints=true, boxes=true)
+
+Adds the debug geometry of the glyphs of `layout` for the next frame.
+"""
+function debugLayout!(overlay::CurveDebug, layout::TextLayout; curves=true, directions=true, controlPoints=true, boxes=true)
+    vertices = overlay.vertices
+    (width, size) = (overlay.lineWidth, overlay.pointSize)
+    for pg in layout.glyphs
+        # em units with y up into layout pixels
+        place(p) = (pg.x, pg.y) .+ pg.transform*(p[1]*pg.size, -p[2]*pg.size)
+        if boxes
+            s = pg.size/pg.font.emSize
+            g = pg.glyph
+            (x0, y0, x1, y1) = (g.bearingX*s, -g.bearingY*s, (g.bearingX + g.width)*s, (g.height - g.bearingY)*s)
+            pushBox!(vertices, [(pg.x, pg.y) .+ pg.transform*c for c in ((x0, y0), (x1, y0), (x1, y1), (x0, y1))], width, glyphBoxColor)
+        end
+        pg.glyph.curveCount == 0 && continue
+        glyphCurveList = collect(glyphCurves(pg.font, pg.glyph))
+        for range in contourRanges(glyphCurveList)
+            contour = glyphCurveList[range]
+            area = sum(c -> (c.x0*c.y1 - c.x1*c.y0) + (c.x1*c.y2 - c.x2*c.y1), contour; init=0f0)
+            color = area >= 0 ? counterClockwiseColor : clockwiseColor
+            for c in contour
+                (p0, p1, p2) = place.(((c.x0, c.y0), (c.x1, c.y1), (c.x2, c.y2)))
+                if controlPoints
+                    pushLine!(vertices, p0, p1, width, controlPolygonColor)
+                    pushLine!(vertices, p1, p2, width, controlPolygonColor)
+                end
+                if curves
+                    for k in 1:overlay.samples
+                        (t0, t1) = ((k - 1)/overlay.samples, k/overlay.samples)
+                        pushLine!(vertices, bezier(p0, p1, p2, t0), bezier(p0, p1, p2, t1), width, color)
+                    end
+                end
+                if directions
+                    tangent = unitVector((p1 .- p0) .+ (p2 .- p1))
+                    if tangent != (0f0, 0f0)
+                        m = bezier(p0, p1, p2, 0.5f0)
+                        n = (-tangent[2], tangent[1])
+                        a = 1.5f0*size
+                        pushTriangle!(vertices, m .+ tangent.*a, m .- tangent.*(a/2) .+ n.*(a/2), m .- tangent.*(a/2) .- n.*(a/2), color)
+                    end
+                end
+                if controlPoints
+                    pushPoint!(vertices, p0, size, onCurveColor)
+                    pushPoint!(vertices, p1, size*0.75f0, controlPointColor)
+                end
+            end
+        end
+    end
+    return overlay
+end
+
+Base.empty!(overlay::CurveDebug) = (empty!(overlay.vertices); overlay)
+
+function prepare!(overlay::CurveDebug, projection::Projection; transform=identityMat4)
+    renderer = overlay.renderer
+    device = renderer.device
+    overlay.vertexCount = length(overlay.vertices)
+    isempty(overlay.vertices) || @span "upload" begin
+        (overlay.vertexBuffer, overlay.vertexCapacity) = writeDynamic(
+            device, overlay.vertexBuffer, overlay.vertexCapacity, overlay.vertices, "curve debug vertex buffer", ["Vertex", "CopyDst"]
+        )
+    end
+    empty!(overlay.vertices)
+    uniforms = [DebugUniforms(projection.matrix, transform, linearizeColors(renderer.options), (0, 0, 0))]
+    if overlay.uniformBuffer === nothing
+        (overlay.uniformBuffer, _) = WGPUCore.createBufferWithData(device, "curve debug uniform buffer", uniforms, ["Uniform", "CopyDst"])
+        overlay.bindGroup = WGPUCore.createBindGroup(
+            "curve debug bind group", device,
+            overlay.pipeline.bindGroupLayout,
+            [WGPUCore.GPUBuffer => [:binding => 0, :buffer => overlay.uniformBuffer, :offset => 0, :size => overlay.uniformBuffer.size]]
+        )
+    else
+        WGPUCore.writeBuffer(device.queue, overlay.uniformBuffer, uniforms)
+    end
+    return overlay
+end
+
+prepare!(overlay::CurveDebug, targetSize::Tuple; kwargs...) = prepare!(overlay, orthographic(targetSize...); kwargs...)
+
+function draw!(overlay::CurveDebug, renderPass)
+    overlay.vertexCount == 0 && return overlay
+    @span "encode" withDebugGroup(renderPass, "curve debug") do
+        WGPUCore.setPipeline(renderPass, overlay.pipeline.pipeline)
+        WGPUCore.setVertexBuffer(renderPass, 0, overlay.vertexBuffer)
+        WGPUCore.setBindGroup(renderPass, 0, overlay.bindGroup, UInt32[], 0, 99)
+        WGPUCore.draw(renderPass, overlay.vertexCount; instanceCount=1, firstVertex=0, firstInstance=0)
+    end
+    return overlay
+end
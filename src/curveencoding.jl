@@ -0,0 +1,263 @@
+# How curve buffers are stored on a device. Every shader reads curves
+# through `loadCurve`, whose storage `compileShader` swaps for the device's
+# encoding, and font buffers are converted on upload, so everything sharing
+# a device agrees.
+#
+# `curveFloat32` keeps the 24 byte em unit curves of font.jl as they are.
+#
+# `curveFloat16` stores them in 12 bytes for devices with the shader-f16
+# feature, the default when `requestRenderDevice` could ask for it with
+# `AdapterOptions.halfPrecisionCurves` set. Paragraphs of small text are
+# bound by reading curves, at half the bytes every fragment fetches half the
+# data. Coordinates are em units, so f16 keeps them within 1/2048 em below
+# one em, about a font unit of common outlines. The coverage math stays in
+# f32 after loading, the offsets to the sample and the ray roots lose whole
+# pixels at display sizes in f16.
+#
+# `curveUnorm16` stores points as 16 bit fractions of the glyph's bounding
+# box, also 12 bytes per curve and without a device feature. Each glyph
+# leads with its box, four f32 words, followed by three words per curve:
+#
+#     origin.x  origin.y  extent.x  extent.y | p0 p1 p2 | p0 p1 p2 | ...
+#
+# and glyph ranges count words instead of curves. A point is off by at most
+# half of 1/65535 of the box, below a thousandth of a pixel for glyphs under
+# a hundred pixels. Set it with `AdapterOptions.quantizedCurves` or
+# `setCurveEncoding!` before uploading fonts or building renderers.
+#
+# Float encodings can also be deduplicated. Composites and CJK radicals
+# repeat the same segments across glyphs, uploads hash the curves of every
+# chunk and store each distinct one once. Chunks are then `u32` words, the
+# index lists of their glyphs followed by the distinct curves:
+#
+#     glyph 0: k0 k1 ... | glyph 1: k0 ... | curve at k0 | curve at k1 | ...
+#
+# where glyph ranges point into the index lists and indices are word offsets
+# of curves, six f32 or three f16 pairs each, the latter read without the
+# shader-f16 feature. Fonts without repeated curves grow by a word per
+# curve, deduplicate for large fonts with composites:
+#
+#     setCurveEncoding!(device, curveFloat16; deduplicate=true)
+
+@enum CurveEncoding curveFloat32 curveFloat16 curveUnorm16
+
+struct HalfCurve
+    x0::Float16
+    y0::Float16
+    x1::Float16
+    y1::Float16
+    x2::Float16
+    y2::Float16
+end
+
+HalfCurve(c::BufferCurve) = HalfCurve(c.x0, c.y0, c.x1, c.y1, c.x2, c.y2)
+
+Base.zero(::Type{HalfCurve}) = HalfCurve(0, 0, 0, 0, 0, 0)
+
+const shaderF16 = WGPUCore.WGPUFeatureName_ShaderF16
+
+hasFeature(object, feature) = hasproperty(object, :features) && feature in object.features
+
+hasShaderF16(device) = hasFeature(device, shaderF16)
+
+const curveEncodings = WeakKeyDict{Any, CurveEncoding}()
+const deduplicatedDevices = WeakKeyDict{Any, Bool}()
+
+"""
+    setCurveEncoding!(device, encoding::CurveEncoding; deduplicate=false)
+
+Stores curves on `device` as `curveFloat32`, `curveFloat16` or
+`curveUnorm16`, shared between glyphs with `deduplicate`, see
+curveencoding.jl. Affects fonts uploaded and shaders compiled afterwards.
+"""
+function setCurveEncoding!(device, encoding::CurveEncoding; deduplicate=false)
+    encoding == curveFloat16 && !deduplicate && !hasShaderF16(device) && throw(FontRenderError(
+        adapterError, "Half precision curves need a device with shader-f16"
+    ))
+    deduplicate && encoding == curveUnorm16 && throw(FontRenderError(
+        adapterError, "Quantized curves are relative to their glyph and can not be deduplicated"
+    ))
+    curveEncodings[device] = encoding
+    deduplicatedDevices[device] = deduplicate
+end
+
+curveEncoding(device) = get(curveEncodings, device) do
+    hasShaderF16(device) ? curveFloat16 : curveFloat32
+end
+
+deduplicatesCurves(device) = get(deduplicatedDevices, device, false)
+
+const curveStorage = "var<storage, read> curves: array<Curve>;"
+const curveLoad = "    return curves[glyph.start + i];"
+
+const halfCurveStruct = """
+struct HalfCurve {
+    p0: vec2<f16>,
+    p1: vec2<f16>,
+    p2: vec2<f16>,
+};
+
+"""
+
+const halfCurveLoad = """
+    let curve = curves[glyph.start + i];
+    return Curve(vec2<f32>(curve.p0), vec2<f32>(curve.p1), vec2<f32>(curve.p2));"""
+
+const quantizedCurveLoad = """
+    let origin = bitcast<vec2<f32>>(vec2<u32>(curves[glyph.start], curves[glyph.start + 1u]));
+    let extent = bitcast<vec2<f32>>(vec2<u32>(curves[glyph.start + 2u], curves[glyph.start + 3u]));
+    let k = glyph.start + 4u + 3u*i;
+    return Curve(
+        origin + unpack2x16unorm(curves[k])*extent,
+        origin + unpack2x16unorm(curves[k + 1u])*extent,
+        origin + unpack2x16unorm(curves[k + 2u])*extent,
+    );"""
+
+const sharedCurveLoad = """
+    let k = curves[glyph.start + i];
+    return Curve(
+        bitcast<vec2<f32>>(vec2<u32>(curves[k], curves[k + 1u])),
+        bitcast<vec2<f32>>(vec2<u32>(curves[k + 2u], curves[k + 3u])),
+        bitcast<vec2<f32>>(vec2<u32>(curves[k + 4u], curves[k + 5u])),
+    );"""
+
+# unpack2x16float reads f16 pairs without the shader-f16 feature
+const sharedHalfCurveLoad = """
+    let k = curves[glyph.start + i];
+    return Curve(unpack2x16float(curves[k]), unpack2x16float(curves[k + 1u]), unpack2x16float(curves[k + 2u]));"""
+
+function curveSource(device, source::String)
+    occursin("fn loadCurve(", source) || return source
+    encoding = curveEncoding(device)
+    if deduplicatesCurves(device)
+        load = encoding == curveFloat16 ? sharedHalfCurveLoad : sharedCurveLoad
+        return replace(source, curveStorage => "var<storage, read> curves: array<u32>;", curveLoad => load)
+    end
+    if encoding == curveFloat16
+        source = replace(source, curveStorage => "var<storage, read> curves: array<HalfCurve>;", curveLoad => halfCurveLoad)
+        # directives precede every declaration
+        return "enable f16;\n\n" * halfCurveStruct * source
+    elseif encoding == curveUnorm16
+        return replace(source, curveStorage => "var<storage, read> curves: array<u32>;", curveLoad => quantizedCurveLoad)
+    end
+    return source
+end
+
+packUnorm16(x, y) = UInt32(round(UInt16, clamp(x, 0, 1)*typemax(UInt16))) | UInt32(round(UInt16, clamp(y, 0, 1)*typemax(UInt16))) << 16
+
+# Origin and extent of the control points of `curves`, which contain them.
+function curveBox(curves)
+    points = [p for c in curves for p in ((c.x0, c.y0), (c.x1, c.y1), (c.x2, c.y2))]
+    isempty(points) && return ((0f0, 0f0), (0f0, 0f0))
+    origin = (minimum(first, points), minimum(last, points))
+    return (origin, (maximum(first, points), maximum(last, points)) .- origin)
+end
+
+# Box header and packed points of one glyph's curves, see the layout above.
+function appendQuantized!(words::Vector{UInt32}, curves)
+    (origin, extent) = curveBox(curves)
+    append!(words, reinterpret.(UInt32, Float32[origin..., extent...]))
+    # a flat box keeps its points at the origin
+    scale = map(e -> e > 0 ? 1/e : 0f0, extent)
+    for c in curves, p in ((c.x0, c.y0), (c.x1, c.y1), (c.x2, c.y2))
+        push!(words, packUnorm16((p .- origin) .* scale...))
+    end
+    return words
+end
+
+function quantizedCurves(font::FontFace)
+    words = UInt32[]
+    wordGlyphs = BufferGlyph[]
+    for glyph in font.bufferGlyphs
+        push!(wordGlyphs, BufferGlyph(length(words), 4 + 3*glyph.count))
+        appendQuantized!(words, view(font.bufferCurves, (glyph.start + 1):(glyph.start + glyph.count)))
+    end
+    return (wordGlyphs, words)
+end
+
+curveWords(c::BufferCurve) = reinterpret.(UInt32, (c.x0, c.y0, c.x1, c.y1, c.x2, c.y2))
+halfCurveWords(c::BufferCurve) = map(((x, y),) -> UInt32(reinterpret(UInt16, Float16(x))) | UInt32(reinterpret(UInt16, Float16(y))) << 16, ((c.x0, c.y0), (c.x1, c.y1), (c.x2, c.y2)))
+
+# Index lists and distinct curves per chunk, a chunk closes when the next
+# glyph's indices and new curves would not fit.
+function sharedCurves(font::FontFace, maxWords, half::Bool)
+    stride = half ? 3 : 6
+    glyphs = BufferGlyph[]
+    glyphChunks = Int32[]
+    chunks = Vector{UInt32}[]
+    indices = UInt32[]
+    distinct = Dict{BufferCurve, Int}()
+    function closeChunk!()
+        curves = Vector{BufferCurve}(undef, length(distinct))
+        for (c, k) in distinct
+            curves[k + 1] = c
+        end
+        # curve indices become word offsets past the index lists
+        words = UInt32[length(indices) + stride*k for k in indices]
+        for c in curves
+            append!(words, half ? halfCurveWords(c) : curveWords(c))
+        end
+        push!(chunks, words)
+        empty!(indices)
+        empty!(distinct)
+    end
+    for glyph in font.bufferGlyphs
+        curves = view(font.bufferCurves, (glyph.start + 1):(glyph.start + glyph.count))
+        added = length(setdiff(Set(curves), keys(distinct)))
+        words = length(indices) + glyph.count + stride*(length(distinct) + added)
+        words > maxWords && !isempty(indices) && closeChunk!()
+        push!(glyphs, BufferGlyph(length(indices), glyph.count))
+        push!(glyphChunks, length(chunks) + 1)
+        for c in curves
+            push!(indices, get!(distinct, c, length(distinct)))
+        end
+    end
+    closeChunk!()
+    return (glyphs, glyphChunks, chunks)
+end
+
+# Glyph ranges, the chunk of every glyph and the chunk contents of `font` on `device`.
+function encodeCurves(device, font::FontFace, maxBindingSize)
+    encoding = curveEncoding(device)
+    deduplicatesCurves(device) && return sharedCurves(font, maxBindingSize ÷ sizeof(UInt32), encoding == curveFloat16)
+    if encoding == curveUnorm16
+        (wordGlyphs, words) = quantizedCurves(font)
+        (glyphs, glyphChunks, ranges) = chunkGlyphs(wordGlyphs, length(words), maxBindingSize ÷ sizeof(UInt32))
+        # loops run over curves, ranges start at the header
+        glyphs = [BufferGlyph(g.start, bg.count) for (g, bg) in zip(glyphs, font.bufferGlyphs)]
+        return (glyphs, glyphChunks, [words[range .+ 1] for range in ranges])
+    end
+    half = encoding == curveFloat16
+    maxCurves = maxBindingSize ÷ sizeof(half ? HalfCurve : BufferCurve)
+    (glyphs, glyphChunks, ranges) = chunkGlyphs(font.bufferGlyphs, length(font.bufferCurves), maxCurves)
+    chunks = [font.bufferCurves[range .+ 1] for range in ranges]
+    return (glyphs, glyphChunks, half ? [HalfCurve.(chunk) for chunk in chunks] : chunks)
+end
+
+# Glyphs and curves of several fonts in one binding each, glyph ranges and
+# shared curve indices rebased past the fonts before. Returns the first
+# merged glyph of every font too.
+function mergeCurves(device, fonts, maxBindingSize=maxStorageBufferBindingSize(device))
+    glyphs = BufferGlyph[]
+    firstGlyphs = UInt32[]
+    merged = nothing
+    for font in fonts
+        (fontGlyphs, _, chunks) = encodeCurves(device, font, maxBindingSize)
+        length(chunks) == 1 || throw(FontRenderError(
+            deviceLimitError, "$(fontLabel(font)) needs $(length(chunks)) curve bindings, merged fonts share one"
+        ))
+        data = chunks[1]
+        offset = merged === nothing ? 0 : length(merged)
+        if deduplicatesCurves(device)
+            indexCount = sum(g -> Int(g.count), fontGlyphs; init=0)
+            data[1:indexCount] .+= offset
+        end
+        push!(firstGlyphs, length(glyphs))
+        append!(glyphs, (BufferGlyph(g.start + offset, g.count) for g in fontGlyphs))
+        merged = merged === nothing ? data : append!(merged, data)
+    end
+    sizeof(something(merged, UInt32[])) > maxBindingSize && throw(FontRenderError(
+        deviceLimitError, "Curves of $(length(fonts)) fonts exceed one storage binding of $maxBindingSize bytes"
+    ))
+    return (glyphs, firstGlyphs, something(merged, BufferCurve[]))
+end
@@ -0,0 +1,18 @@
+# Every fallible api throws a `FontRenderError`, hosts can catch it and
+# inspect `kind` to degrade gracefully instead of crashing.
+
+@enum FontRenderErrorKind begin
+    ioError
+    fontParseError
+    deviceLimitError
+    shaderCompileError
+    glyphMissingError
+    adapterError
+end
+
+struct FontRenderError <: Exception
+    kind::FontRenderErrorKind
+    msg::String
+end
+
+Base.showerror(io::IO, err::FontRenderError) = print(io, "FontRenderError ($(err.kind)) : ", err.msg)
@@ -0,0 +1,93 @@
+# Image and vector exporters built on top of the layout and headless paths.
+
+using PNGFiles
+using ColorTypes
+using FixedPointNumbers
+
+# The renderer blends premultiplied colors, image files expect straight alpha.
+function unpremultiply(pixels::Vector{UInt8})
+    straight = similar(pixels)
+    for i in 1:4:length(pixels)
+        a = pixels[i + 3]
+        for c in 0:2
+            straight[i + c] = a == 0 ? 0x00 : UInt8(min(255, round(Int, pixels[i + c]*255/a)))
+        end
+        straight[i + 3] = a
+    end
+    return straight
+end
+
+function toImage(pixels::Vector{UInt8}, (width, height))
+    rgba = reinterpret(RGBA{N0f8}, pixels)
+    return permutedims(reshape(rgba, width, height))
+end
+
+function fitTargetSize(layout::TextLayout, padding)
+    (ceil(Int, layout.width + 2*padding), ceil(Int, layout.height + 2*padding))
+end
+
+"""
+    exportPNG(path, text, style; targetSize=nothing, padding=4)
+
+Renders `text` headlessly and saves it as a straight alpha png.
+The image is sized to fit the laid out text unless `targetSize` is given.
+"""
+function exportPNG(path::AbstractString, text::AbstractString, style::TextStyle; targetSize=nothing, padding=4, kwargs...)
+    if targetSize === nothing
+        targetSize = fitTargetSize(layoutText(text, style), padding)
+    end
+    pixels = renderToTexture(text, style, targetSize; origin=(Float32(padding), Float32(padding)), kwargs...)
+    PNGFiles.save(path, toImage(unpremultiply(pixels), targetSize))
+    return path
+end
+
+svgNumber(x) = string(round(x; digits=3))
+
+# Curves of a contour are stored back to back, so a new subpath only
+# starts when a curve does not continue from the previous end point.
+function svgPathData(pg::PositionedGlyph)
+    toPixel(x, y) = (pg.x, pg.y) .+ pg.transform*(x*pg.size, -y*pg.size)
+    io = IOBuffer()
+    previous = nothing
+    for curve in glyphCurves(pg.font, pg.glyph)
+        p0 = toPixel(curve.x0, curve.y0)
+        p1 = toPixel(curve.x1, curve.y1)
+        p2 = toPixel(curve.x2, curve.y2)
+        if previous === nothing || !all(isapprox.(previous, p0; atol=1e-4))
+            previous === nothing || print(io, "Z ")
+            print(io, "M", svgNumber(p0[1]), " ", svgNumber(p0[2]), " ")
+        end
+        print(io, "Q", join(svgNumber.((p1..., p2...)), " "), " ")
+        previous = p2
+    end
+    previous === nothing || print(io, "Z")
+    return String(take!(io))
+end
+
+svgColor((r, g, b, a)) =
+    ("rgb($(round(Int, 255r)),$(round(Int, 255g)),$(round(Int, 255b)))", svgNumber(a))
+
+function writeSVG(io::IO, layout::TextLayout; padding=0)
+    width = layout.width + 2*padding
+    height = layout.height + 2*padding
+    println(io, """<svg xmlns="http://www.w3.org/2000/svg" width="$(svgNumber(width))" height="$(svgNumber(height))" viewBox="$(-padding) $(-padding) $(svgNumber(width)) $(svgNumber(height))">""")
+    for pg in layout.glyphs
+        pg.glyph.curveCount == 0 && continue
+        (fill, opacity) = svgColor(pg.color)
+        println(io, """  <path fill="$fill" fill-opacity="$opacity" fill-rule="nonzero" d="$(svgPathData(pg))"/>""")
+    end
+    println(io, "</svg>")
+end
+
+"""
+    exportSVG(path, text, style; padding=4)
+
+Writes the outlines of the laid out text as svg paths, one per glyph.
+"""
+function exportSVG(path::AbstractString, text::AbstractString, style::TextStyle; padding=4)
+    layout = layoutText(text, style)
+    open(path, "w") do io
+        writeSVG(io, layout; padding=padding)
+    end
+    return path
+end
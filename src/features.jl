@@ -0,0 +1,39 @@
+# Optional subsystems, toggled through Preferences.jl and read at precompile time
+#
+#     using Preferences, WGPUFontRenderer
+#     set_preferences!(WGPUFontRenderer, "shaping" => false)
+#
+# Embedders that only draw fixed ascii text switch off the shaping and layout
+# passes, the package has to be reloaded for a change to take effect. Only the
+# runtime paths are switched: the line break, shaping and vertical form tables
+# are part of the package and load either way.
+
+using Preferences
+
+# kerning and opentype substitutions, plain cmap lookup when disabled
+const enableShaping = @load_preference("shaping", true)
+# line breaking and justification, only explicit newlines when disabled
+const enableLayout = @load_preference("layout", true)
+# color glyph layers
+const enableColorFonts = @load_preference("colorFonts", true)
+# cached coverage textures for tiny or static text
+const enableAtlas = @load_preference("atlas", true)
+# shape and lay out independent sections on all threads
+const enableParallel = @load_preference("parallel", false)
+# watch shaders/*.wgsl and rebuild pipelines on change, for shader development
+const enableHotReload = @load_preference("devHotReload", false)
+
+features() = (
+    shaping=enableShaping,
+    layout=enableLayout,
+    colorFonts=enableColorFonts,
+    atlas=enableAtlas,
+    parallel=enableParallel,
+    devHotReload=enableHotReload,
+)
+
+function setFeature!(name::AbstractString, enabled::Bool)
+    name in ("shaping", "layout", "colorFonts", "atlas", "parallel", "devHotReload") || throw(ArgumentError("unknown feature $name"))
+    @set_preferences!(name => enabled)
+    @info "Feature $name set to $enabled, restart Julia for it to take effect"
+end
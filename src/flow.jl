@@ -0,0 +1,261 @@
+# Text flowed through a sequence of fixed size containers, e.g. pages,
+# columns or boxes of a magazine layout. Lines fill a container until the
+# next one would cross its bottom and continue in the next container,
+# rewrapped to its width. Paragraphs keep at least `orphans` lines before
+# and `widows` lines after such a split, css style; lines that cannot stay
+# together move on to the next container. Exclusions, figures, drop caps or
+# sidebars, shorten the lines they reach to the widest gap they leave.
+
+# Polygon text flows around, in layout space, kept `margin` pixels away.
+struct Exclusion
+    points::Vector{NTuple{2, Float32}}
+    margin::Float32
+end
+
+Exclusion(points; margin=0) = Exclusion(points, margin)
+Exclusion(x, y, width, height; margin=0) =
+    Exclusion([(x, y), (x + width, y), (x + width, y + height), (x, y + height)], margin)
+
+struct TextContainer
+    origin::NTuple{2, Float32}
+    width::Float32
+    height::Float32
+    exclusions::Vector{Exclusion}
+end
+
+TextContainer(origin, width, height) = TextContainer(origin, width, height, Exclusion[])
+TextContainer(width, height) = TextContainer((0f0, 0f0), width, height)
+
+# Horizontal extent of `exclusion` within the band from `top` to `bottom`.
+function blockedSpan(exclusion::Exclusion, top, bottom)
+    (top, bottom) = (top - exclusion.margin, bottom + exclusion.margin)
+    (left, right) = (Inf32, -Inf32)
+    points = exclusion.points
+    for (k, (x0, y0)) in enumerate(points)
+        (x1, y1) = points[mod1(k + 1, length(points))]
+        # the edge clipped to the band
+        (t0, t1) = y0 == y1 ? (y0 >= top && y0 <= bottom ? (0f0, 1f0) : (1f0, 0f0)) :
+            extrema(((top - y0)/(y1 - y0), (bottom - y0)/(y1 - y0)))
+        (t0, t1) = (max(t0, 0f0), min(t1, 1f0))
+        t0 <= t1 || continue
+        for t in (t0, t1)
+            x = x0 + t*(x1 - x0)
+            (left, right) = (min(left, x), max(right, x))
+        end
+    end
+    return left <= right ? (left - exclusion.margin, right + exclusion.margin) : nothing
+end
+
+# Widest gap the exclusions leave for the line band at `top`, as (left,
+# width) in layout space, `nothing` if it is narrower than `minimum`.
+function freeSpan(container::TextContainer, top, height, minimum)
+    (x0, x1) = (container.origin[1], container.origin[1] + container.width)
+    isempty(container.exclusions) && return (x0, container.width)
+    blocked = sort!(filter(!isnothing, [blockedSpan(e, top, top + height) for e in container.exclusions]))
+    (best, bestWidth) = (x0, 0f0)
+    left = x0
+    for (a, b) in [blocked; (x1, x1)]
+        gap = min(a, x1) - left
+        gap > bestWidth && ((best, bestWidth) = (left, gap))
+        left = max(left, b)
+    end
+    return bestWidth >= minimum ? (best, bestWidth) : nothing
+end
+
+# Box of one placed line in layout space, `glyphs` indexes the glyphs of its
+# container's layout.
+struct LineBox
+    x::Float32
+    y::Float32      # top of the line
+    width::Float32
+    height::Float32
+    glyphs::UnitRange{Int}
+end
+
+# Lines of `total` placed in a container with room for `available`, the
+# paragraph started in this container if `starting`.
+function keptLines(total, available, starting, emptyContainer; orphans, widows)
+    total <= available && return total
+    take = available
+    total - take < widows && (take = total - widows)
+    starting && take < orphans && (take = 0)
+    # a container too small to keep anything together still takes what fits
+    take <= 0 && emptyContainer && return available
+    return max(take, 0)
+end
+
+"""
+    flowText(text, style, containers; align=alignLeft, orphans=2, widows=2) -> (layouts, overflow, lineBoxes)
+
+One layout per container, in layout space like `layoutText` with each
+container's `origin`. `overflow` is the string index where text stopped
+fitting into the last container, `nothing` if all of it was placed.
+`lineBoxes` holds the `LineBox`es of every container.
+"""
+function flowText(
+    text::TextInput, style::TextStyle, containers::AbstractVector{TextContainer};
+    align::TextAlign=alignLeft, orphans=2, widows=2
+)
+    text = validText(text)
+    isempty(containers) && return (TextLayout[], firstindex(text), Vector{LineBox}[])
+    style = resolvedStyle(style)
+    font = style.font
+    scale = pixelScale(style)
+    lineAdvance = font.metrics.height*scale*style.lineHeight
+    capacity(c) = isfinite(containers[c].height) ? floor(Int, containers[c].height/lineAdvance) : typemax(Int)
+    placed = [PositionedGlyph[] for _ in containers]
+    boxes = [LineBox[] for _ in containers]
+    widths = zeros(Float32, length(containers))
+    used = zeros(Int, length(containers))
+    results() = [TextLayout(placed[k], widths[k], used[k]*lineAdvance) for k in eachindex(containers)]
+
+    wrap(glyphs, paragraph, width) = enableLayout ?
+        wrapLines(glyphs, style, paragraph, width; clusterOffset=paragraph.offset) : [(glyphs, false)]
+    # Lines of `glyphs` in the free slots of container `c` from its line
+    # `used[c]` on, `nothing` for slots an exclusion leaves too narrow.
+    # Every line comes with the glyphs from its start on; the glyphs left
+    # over are returned as well, `nothing` when the paragraph ended.
+    function planLines(glyphs, paragraph, c)
+        container = containers[c]
+        plan = []
+        for slot in used[c]:(capacity(c) - 1)
+            span = freeSpan(container, container.origin[2] + slot*lineAdvance, lineAdvance, style.size)
+            span === nothing && (push!(plan, nothing); continue)
+            (left, width) = span
+            lines = wrap(glyphs, paragraph, width)
+            push!(plan, (lines[1]..., left, width, glyphs))
+            length(lines) == 1 && return (plan, nothing)
+            cluster = lines[2][1][1][1].cluster
+            glyphs = glyphs[findfirst(g -> g[1].cluster >= cluster, glyphs):end]
+        end
+        return (plan, glyphs)
+    end
+
+    c = 1
+    for paragraph in eachsplit(text, '\n')
+        pending = @span "shaping" shapeStyled(style, paragraph; clusterOffset=paragraph.offset)
+        starting = true
+        while true
+            container = containers[c]
+            (plan, rest) = planLines(pending, paragraph, c)
+            lines = filter(!isnothing, plan)
+            remaining = rest === nothing ? 0 : length(wrap(rest, paragraph, container.width))
+            take = keptLines(length(lines) + remaining, length(lines), starting, used[c] == 0; orphans=orphans, widows=widows)
+            taken = 0
+            for entry in plan
+                taken == take && break
+                used[c] += 1
+                entry === nothing && continue
+                (line, wrapped, left, width, _) = entry
+                (offset, line) = alignLine(line, style, paragraph, width, align, wrapped; clusterOffset=paragraph.offset)
+                top = container.origin[2] + (used[c] - 1)*lineAdvance
+                firstGlyph = length(placed[c]) + 1
+                x = placeShaped!(placed[c], style, line, left + offset, top + font.metrics.ascender*scale)
+                push!(boxes[c], LineBox(left + offset, top, x - left - offset, lineAdvance, firstGlyph:length(placed[c])))
+                widths[c] = max(widths[c], x - container.origin[1])
+                taken += 1
+            end
+            take == length(lines) + remaining && break
+            # what is left of the paragraph goes to the next container
+            pending = take < length(lines) ? lines[take + 1][5] : rest
+            starting = starting && take == 0
+            if c == length(containers)
+                return (results(), isempty(pending) ? nothing : pending[1][1].cluster, boxes)
+            end
+            c += 1
+        end
+    end
+    return (results(), nothing, boxes)
+end
+
+# Columns of equal width side by side, newspaper style.
+struct ColumnLayout
+    count::Int
+    width::Float32
+    gap::Float32
+    # Inf32 grows the columns to fit, they are balanced then
+    height::Float32
+    # columns of about equal length instead of filling them one by one
+    balance::Bool
+end
+
+ColumnLayout(count, width; gap=16, height=Inf32, balance=false) = ColumnLayout(count, width, gap, height, balance)
+
+columnContainers(columns::ColumnLayout, origin, height, exclusions) = [
+    TextContainer((origin[1] + (k - 1)*(columns.width + columns.gap), origin[2]), columns.width, height, exclusions)
+    for k in 1:columns.count
+]
+
+"""
+    layoutColumns(paragraphs, style, columns::ColumnLayout; origin, align, orphans=2, widows=2, exclusions=[]) -> (layouts, overflow, lineBoxes)
+
+`flowText` of the paragraphs joined by newlines through the columns, clusters
+index the joined text. Balanced columns get the least height that fits
+everything, at most `columns.height`. `exclusions` apply to every column.
+"""
+function layoutColumns(
+    paragraphs, style::TextStyle, columns::ColumnLayout;
+    origin=(0f0, 0f0), align::TextAlign=alignLeft, orphans=2, widows=2, exclusions=Exclusion[]
+)
+    text = join(paragraphs, '\n')
+    flow(height) = flowText(
+        text, style, columnContainers(columns, origin, height, exclusions);
+        align=align, orphans=orphans, widows=widows
+    )
+    (columns.balance || !isfinite(columns.height)) || return flow(columns.height)
+    resolved = resolvedStyle(style)
+    lineAdvance = resolved.font.metrics.height*pixelScale(resolved)*resolved.lineHeight
+    (single, _, _) = flowText(text, style, [TextContainer(origin, columns.width, Inf32, exclusions)]; align=align)
+    lines = round(Int, single[1].height/lineAdvance)
+    maxRows = isfinite(columns.height) ? floor(Int, columns.height/lineAdvance) : lines
+    rows = cld(lines, columns.count)
+    while true
+        # half a line of room keeps the row count clear of rounding
+        result = flow(min(rows + 0.5f0, maxRows + 0.5f0)*lineAdvance)
+        (result[2] === nothing || rows >= maxRows) && return result
+        rows += 1
+    end
+end
+
+# One page of `paginate`, `range` holds the string indices of its text.
+struct Page
+    layout::TextLayout
+    lineBoxes::Vector{LineBox}
+    range::UnitRange{Int}
+end
+
+"""
+    paginate(text, style, pageSize; margin=0, align=alignLeft, orphans=2, widows=2) -> Vector{Page}
+
+`flowText` through as many pages of `pageSize` pixels as the text needs,
+inset by `margin`. Pages break at the same string indices for the same
+text, style and size, so readers can keep their place across sessions.
+"""
+function paginate(
+    text::TextInput, style::TextStyle, pageSize;
+    margin=0, align::TextAlign=alignLeft, orphans=2, widows=2
+)
+    text = validText(text)
+    (width, height) = Float32.(pageSize) .- 2margin
+    page = TextContainer((Float32(margin), Float32(margin)), width, height)
+    count = 1
+    while true
+        (layouts, overflow, boxes) = flowText(text, style, fill(page, count); align=align, orphans=orphans, widows=widows)
+        # pages too small for a single line never take any text
+        (overflow === nothing || all(isempty, boxes)) && return pageRanges(text, layouts, boxes)
+        count *= 2
+    end
+end
+
+function pageRanges(text, layouts, boxes)
+    used = findlast(!isempty, boxes)
+    used === nothing && return [Page(TextLayout(PositionedGlyph[], 0, 0), LineBox[], 1:ncodeunits(text))]
+    starts = Int[]
+    for (layout, pageBoxes) in zip(layouts[1:used], boxes[1:used])
+        glyphs = findfirst(box -> !isempty(box.glyphs), pageBoxes)
+        push!(starts, glyphs === nothing ? (isempty(starts) ? 1 : starts[end]) : layout.glyphs[first(pageBoxes[glyphs].glyphs)].cluster)
+    end
+    starts[1] = 1
+    stops = [starts[2:end] .- 1; ncodeunits(text)]
+    return [Page(layouts[k], boxes[k], starts[k]:stops[k]) for k in 1:used]
+end
@@ -4,6 +4,7 @@
 # Hopefully efficient one for WGPUMakie library ...
 
 using FreeType
+using Downloads
 
 using WGPUCore
 
@@ -14,9 +15,9 @@ rootType(::Type{Ref{T}}) where T = T
 Base.fieldnames(::Type{FT_Face}) = Base.fieldnames(FT_FaceRec)
 Base.fieldnames(::Type{FT_GlyphSlot}) = Base.fieldnames(FT_GlyphSlotRec)
 
-Base.getproperty(fo::FT_Face, sym::Symbol) = 
+Base.getproperty(fo::FT_Face, sym::Symbol) =
     Base.getproperty(fo |> unsafe_load, sym)
-Base.getproperty(fo::FT_GlyphSlot, sym::Symbol) = 
+Base.getproperty(fo::FT_GlyphSlot, sym::Symbol) =
     Base.getproperty(fo |> unsafe_load, sym)
 
 
@@ -31,11 +32,24 @@ mutable struct Glyph
     advance::FT_Pos
 end
 
-mutable struct BufferGlyph
+"""
+    BufferGlyph(start, count)
+
+Element of the glyph storage buffer, `struct Glyph` in font.wgsl: the range
+of a glyph's curves in its curve chunk. Eight bytes, see gpulayout.jl.
+"""
+struct BufferGlyph
     start::UInt32
     count::UInt32
 end
 
+"""
+    BufferCurve(x0, y0, x1, y1, x2, y2)
+
+Element of the curve storage buffer, `struct Curve` in font.wgsl: a
+quadratic with its control points in em units, y up. 24 bytes, see
+gpulayout.jl.
+"""
 struct BufferCurve
     x0::Float32
     y0::Float32
@@ -51,75 +65,448 @@ struct BufferVertex
     u::Float32
     v::Float32
     bufferIndex::Int32
+    timeOffset::Float32
+    amplitude::Float32
+    animationFlags::UInt32
+    color::UInt32       # rgba8, red in the lowest byte
 end
 
-bufferCurves = BufferCurve[]
-bufferGlyphs = BufferGlyph[]
-glyphs = Dict()
+const ftLib = Ref{FT_Library}(C_NULL)
+# FT_New_Face and FT_Done_Face alter the shared library, faces are used by one task at a time.
+const ftLock = ReentrantLock()
 
-function buildGlyph(face, curves, bufferGlyphs, charCode, glyphIdx)
-    # bufferCurves = BufferCurve[]
-    faceRec = face |> unsafe_load
-    bufferGlyph = BufferGlyph(0, 0)
-    bufferGlyph.start = (curves |> length)
+freetypeLibrary() = lock(initFreetype, ftLock)
 
-    start = 1
+function initFreetype()
+    if ftLib[] == C_NULL
+        err = FT_Init_FreeType(ftLib)
+        err == 0 || throw(FontRenderError(ioError, "Could not initialize freetype : Errored $err"))
+    end
+    return ftLib[]
+end
+
+struct FreeTypeProvider <: FontProvider
+    face::FT_Face
+    loadFlags::Int32
+    # path or bytes the face was opened from, reopened for variation instances;
+    # also keeps the bytes of faces opened from memory alive
+    data::Union{Nothing, String, Vector{UInt8}}
+end
+
+FreeTypeProvider(face::FT_Face, data=nothing) =
+    FreeTypeProvider(face, FT_LOAD_NO_SCALE | FT_LOAD_NO_HINTING | FT_LOAD_NO_BITMAP, data)
+
+defaultFontPath() = joinpath(@__DIR__, "..", "assets", "JuliaMono-Light.ttf")
+
+function loadFace(filename::String, ftlib=freetypeLibrary())
+    isfile(filename) || throw(FontRenderError(ioError, "No font file at $filename"))
+    face = Ref{FT_Face}()
+    err = lock(() -> FT_New_Face(ftlib, filename, 0, face), ftLock)
+    err == 0 || throw(FontRenderError(fontParseError, "Could not load face at $filename with index 0 : Errored $err"))
+    return face[]
+end
 
-    glyph = faceRec.glyph |> unsafe_load
+function loadFace(data::Vector{UInt8}, ftlib=freetypeLibrary())
+    face = Ref{FT_Face}()
+    err = lock(() -> FT_New_Memory_Face(ftlib, data, length(data), 0, face), ftLock)
+    err == 0 || throw(FontRenderError(fontParseError, "Could not load face from memory with index 0 : Errored $err"))
+    return face[]
+end
+
+fontMetrics(provider::FreeTypeProvider) = FontMetrics(
+    provider.face.units_per_EM,
+    provider.face.ascender,
+    provider.face.descender,
+    provider.face.height,
+)
+
+glyphIndex(provider::FreeTypeProvider, chr::Char) = FT_Get_Char_Index(provider.face, UInt32(chr))
+
+hasKerning(provider::FreeTypeProvider) = (provider.face.face_flags & FT_FACE_FLAG_KERNING) != 0
+
+function kerning(provider::FreeTypeProvider, left, right)
+    vec = Ref{FT_Vector}()
+    err = FT_Get_Kerning(provider.face, left, right, FT_KERNING_UNSCALED, vec)
+    return err == 0 ? vec[].x : FT_Pos(0)
+end
+
+function sfntTable(provider::FreeTypeProvider, tag::AbstractString)
+    tagValue = foldl((value, c) -> value << 8 | UInt8(c), tag; init=FT_ULong(0))
+    length = Ref{FT_ULong}(0)
+    FT_Load_Sfnt_Table(provider.face, tagValue, 0, C_NULL, length) == 0 || return nothing
+    data = Vector{UInt8}(undef, length[])
+    FT_Load_Sfnt_Table(provider.face, tagValue, 0, data, length) == 0 || return nothing
+    return data
+end
+
+tagString(tag::FT_ULong) = String([UInt8(tag >> shift & 0xff) for shift in (24, 16, 8, 0)])
+
+function variationAxes(provider::FreeTypeProvider)
+    (provider.face.face_flags & FT_FACE_FLAG_MULTIPLE_MASTERS) == 0 && return VariationAxis[]
+    mm = Ref{Ptr{FT_MM_Var}}(C_NULL)
+    FT_Get_MM_Var(provider.face, mm) == 0 || return VariationAxis[]
+    var = unsafe_load(mm[])
+    axes = map(1:var.num_axis) do i
+        axis = unsafe_load(var.axis, i)
+        VariationAxis(tagString(axis.tag), axis.minimum/65536, axis.def/65536, axis.maximum/65536)
+    end
+    FT_Done_MM_Var(freetypeLibrary(), mm[])
+    return axes
+end
+
+function variationInstance(provider::FreeTypeProvider, coordinates)
+    (provider.data === nothing || isempty(variationAxes(provider))) && return nothing
+    face = loadFace(provider.data)
+    coords = [FT_Fixed(round(get(coordinates, axis.tag, axis.default)*65536)) for axis in variationAxes(provider)]
+    err = FT_Set_Var_Design_Coordinates(face, length(coords), coords)
+    err == 0 || throw(FontRenderError(fontParseError, "Could not set variation coordinates : Errored $err"))
+    return FreeTypeProvider(face, provider.loadFlags, provider.data)
+end
+
+# Family name used in gpu resource labels.
+fontLabel(provider::FreeTypeProvider) =
+    provider.face.family_name == C_NULL ? "font" : unsafe_string(provider.face.family_name)
+
+function loadGlyph!(curves, provider::FreeTypeProvider, glyphIdx)
+    err = FT_Load_Glyph(provider.face, glyphIdx, provider.loadFlags)
+    err == 0 || throw(FontRenderError(glyphMissingError, "Could not load glyph $glyphIdx : Errored $err"))
+
+    glyph = provider.face.glyph |> unsafe_load
+    emSize = provider.face.units_per_EM
 
     nContours = glyph.outline.n_contours
     contours = unsafe_wrap(Array, glyph.outline.contours, nContours)
 
+    # contour end points are zero based indices into the outline points
+    start = 1
     for contourIdx in 1:nContours
-        convertContour(curves, glyph.outline, start, contours[contourIdx])
-        start = contours[contourIdx] + 1
-        @info start
+        convertContour(curves, glyph.outline, start, contours[contourIdx] + 1, emSize)
+        start = contours[contourIdx] + 2
     end
 
-    bufferGlyph.count = (curves |> length) - bufferGlyph.start
-    bufferIdx = bufferGlyphs |> length
-    push!(bufferGlyphs, bufferGlyph)
-
-    glyph = Glyph(
-        glyphIdx,
-        bufferIdx,
-        bufferGlyph.count,
+    return GlyphMetrics(
         glyph.metrics.width,
         glyph.metrics.height,
         glyph.metrics.horiBearingX,
         glyph.metrics.horiBearingY,
         glyph.metrics.horiAdvance,
     )
-    glyphs[charCode] = glyph
 end
 
+# Bitmap at `pixelSize` pixels per em with rows top first, `left` and `top`
+# place it relative to the pen position in pixels, y up. Faces with only
+# fixed strikes, like most color emoji fonts, render at their first strike.
+function rasterizeGlyph(provider::FreeTypeProvider, glyphIdx, pixelSize; color=false)
+    face = provider.face
+    ppem = pixelSize
+    if FT_Set_Pixel_Sizes(face, 0, pixelSize) != 0
+        face.num_fixed_sizes > 0 && FT_Select_Size(face, 0) == 0 || return nothing
+        ppem = unsafe_load(face.available_sizes).y_ppem >> 6
+    end
+    FT_Load_Glyph(face, glyphIdx, FT_LOAD_RENDER | (color ? FT_LOAD_COLOR : 0)) == 0 || return nothing
+    slot = face.glyph |> unsafe_load
+    bitmap = slot.bitmap
+    texels = if bitmap.pixel_mode == FT_PIXEL_MODE_GRAY
+        [unsafe_load(bitmap.buffer + (row - 1)*bitmap.pitch, col) for row in 1:bitmap.rows, col in 1:bitmap.width]
+    elseif bitmap.pixel_mode == FT_PIXEL_MODE_BGRA
+        map(Iterators.product(1:bitmap.rows, 1:bitmap.width)) do (row, col)
+            p = bitmap.buffer + (row - 1)*bitmap.pitch + 4*(col - 1)
+            (b, g, r, a) = (unsafe_load(p, i) for i in 1:4)
+            (r, g, b, a)
+        end
+    else
+        return nothing
+    end
+    return (texels, Int(slot.bitmap_left), Int(slot.bitmap_top), Int(ppem))
+end
+
+# Gpu side glyph cache over any provider.
+# Curves are stored in em units, all other metrics in font units.
+mutable struct FontFace
+    provider::FontProvider
+    metrics::FontMetrics
+    emSize::FT_Pos
+    bufferCurves::Vector{BufferCurve}
+    bufferGlyphs::Vector{BufferGlyph}
+    glyphs::Dict{FT_UInt, Glyph}
+    # icons and logos registered with `registerGlyph!`
+    customGlyphs::Dict{Char, FT_UInt}
+    # parsed on first use, false without a usable table
+    gsub::Union{Nothing, Bool, GSUBTable}
+    scriptMetrics::Union{Nothing, ScriptMetrics}
+    # parsed on first use, false without vhea and vmtx
+    verticalMetrics::Union{Nothing, Bool, VerticalMetrics}
+    axes::Union{Nothing, Vector{VariationAxis}}
+    # instances at whole point optical sizes, see `opticalSizeFace`
+    opticalSizes::Dict{Float32, FontFace}
+    # synthesized styles, see synthetic.jl
+    synthetic::Dict{Float32, FontFace}
+end
+
+function FontFace(provider::FontProvider)
+    metrics = fontMetrics(provider)
+    font = FontFace(
+        provider,
+        metrics,
+        metrics.unitsPerEm,
+        BufferCurve[],
+        BufferGlyph[],
+        Dict{FT_UInt, Glyph}(),
+        Dict{Char, FT_UInt}(),
+        nothing,
+        nothing,
+        nothing,
+        nothing,
+        Dict{Float32, FontFace}(),
+        Dict{Float32, FontFace}(),
+    )
+    # .notdef is used for every character missing from the face
+    prepareGlyph(font, FT_UInt(0))
+    return font
+end
+
+FontFace(face::FT_Face, data=nothing) = FontFace(FreeTypeProvider(face, data))
+
+loadFont(filename::String=defaultFontPath()) = FontFace(loadFace(filename), filename)
+loadFont(data::Vector{UInt8}) = FontFace(loadFace(data), data)
+
+# Downloads straight into memory, nothing is written to the file system.
+function fetchFont(url::AbstractString)
+    io = IOBuffer()
+    try
+        Downloads.download(url, io)
+    catch err
+        throw(FontRenderError(ioError, "Could not fetch font from $url : $err"))
+    end
+    return loadFont(take!(io))
+end
+
+const asciiPrintable = join(' ':'~')
+
+"""
+    loadFontAsync(path_or_bytes; prefetch=asciiPrintable) -> Task
+    fetchFontAsync(url; prefetch=asciiPrintable) -> Task
+
+Parse the face and build the outlines of `prefetch` on a worker thread,
+`fetch` the task for the `FontFace`, errors are rethrown there. The font must
+not be shaped on another task before the returned task finished.
+"""
+loadFontAsync(source::Union{String, Vector{UInt8}}; prefetch=asciiPrintable) =
+    Threads.@spawn prefetchGlyphs(loadFont(source), prefetch)
+
+fetchFontAsync(url::AbstractString; prefetch=asciiPrintable) =
+    Threads.@spawn prefetchGlyphs(fetchFont(url), prefetch)
+
+prefetchGlyphs(font::FontFace, text) = (prepareGlyphsForText(font, text); font)
+
+fontLabel(font::FontFace) = fontLabel(font.provider)
+
+glyphIndex(font::FontFace, chr::Char) = get(() -> glyphIndex(font.provider, chr), font.customGlyphs, chr)
+
+# Like `glyphIndex` but refuses to fall back to .notdef.
+function requireGlyphIndex(font::FontFace, chr::Char)
+    glyphIdx = glyphIndex(font, chr)
+    glyphIdx == 0 && throw(FontRenderError(glyphMissingError, "No glyph for $(repr(chr)) in face"))
+    return glyphIdx
+end
+
+# Cached glyphs are only read, so parallel layout can share warm caches.
+function prepareGlyph(font::FontFace, glyphIdx)
+    glyph = get(font.glyphs, glyphIdx, nothing)
+    glyph === nothing || return glyph
+    return font.glyphs[glyphIdx] = buildGlyph(font, glyphIdx)
+end
+
+function prepareGlyphsForText(font::FontFace, str::AbstractString; features=())
+    isempty(features) || return (shapeText(font, str; features=features); nothing)
+    for chr in str
+        prepareGlyph(font, glyphIndex(font, chr))
+    end
+end
+
+# Substitution table of the face, `nothing` if it has none.
+function gsubTable(font::FontFace)
+    font.gsub === nothing && (font.gsub = something(lock(() -> loadGSUB(font.provider), ftLock), false))
+    return font.gsub === false ? nothing : font.gsub
+end
+
+"""
+    availableStylisticSets(font)
+
+The `ss01` to `ss20` sets of the face as `(number, name)` pairs, the name
+comes from the feature params and is `nothing` when the font has none.
+Enable them with `TextStyle(; stylisticSets=stylisticSetMask(numbers...))`.
+"""
+function availableStylisticSets(font::FontFace)
+    gsub = gsubTable(font)
+    gsub === nothing && return Tuple{Int, Union{Nothing, String}}[]
+    sets = Tuple{Int, Union{Nothing, String}}[]
+    for n in 1:20
+        tag = "ss" * lpad(n, 2, '0')
+        any(f -> f[1] == tag, gsub.features) || continue
+        nameID = get(gsub.featureNames, tag, nothing)
+        push!(sets, (n, nameID === nothing ? nothing : lock(() -> nameString(font.provider, nameID), ftLock)))
+    end
+    return sets
+end
 
-function convertContour(bufferCurves, outline, firstIdx, lastIdx)
+function variationAxes(font::FontFace)
+    font.axes === nothing && (font.axes = lock(() -> variationAxes(font.provider), ftLock))
+    return font.axes
+end
+
+# Points per css pixel, optical sizes are given in points.
+const pointsPerPixel = 0.75f0
+
+"""
+    opticalSizeFace(font, opsz)
+
+The instance of a variable `font` with its `opsz` axis at `opsz` points,
+rounded to whole points and clamped to the axis. Faces without the axis,
+with registered custom glyphs or whose provider cannot instance return
+`font` itself. Instances are cached on `font`.
+"""
+function opticalSizeFace(font::FontFace, opsz)
+    axis = findfirst(a -> a.tag == "opsz", variationAxes(font))
+    (axis === nothing || !isempty(font.customGlyphs)) && return font
+    range = variationAxes(font)[axis]
+    value = clamp(Float32(round(opsz)), range.minimum, range.maximum)
+    value == range.default && return font
+    return lock(ftLock) do
+        get!(font.opticalSizes, value) do
+            provider = variationInstance(font.provider, Dict("opsz" => value))
+            provider === nothing ? font : FontFace(provider)
+        end
+    end
+end
+
+function verticalMetrics(font::FontFace)
+    font.verticalMetrics === nothing &&
+        (font.verticalMetrics = something(lock(() -> loadVerticalMetrics(font.provider), ftLock), false))
+    return font.verticalMetrics === false ? nothing : font.verticalMetrics
+end
+
+function scriptMetrics(font::FontFace)
+    font.scriptMetrics === nothing &&
+        (font.scriptMetrics = lock(() -> loadScriptMetrics(font.provider, font.metrics.unitsPerEm), ftLock))
+    return font.scriptMetrics
+end
+
+# Em space curves of a prepared glyph.
+glyphCurves(font::FontFace, glyph::Glyph) =
+    let bufferGlyph = font.bufferGlyphs[glyph.bufferIndex + 1]
+        view(font.bufferCurves, (bufferGlyph.start + 1):(bufferGlyph.start + bufferGlyph.count))
+    end
+
+hasKerning(font::FontFace) = hasKerning(font.provider)
+kerning(font::FontFace, left, right) =
+    isCustomGlyph(left) || isCustomGlyph(right) || isInvisibleGlyph(left) || isInvisibleGlyph(right) ? FT_Pos(0) :
+        kerning(font.provider, left, right)
+
+# Custom glyph indices start far above the 16 bit glyph ids of real faces.
+const customGlyphBase = FT_UInt(0x80000000)
+# Empty zero advance glyphs for default ignorable characters the face does
+# not map, ligatures look through the joiner one.
+const invisibleGlyph = customGlyphBase - FT_UInt(1)
+const joinerGlyph = customGlyphBase - FT_UInt(2)
+isInvisibleGlyph(glyphIdx) = glyphIdx == invisibleGlyph || glyphIdx == joinerGlyph
+
+isCustomGlyph(glyphIdx) = glyphIdx >= customGlyphBase
+
+isPrivateUse(chr::Char) = '\ue000' <= chr <= '\uf8ff' || '\U000f0000' <= chr <= '\U0010fffd'
+
+"""
+    registerGlyph!(font, chr, curves; advance=nothing)
+
+Binds `curves` to the private use character `chr`, so icons render through
+the same pipeline as text. Curves are `(p0, p1, p2)` quadratic control points
+in em units with y growing upwards, origin on the pen position and baseline.
+`advance` is in em units and defaults to the right edge of the outline.
+Registering a character again replaces its outline.
+"""
+function registerGlyph!(font::FontFace, chr::Char, curves; advance=nothing)
+    isPrivateUse(chr) || throw(ArgumentError("custom glyphs must use private use codepoints, got $(repr(chr))"))
+    glyphIdx = get(font.customGlyphs, chr, customGlyphBase + FT_UInt(length(font.customGlyphs)))
+
+    bufferStart = font.bufferCurves |> length
+    (minX, minY, maxX, maxY) = (Inf32, Inf32, -Inf32, -Inf32)
+    for (p0, p1, p2) in curves
+        push!(font.bufferCurves, BufferCurve(p0..., p1..., p2...))
+        for (x, y) in (p0, p1, p2)
+            (minX, minY, maxX, maxY) = (min(minX, x), min(minY, y), max(maxX, x), max(maxY, y))
+        end
+    end
+    count = (font.bufferCurves |> length) - bufferStart
+    count == 0 && ((minX, minY, maxX, maxY) = (0f0, 0f0, 0f0, 0f0))
+
+    bufferIdx = font.bufferGlyphs |> length
+    push!(font.bufferGlyphs, BufferGlyph(bufferStart, count))
+
+    toUnits(v) = round(FT_Pos, v*font.emSize)
+    font.glyphs[glyphIdx] = Glyph(
+        glyphIdx,
+        bufferIdx,
+        count,
+        toUnits(maxX - minX),
+        toUnits(maxY - minY),
+        toUnits(minX),
+        toUnits(maxY),
+        toUnits(advance === nothing ? max(maxX, 0f0) : advance),
+    )
+    font.customGlyphs[chr] = glyphIdx
+    return glyphIdx
+end
+
+function buildGlyph(font::FontFace, glyphIdx)
+    curves = font.bufferCurves
+    bufferStart = curves |> length
+
+    metrics = isInvisibleGlyph(glyphIdx) ? GlyphMetrics(0, 0, 0, 0, 0) :
+        isHexBox(glyphIdx) ? appendHexBox!(curves, font, glyphIdx - hexBoxBase) :
+        loadGlyph!(curves, font.provider, glyphIdx)
+
+    bufferGlyph = BufferGlyph(bufferStart, (curves |> length) - bufferStart)
+    bufferIdx = font.bufferGlyphs |> length
+    push!(font.bufferGlyphs, bufferGlyph)
+
+    return Glyph(
+        glyphIdx,
+        bufferIdx,
+        bufferGlyph.count,
+        metrics.width,
+        metrics.height,
+        metrics.bearingX,
+        metrics.bearingY,
+        metrics.advance,
+    )
+end
+
+
+function convertContour(bufferCurves, outline, firstIdx, lastIdx, emSize)
     if firstIdx == lastIdx
-        return 
+        return
     end
     dIdx = 1
-    if (outline.flags & FT_OUTLINE_REVERSE_FILL)  == 1
+    if (outline.flags & FT_OUTLINE_REVERSE_FILL) != 0
         (lastIdx, firstIdx) = (firstIdx, lastIdx)
-        dIdx = -1 
+        dIdx = -1
     end
 
     tags = unsafe_wrap(Array, outline.tags, outline.n_points)
     points = unsafe_wrap(Array, outline.points, outline.n_points)
-    firstOnCurve = (tags[firstIdx] & FT_CURVE_TAG_ON) == 1
-    lastOnCurve = (tags[lastIdx] & FT_CURVE_TAG_ON) == 1
+    toEm(vec2) = (Float32(vec2.x/emSize), Float32(vec2.y/emSize))
+    curveTag(tag) = tag & 0x03
+
+    firstOnCurve = curveTag(tags[firstIdx]) == FT_CURVE_TAG_ON
+    lastOnCurve = curveTag(tags[lastIdx]) == FT_CURVE_TAG_ON
     if firstOnCurve
-        vec2 = points[firstIdx]
-        first = [vec2.x, vec2.y]
+        first = toEm(points[firstIdx])
         firstIdx += dIdx
     elseif lastOnCurve
-        vec2 = points[lastIdx]
-        first = [vec2.x, vec2.y]
+        first = toEm(points[lastIdx])
         lastIdx -= dIdx
     else
-        fvec2 = points[firstIdx]
-        lvec2 = points[lastIdx]
-        first = [(fvec2.x + lvec2.x)/2, (fvec2.y + lvec2.y)/2]
+        first = (toEm(points[firstIdx]) .+ toEm(points[lastIdx]))./2
     end
     start = first
     control = first
@@ -128,30 +515,28 @@ function convertContour(bufferCurves, outline, firstIdx, lastIdx)
     previousTag = FT_CURVE_TAG_ON
 
     for idx in firstIdx:dIdx:lastIdx
-        vec2 = points[idx]
-        current = [vec2.x, vec2.y]
-        currentTag = tags[idx]
+        current = toEm(points[idx])
+        currentTag = curveTag(tags[idx])
         if currentTag == FT_CURVE_TAG_CUBIC
             control = previous
         elseif currentTag == FT_CURVE_TAG_ON
             if previousTag == FT_CURVE_TAG_CUBIC
-                #TODO
                 b0 = start
                 b1 = control
                 b2 = previous
                 b3 = current
 
-                c0 = b0 .+ 0.75f0*(b1 .- b0)
-                c1 = b3 .+ 0.75f0*(b2 .- b3)
+                c0 = b0 .+ 0.75f0.*(b1 .- b0)
+                c1 = b3 .+ 0.75f0.*(b2 .- b3)
 
-                d = (c0 .+ c1)/2
+                d = (c0 .+ c1)./2
 
-                push!(bufferCurves, BufferCurve(c0..., c1..., d...))
-            
+                push!(bufferCurves, BufferCurve(b0..., c0..., d...))
+                push!(bufferCurves, BufferCurve(d..., c1..., b3...))
             elseif previousTag == FT_CURVE_TAG_ON
-                midPoint = (previous .+ current)/2
+                midPoint = (previous .+ current)./2
                 push!(bufferCurves, BufferCurve(previous..., midPoint..., current...))
-            else 
+            else
                 push!(bufferCurves, BufferCurve(start..., previous..., current...))
             end
             start = current
@@ -160,7 +545,7 @@ function convertContour(bufferCurves, outline, firstIdx, lastIdx)
             if previousTag == FT_CURVE_TAG_ON
                 # NO OP
             else
-                midPoint = (previous .+ current)/2
+                midPoint = (previous .+ current)./2
                 push!(bufferCurves, BufferCurve(start..., previous..., midPoint...))
                 start = midPoint
                 control = midPoint
@@ -176,15 +561,15 @@ function convertContour(bufferCurves, outline, firstIdx, lastIdx)
         b2 = previous
         b3 = first
 
-        c0 = b0 .+ 0.75f0*(b1 .- b0)
-        c1 = b3 .+ 0.75f0*(b2 .- b3)
+        c0 = b0 .+ 0.75f0.*(b1 .- b0)
+        c1 = b3 .+ 0.75f0.*(b2 .- b3)
 
-        d = (c0 .+ c1)/2
+        d = (c0 .+ c1)./2
 
         push!(bufferCurves, BufferCurve(b0..., c0..., d...))
         push!(bufferCurves, BufferCurve(d..., c1..., b3...))
     elseif previousTag == FT_CURVE_TAG_ON
-        midPoint = (previous .+ first)/2
+        midPoint = (previous .+ first)./2
         push!(bufferCurves, BufferCurve(previous..., midPoint..., first...))
     else
         push!(bufferCurves, BufferCurve(start..., previous..., first...))
@@ -217,89 +602,3 @@ str = """
 
     [from Invisible Cities by Italo Calvino]
 """
-
-function prepareGlyphsForText(str::String)
-    ftLib = Ref{FT_Library}(C_NULL)
-
-    FT_Init_FreeType(ftLib)
-
-    @assert ftLib[] != C_NULL
-    
-    function loadFace(filename::String, ftlib=ftLib[])
-        face = Ref{FT_Face}()
-        err = FT_New_Face(ftlib, filename, 0, face)
-        @assert err == 0 "Could not load face at $filename with index 0 : Errored $err"
-        return face[]
-    end
-    
-    face = loadFace(joinpath(@__DIR__, "..", "assets", "JuliaMono-Light.ttf"))
-
-    FT_Set_Pixel_Sizes(face, 1.0, 1.0)
-
-    loadFlags = FT_LOAD_NO_BITMAP | FT_KERNING_DEFAULT
-
-    for chr in str
-        glyphIdx = FT_Get_Char_Index(face, chr)
-        FT_Load_Glyph(face, glyphIdx, loadFlags)
-        glyph = face.glyph |> unsafe_load
-        outline = glyph.outline
-        buildGlyph(face, bufferCurves, bufferGlyphs, chr, glyphIdx)
-    end
-
-    FT_Done_FreeType(ftLib[])
-end
-
-
-function getShaderCode()
-    src = quote
-        struct GlyphUniform
-            start::UInt32
-            stop::UInt32
-        end
-
-        struct CurveUniform
-            p0::Vec2{Float32}
-            p1::Vec2{Float32}
-            p2::Vec2{Float32}
-        end
-
-        @var Uniform 0 0 glyph::@user GlyphUniform
-        @var Uniform 0 1 curve::@user CurveUniform
-        
-    end
-end
-
-
-function getVertexBufferLayout(glyph::Glyph; offset = 0)
-    WGPUCore.GPUVertexBufferLayout => []
-end
-
-
-function getBindingLayouts(glyph::Glyph; binding=0)
-    bindingLayouts = [
-        WGPUCore.WGPUBufferEntry => [
-            :binding => binding,
-            :visibility => ["Vertex", "Fragment"],
-            :type => "Uniform"
-        ],
-    ]
-    return bindingLayouts
-end
-
-
-function getBindings(glyph::Glyph, uniformBuffer; binding=0)
-    bindings = [
-        WGPUCore.GPUBuffer => [
-            :binding => binding,
-            :buffer  => uniformBuffer,
-            :offset  => 0,
-            :size    => uniformBuffer.size
-        ],
-    ]
-end
-
-
-
-# font = FTFont(joinpath(@__DIR__, "..", "assets", "JuliaMono-Light.ttf"))
-
-# 
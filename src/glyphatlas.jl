@@ -0,0 +1,132 @@
+# Coverage atlas for tiny ui text.
+# Below `RenderOptions.rasterTextThreshold` pixels per em even distance fields
+# lose stem contrast, glyphs are rasterized at their drawn size instead and
+# sampled about one texel per pixel. Entries are kept per pixel size after
+# the glyph transform, so scaled glyphs stay one texel per pixel too. The
+# provider rasterizes when it can, which is also where bitmap strikes come
+# from, custom glyphs fall back to supersampled coverage of their curves.
+#
+# `atlasR8` keeps one byte per texel and draws every glyph in the text color.
+# `atlasRGBA8` costs four times the memory but also holds color bitmaps such
+# as emoji strikes, coverage is stored as premultiplied white there.
+
+@enum AtlasFormat atlasR8 atlasRGBA8
+
+mutable struct GlyphAtlas
+    device
+    size::Int
+    format::AtlasFormat
+    texture
+    view
+    sampler
+    # cpu copy per page, row major with the top row first
+    texels::Vector{Matrix{UInt8}}
+    # keyed by (font, glyph index, pixels per em)
+    entries::Dict{Tuple{FontFace, FT_UInt, Int}, AtlasEntry}
+    lastUse::Dict{Tuple{FontFace, FT_UInt, Int}, Int}
+    frame::Int
+    packers::Vector{ShelfPacker}
+    maxPages::Int
+    evictions::Int
+    # texels per page changed since the last flush, (x0, y0, x1, y1) zero based
+    # and exclusive, nothing for clean pages
+    dirtyRects::Vector{Union{Nothing, NTuple{4, Int}}}
+    bindGroups::IdDict{Any, Any}    # per uniform buffer
+end
+
+atlasTextureOptions(atlas::GlyphAtlas) = atlasTextureOptions(GlyphAtlas; format=atlas.format)
+atlasTextureOptions(::Type{GlyphAtlas}; format=atlasR8) = (
+    label="glyph atlas",
+    mipLevels=atlasMipLevels,
+    format=format == atlasR8 ? WGPUCore.WGPUTextureFormat_R8Unorm : WGPUCore.WGPUTextureFormat_RGBA8Unorm,
+    usage=["TextureBinding", "CopyDst"],
+)
+
+atlasKind(format::AtlasFormat) = format == atlasR8 ? :alpha : :color
+
+function GlyphAtlas(device; size=1024, format=atlasR8, maxPages=8)
+    textureOptions = atlasTextureOptions(GlyphAtlas; format=format)
+    texture = createAtlasTexture(device, size; textureOptions...)
+    channels = format == atlasR8 ? 1 : 4
+    return GlyphAtlas(
+        device, size, format,
+        texture, createArrayView(texture), createAtlasSampler(device),
+        [zeros(UInt8, size, channels*size)],
+        Dict{Tuple{FontFace, FT_UInt, Int}, AtlasEntry}(),
+        Dict{Tuple{FontFace, FT_UInt, Int}, Int}(),
+        0,
+        [atlasPacker(size, textureOptions.mipLevels)],
+        maxPages,
+        0,
+        Union{Nothing, NTuple{4, Int}}[nothing],
+        IdDict{Any, Any}()
+    )
+end
+
+function rasterizeCurves(font::FontFace, glyph::Glyph, pixelSize; samples=4)
+    emSize = font.emSize
+    left = floor(Int, glyph.bearingX/emSize*pixelSize)
+    top = ceil(Int, glyph.bearingY/emSize*pixelSize)
+    width = ceil(Int, (glyph.bearingX + glyph.width)/emSize*pixelSize) - left
+    height = top - floor(Int, (glyph.bearingY - glyph.height)/emSize*pixelSize)
+    curves = glyphCurves(font, glyph)
+    coverage = Matrix{UInt8}(undef, height, width)
+    for row in 1:height, col in 1:width
+        hits = count(
+            winding(((left + col - 1 + (i - 0.5f0)/samples)/pixelSize, (top - row + 1 - (j - 0.5f0)/samples)/pixelSize), curves) != 0
+            for i in 1:samples, j in 1:samples
+        )
+        coverage[row, col] = round(UInt8, 255*hits/samples^2)
+    end
+    return (coverage, left, top, pixelSize)
+end
+
+function rasterizeGlyph(font::FontFace, glyph::Glyph, pixelSize; color=false)
+    raster = isCustomGlyph(glyph.index) ? nothing :
+        lock(() -> rasterizeGlyph(font.provider, glyph.index, pixelSize; color=color), ftLock)
+    return raster === nothing ? rasterizeCurves(font, glyph, pixelSize) : raster
+end
+
+# Copies a gray or premultiplied rgba bitmap into the interleaved page.
+function blitBitmap!(page::Matrix{UInt8}, bitmap, x, y, channels)
+    (height, width) = size(bitmap, 1), size(bitmap, 2)
+    for row in 1:height, col in 1:width
+        texel = bitmap[row, col]
+        values = channels == 1 ? (texel isa UInt8 ? texel : texel[4],) :
+            texel isa UInt8 ? (texel, texel, texel, texel) : texel
+        for c in 1:channels
+            page[y + row, channels*(x + col - 1) + c] = values[c]
+        end
+    end
+end
+
+# Pixels per em the bitmap of `pg` is rasterized at.
+atlasPixelSize(pg::PositionedGlyph) = max(round(Int, effectivePixelSize(pg)), 1)
+
+# Layout pixels per em of the quad, the glyph transform then scales it to
+# exactly the rasterized size.
+function quadSize(::GlyphAtlas, pg::PositionedGlyph)
+    pixelSize = effectivePixelSize(pg)
+    return pixelSize > 0 ? pg.size*atlasPixelSize(pg)/pixelSize : pg.size
+end
+
+function atlasEntry!(atlas::GlyphAtlas, pg::PositionedGlyph)
+    pixelSize = atlasPixelSize(pg)
+    key = (pg.font, pg.glyph.index, pixelSize)
+    get!(atlas.entries, key) do
+        color = atlas.format == atlasRGBA8 && enableColorFonts
+        (bitmap, left, top, ppem) = @span "rasterize" rasterizeGlyph(pg.font, pg.glyph, pixelSize; color=color)
+        (height, width) = size(bitmap)
+        (x, y, layer) = allocate!(atlas, width, height)
+        blitBitmap!(atlas.texels[layer + 1], bitmap, x, y, bytesPerTexel(atlas))
+        markDirty!(atlas, layer, x, y, width, height)
+        # em space bounds, so quads scale with `quadSize` like the other atlases;
+        # bitmap strikes come at their own ppem and are scaled to the drawn size
+        AtlasEntry(
+            x, y, layer, width, height,
+            left/ppem, (top - height)/ppem, (left + width)/ppem, top/ppem,
+            eltype(bitmap) != UInt8 && atlas.format == atlasRGBA8
+        )
+    end
+    return touch!(atlas, key)
+end
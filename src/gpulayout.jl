@@ -0,0 +1,56 @@
+# Stable layouts of the glyph and curve storage buffers, for tooling that
+# writes compatible buffers itself, e.g. a baking step shipping preconverted
+# fonts. `BufferGlyph`, `BufferCurve` and `HalfCurve` are plain bits types
+# laid out like their wgsl structs without padding, so a `Vector` of them is
+# the buffer as the shaders read it:
+#
+#     Glyph       start: u32, count: u32                              8 bytes
+#     Curve       p0: vec2<f32>, p1: vec2<f32>, p2: vec2<f32>        24 bytes
+#     HalfCurve   p0: vec2<f16>, p1: vec2<f16>, p2: vec2<f16>        12 bytes
+#
+# Curve points are em units with y up, glyph ranges index the curve chunk
+# the glyph lives in. Quantized and deduplicated devices store chunks as
+# `u32` words instead, laid out in curveencoding.jl. `gpuLayoutVersion` changes with any of these layouts.
+# Every shader declaring them carries the version as `layoutVersion`, and
+# compiling one whose version or struct fields differ throws.
+
+"""
+    gpuLayoutVersion
+
+Version of the `Glyph` and `Curve` storage layouts, see gpulayout.jl.
+"""
+const gpuLayoutVersion = 1
+
+const wgslLayouts = Dict(
+    "Glyph" => (BufferGlyph, ["start: u32", "count: u32"]),
+    "Curve" => (BufferCurve, ["p0: vec2<f32>", "p1: vec2<f32>", "p2: vec2<f32>"]),
+    "HalfCurve" => (HalfCurve, ["p0: vec2<f16>", "p1: vec2<f16>", "p2: vec2<f16>"]),
+)
+
+const wgslSizes = Dict("u32" => 4, "f32" => 4, "vec2<f32>" => 8, "vec2<f16>" => 4)
+
+for (name, (T, fields)) in wgslLayouts
+    @assert isbitstype(T) && sizeof(T) == sum(field -> wgslSizes[split(field, ": ")[2]], fields) "$T does not match struct $name"
+end
+
+# Field declarations of `struct name` in `source`, comments and blanks dropped.
+function wgslFields(source::AbstractString, name::AbstractString)
+    body = match(Regex("struct $name \\{([^}]*)\\}"), source)
+    body === nothing && return nothing
+    lines = (strip(replace(line, r"//.*" => "")) for line in split(body[1], '\n'))
+    return [rstrip(line, ',') for line in lines if !isempty(line)]
+end
+
+function checkLayouts(source::AbstractString, label)
+    for (name, (_, fields)) in wgslLayouts
+        declared = wgslFields(source, name)
+        declared === nothing && continue
+        declared == fields || throw(FontRenderError(
+            shaderCompileError, "$label declares struct $name as $(join(declared, ", ")), the buffers hold $(join(fields, ", "))"
+        ))
+        version = match(r"const layoutVersion = (\d+)u;", source)
+        version !== nothing && parse(Int, version[1]) == gpuLayoutVersion || throw(FontRenderError(
+            shaderCompileError, "$label is written for layout version $(version === nothing ? "none" : version[1]), the buffers have $gpuLayoutVersion"
+        ))
+    end
+end
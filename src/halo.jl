@@ -0,0 +1,34 @@
+# Halos keep text readable over busy backgrounds such as maps.
+# Every glyph is drawn a second time behind itself from its outline grown by
+# the halo width, see synthetic.jl, in the halo color. Grown outlines keep
+# the left side bearing, so halo glyphs move back by the growth to stay
+# centered on the glyphs they surround.
+
+struct Halo
+    color::NTuple{4, Float32}
+    width::Float32      # pixels around the outlines
+end
+
+Halo(color; width=2) = Halo(color, width)
+
+# Strengths are rounded to 1/256 em so labels of close sizes share faces.
+haloStrength(halo::Halo, size) = round(halo.width/size*256)/256
+
+"""
+    haloLayout(layout, halo) -> TextLayout
+
+`layout` with the halo glyphs in front of its glyphs, so they are drawn
+behind them.
+"""
+function haloLayout(layout::TextLayout, halo::Halo)
+    halo.width > 0 || return layout
+    glyphs = PositionedGlyph[]
+    for pg in layout.glyphs
+        pg.glyph.curveCount == 0 && continue
+        strength = haloStrength(halo, pg.size)
+        face = emboldenedFace(pg.font, strength)
+        (dx, dy) = pg.transform*(-strength*pg.size, 0f0)
+        push!(glyphs, setfields(pg; glyph=prepareGlyph(face, pg.glyph.index), font=face, x=pg.x + dx, y=pg.y + dy, color=halo.color))
+    end
+    return TextLayout(append!(glyphs, layout.glyphs), layout.width, layout.height)
+end
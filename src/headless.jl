@@ -0,0 +1,122 @@
+# Offscreen rendering without a window or surface.
+
+const headlessFormat = WGPUCore.WGPUTextureFormat_RGBA8Unorm
+
+# wgpu requires texture to buffer copies to use rows aligned to 256 bytes.
+paddedBytesPerRow(width) = cld(4*width, 256)*256
+
+function createRenderTarget(device, (width, height); format=headlessFormat, label="text render target")
+    WGPUCore.createTexture(
+        device, label,
+        (width, height, 1),
+        1, 1,
+        WGPUCore.WGPUTextureDimension_2D,
+        format,
+        WGPUCore.getEnum(WGPUCore.WGPUTextureUsage, ["RenderAttachment", "CopySrc"])
+    )
+end
+
+function createDepthTarget(device, (width, height); format=WGPUCore.WGPUTextureFormat_Depth24Plus, sampleCount=1)
+    WGPUCore.createTexture(
+        device, "text depth target",
+        (width, height, 1),
+        1, sampleCount,
+        WGPUCore.WGPUTextureDimension_2D,
+        format,
+        WGPUCore.getEnum(WGPUCore.WGPUTextureUsage, ["RenderAttachment"])
+    )
+end
+
+function readTexture(device, encoder, texture, (width, height))
+    bytesPerRow = paddedBytesPerRow(width)
+    readbackBuffer = WGPUCore.createBuffer(
+        "text readback buffer", device,
+        bytesPerRow*height,
+        ["MapRead", "CopyDst"],
+        false
+    )
+    WGPUCore.copyTextureToBuffer(
+        encoder,
+        [
+            :texture => texture,
+            :mipLevel => 0,
+            :origin => ((0, 0, 0) .|> Float32)
+        ],
+        [
+            :buffer => readbackBuffer,
+            :layout => [
+                :offset => 0,
+                :bytesPerRow => bytesPerRow,
+                :rowsPerImage => height
+            ]
+        ],
+        [
+            :width => width,
+            :height => height,
+            :depthOrArrayLayers => 1
+        ]
+    )
+    WGPUCore.submit(device.queue, [WGPUCore.finish(encoder),])
+    data = WGPUCore.readBuffer(device, readbackBuffer, 0, bytesPerRow*height)
+
+    # strip row padding
+    pixels = Vector{UInt8}(undef, 4*width*height)
+    for row in 1:height
+        copyto!(pixels, (row - 1)*4*width + 1, data, (row - 1)*bytesPerRow + 1, 4*width)
+    end
+    return pixels
+end
+
+# Pipeline and font buffers of a device, kept across offscreen renders.
+struct HeadlessCache
+    pipeline::FontPipeline
+    fontBuffers::IdDict{FontFace, FontBuffers}
+end
+
+const headlessCaches = WeakKeyDict{Any, HeadlessCache}()
+const headlessLock = ReentrantLock()
+
+headlessCache(device) = lock(headlessLock) do
+    get!(headlessCaches, device) do
+        HeadlessCache(createFontPipeline(device, headlessFormat), IdDict{FontFace, FontBuffers}())
+    end
+end
+
+# Uploaded on first use and again whenever the font grew.
+function headlessFontBuffers(cache::HeadlessCache, device, font::FontFace)
+    previous = get(cache.fontBuffers, font, nothing)
+    return cache.fontBuffers[font] = previous === nothing ? uploadFont(device, font) : updateFont(device, previous)
+end
+
+"""
+    renderToTexture(text, style, (width, height)) -> Vector{UInt8}
+
+Renders `text` into an offscreen rgba8 target and reads it back.
+Pixels are row major, top row first, with premultiplied alpha.
+"""
+function renderToTexture(
+        text::AbstractString, style::TextStyle, targetSize;
+        device=WGPUCore.getDefaultDevice(),
+        origin=(0f0, 0f0),
+        clearColor=(0.0, 0.0, 0.0, 0.0)
+    )
+    cache = headlessCache(device)
+    fp = cache.pipeline
+    texture = createRenderTarget(device, targetSize)
+    view = WGPUCore.createView(texture)
+
+    layout = layoutText(text, style; origin=origin)
+    # fallback faces and fonts of several curve chunks take one draw each
+    textDraws = prepareChunkedText(fp, font -> headlessFontBuffers(cache, device, font), layout, orthographic(targetSize...))
+
+    encoder = WGPUCore.createCommandEncoder(device, "headless text encoder")
+    renderPass = WGPUCore.beginRenderPass(
+        encoder,
+        colorAttachmentOptions(view, clearColor) |> Ref;
+        label="headless text pass"
+    )
+    drawText(renderPass, fp, textDraws)
+    WGPUCore.endEncoder(renderPass)
+
+    return readTexture(device, encoder, texture, targetSize)
+end
@@ -0,0 +1,63 @@
+# Hex boxes stand in for characters that should be seen but have no glyph.
+# The box is generated as curves: a frame around the code point in hex drawn
+# with a 3x5 cell font, two rows of two digits, or three for code points
+# above U+FFFF. Glyph indices encode the code point, so every face builds
+# boxes lazily through `prepareGlyph` like any other glyph.
+
+const hexBoxBase = FT_UInt(0xc0000000)
+
+isHexBox(glyphIdx) = glyphIdx >= hexBoxBase
+
+hexBoxGlyph(chr::Char) = hexBoxBase + FT_UInt(UInt32(chr))
+
+const hexDigitCells = Dict(
+    '0' => ("111", "101", "101", "101", "111"), '1' => ("010", "110", "010", "010", "111"),
+    '2' => ("111", "001", "111", "100", "111"), '3' => ("111", "001", "111", "001", "111"),
+    '4' => ("101", "101", "111", "001", "001"), '5' => ("111", "100", "111", "001", "111"),
+    '6' => ("111", "100", "111", "101", "111"), '7' => ("111", "001", "001", "001", "001"),
+    '8' => ("111", "101", "111", "101", "111"), '9' => ("111", "101", "111", "001", "111"),
+    'A' => ("111", "101", "111", "101", "101"), 'B' => ("110", "101", "110", "101", "110"),
+    'C' => ("111", "100", "100", "100", "111"), 'D' => ("110", "101", "101", "101", "110"),
+    'E' => ("111", "100", "111", "100", "111"), 'F' => ("111", "100", "111", "100", "100"),
+)
+
+# In em units: digit cell, padding inside the frame, frame and side bearing.
+const hexCell = 0.05f0
+const hexPadding = 0.05f0
+const hexFrame = 0.03f0
+const hexBearing = 0.05f0
+
+lineCurve(p0, p2) = BufferCurve(p0..., ((p0 .+ p2)./2)..., p2...)
+
+# Clockwise with y up, the fill direction of outer contours.
+function appendRectangle!(curves, x0, y0, x1, y1; hole=false)
+    corners = [(x0, y0), (x0, y1), (x1, y1), (x1, y0)]
+    hole && reverse!(corners)
+    for k in 1:4
+        push!(curves, lineCurve(corners[k], corners[mod1(k + 1, 4)]))
+    end
+end
+
+function appendHexBox!(curves, font::FontFace, codepoint)
+    digits = uppercase(string(codepoint; base=16, pad=codepoint > 0xffff ? 6 : 4))
+    perRow = length(digits) ÷ 2
+    inset = hexPadding + hexFrame
+    width = perRow*3hexCell + (perRow - 1)*hexCell + 2inset
+    height = 11hexCell + 2inset
+    (left, right) = (hexBearing, hexBearing + width)
+    appendRectangle!(curves, left, 0f0, right, height)
+    appendRectangle!(curves, left + hexFrame, hexFrame, right - hexFrame, height - hexFrame; hole=true)
+    for (i, digit) in enumerate(digits)
+        (row, column) = divrem(i - 1, perRow)
+        x = left + inset + column*4hexCell
+        top = height - inset - row*6hexCell
+        for (r, bits) in enumerate(hexDigitCells[digit]), (c, bit) in enumerate(bits)
+            bit == '1' || continue
+            cx = x + (c - 1)*hexCell
+            cy = top - r*hexCell
+            appendRectangle!(curves, cx, cy, cx + hexCell, cy + hexCell)
+        end
+    end
+    toUnits(v) = round(Int, v*font.emSize)
+    return GlyphMetrics(toUnits(width), toUnits(height), toUnits(left), toUnits(height), toUnits(width + 2hexBearing))
+end
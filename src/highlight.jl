@@ -0,0 +1,53 @@
+# Syntax highlighting of laid out code.
+# Highlighters such as tree-sitter or syntect produce byte ranges with
+# colors. Those only repaint glyphs, so code is shaped and laid out once and
+# recolored whenever the highlights change. Later spans win over earlier
+# ones, captures nested in outer ones are passed after them.
+
+struct HighlightSpan
+    range::UnitRange{Int}       # string indices, matched against glyph clusters
+    color::NTuple{4, Float32}
+    # synthetic italic, leaning like the `oblique` style option
+    oblique::Bool
+end
+
+HighlightSpan(range, color; oblique=false) = HighlightSpan(range, color, oblique)
+HighlightSpan(span::Pair) = HighlightSpan(span.first, span.second)
+HighlightSpan(span::HighlightSpan) = span
+
+"""
+    highlightLayout(layout, spans; offset=0, obliqueAngle=12) -> TextLayout
+
+`layout` with the glyphs of every `HighlightSpan`, or `range => color` pair,
+in its color. Span ranges index the text `layout` was laid out from,
+shifted by `offset` when it holds a part starting after index `offset`,
+e.g. one line of a file. Glyphs are found by binary search over their
+clusters, so a file of tens of thousands of lines takes about one pass
+over its glyphs plus a search per span.
+"""
+function highlightLayout(layout::TextLayout, spans; offset=0, obliqueAngle=12f0)
+    glyphs = copy(layout.glyphs)
+    (isempty(glyphs) || isempty(spans)) && return TextLayout(glyphs, layout.width, layout.height)
+    clusters = [pg.cluster for pg in glyphs]
+    # left to right text is already in cluster order
+    order = issorted(clusters) ? nothing : sortperm(clusters)
+    sorted = order === nothing ? clusters : clusters[order]
+    spans = [HighlightSpan(span) for span in spans]
+    # the last span covering a glyph, painted once at the end
+    winner = zeros(Int, length(glyphs))
+    for (s, span) in enumerate(spans)
+        range = span.range .- offset
+        for k in searchsortedfirst(sorted, first(range)):searchsortedlast(sorted, last(range))
+            winner[order === nothing ? k : order[k]] = s
+        end
+    end
+    skew = skewing(-deg2rad(Float32(obliqueAngle)))
+    for (g, s) in enumerate(winner)
+        s == 0 && continue
+        (pg, span) = (glyphs[g], spans[s])
+        glyphs[g] = span.oblique ?
+            setfields(pg; color=span.color, transform=pg.transform*skew) :
+            setfields(pg; color=span.color)
+    end
+    return TextLayout(glyphs, layout.width, layout.height)
+end
@@ -0,0 +1,94 @@
+# Shader hot reload for shader development, behind the `devHotReload` feature.
+#
+#     using Preferences, WGPUFontRenderer
+#     set_preferences!(WGPUFontRenderer, "devHotReload" => true)
+#
+# Every render pipeline built by `createFontPipeline` is tracked, and the
+# first one starts a task watching the shader directory. Saving a .wgsl file
+# validates it with naga when the cli is installed, compiles it once per
+# device and rebuilds the tracked pipelines of that file in place, so
+# renderers, targets and batches keep their `FontPipeline` and bind groups.
+# Pipelines built afterwards, including new variants, use the saved source.
+# Broken edits are logged and the running pipelines stay as they were.
+
+using FileWatching
+
+# latest saved source of every reloaded shader, by file name
+const reloadedShaders = Dict{String, String}()
+
+struct WatchedPipeline
+    pipeline::WeakRef           # FontPipeline
+    shaderName::String
+    # builds the render pipeline from a shader module
+    build
+end
+
+const watchedPipelines = WatchedPipeline[]
+const shaderWatcher = Ref{Union{Nothing, Task}}(nothing)
+const reloadLock = ReentrantLock()
+
+shaderName(source::String) = findfirst(==(source), embeddedShaders)
+
+# Embedded shader sources map to the last saved version of their file.
+function latestSource(source::String)
+    enableHotReload || return source
+    name = shaderName(source)
+    return name === nothing ? source : get(reloadedShaders, name, source)
+end
+
+function watchPipeline!(fp::FontPipeline, source::String, build)
+    name = something(shaderName(source), findfirst(==(source), reloadedShaders), Some(nothing))
+    # custom shader sources have no file to watch
+    name === nothing && return fp
+    lock(reloadLock) do
+        push!(watchedPipelines, WatchedPipeline(WeakRef(fp), name, build))
+        shaderWatcher[] === nothing && (shaderWatcher[] = watchShaders())
+    end
+    return fp
+end
+
+function watchShaders()
+    @info "Watching $shaderDir for shader changes"
+    @async while true
+        (file, event) = watch_folder(shaderDir)
+        endswith(file, ".wgsl") && event.changed || continue
+        try
+            reloadShader!(file)
+        catch err
+            @warn "Could not reload shader" file exception=err
+        end
+    end
+end
+
+"""
+    reloadShader!(name)
+
+Reads `shaders/name` again and rebuilds the pipelines using it, see
+hotreload.jl. Called by the shader watcher, also usable by hand.
+"""
+function reloadShader!(name::AbstractString)
+    path = joinpath(shaderDir, name)
+    validateShader(path)
+    source = read(path, String)
+    lock(reloadLock) do
+        filter!(watched -> watched.pipeline.value !== nothing, watchedPipelines)
+        # every module compiles before any pipeline is swapped
+        shaders = IdDict{Any, Any}()
+        for watched in watchedPipelines
+            fp = watched.pipeline.value
+            watched.shaderName == name || continue
+            get!(() -> compileShader(fp.device, "$name shader", source), shaders, fp.shader)
+        end
+        for watched in watchedPipelines
+            fp = watched.pipeline.value
+            watched.shaderName == name || continue
+            fp.pipeline = watched.build(shaders[fp.shader])
+        end
+        for watched in watchedPipelines
+            fp = watched.pipeline.value
+            haskey(shaders, fp.shader) && (fp.shader = shaders[fp.shader])
+        end
+        reloadedShaders[name] = source
+        @info "Reloaded $name, $(length(shaders)) shader modules rebuilt"
+    end
+end
@@ -0,0 +1,67 @@
+# Inline objects, images or widgets in the text stream.
+# Every U+FFFC OBJECT REPLACEMENT CHARACTER of the text stands for the next
+# object. The character draws nothing, lines reserve the width of its object
+# and grow to fit taller ones, and `inlineObjectBoxes` reports where hosts
+# draw them once the layout is done.
+
+struct InlineObject
+    width::Float32
+    height::Float32
+    # from the top of the object down to the baseline it sits on
+    baseline::Float32
+end
+
+InlineObject(width, height; baseline=height) = InlineObject(width, height, baseline)
+
+const objectReplacement = '￼'
+
+# Objects by the string index of their replacement character.
+function objectClusters(text::AbstractString, objects)
+    clusters = Dict{Int, InlineObject}()
+    n = 0
+    for (idx, chr) in pairs(text)
+        chr == objectReplacement || continue
+        n += 1
+        n <= length(objects) && (clusters[idx] = objects[n])
+    end
+    return clusters
+end
+
+# `shapeStyled` tuples with the advances of the objects they stand for.
+function reserveObjects(shaped, objects::Dict{Int, InlineObject})
+    isempty(objects) && return shaped
+    return map(shaped) do g
+        object = get(objects, g[1].cluster, nothing)
+        object === nothing ? g : (g[1], g[2], object.width, g[4])
+    end
+end
+
+# Room a line needs above its ascent and below its descent for its objects.
+function objectExtents(line, objects::Dict{Int, InlineObject}, ascent, descent)
+    (above, below) = (0f0, 0f0)
+    for g in line
+        object = get(objects, g[1].cluster, nothing)
+        object === nothing && continue
+        above = max(above, object.baseline - ascent)
+        below = max(below, object.height - object.baseline - descent)
+    end
+    return (above, below)
+end
+
+"""
+    inlineObjectBoxes(layout, text, objects) -> Vector
+
+`(x, y, width, height)` of every object of `layoutText(text, style;
+objects)` in layout space, y at the top of the object, or `nothing` for
+objects without a replacement character in the text.
+"""
+function inlineObjectBoxes(layout::TextLayout, text::TextInput, objects)
+    clusters = sort!(collect(keys(objectClusters(validText(text), objects))))
+    placed = Dict(pg.cluster => pg for pg in layout.glyphs)
+    return map(eachindex(objects)) do n
+        n <= length(clusters) || return nothing
+        pg = get(placed, clusters[n], nothing)
+        object = objects[n]
+        pg === nothing ? nothing : (pg.x, pg.y - object.baseline, object.width, object.height)
+    end
+end
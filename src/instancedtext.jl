@@ -0,0 +1,217 @@
+# Heterogeneous text in one draw, e.g. overlays mixing fonts, sizes and
+# rotations per glyph.
+#
+#     batch = InstancedText(renderer)
+#     push!(batch, layoutText("Hello", style; origin=(20, 40)))
+#     push!(batch, font, '!'; position=(300, 80), scale=48, rotation=0.3)
+#     prepare!(batch, surface.size)
+#     draw!(batch, renderPass)
+#
+# Every glyph is one `GlyphInstance` in a storage buffer, the vertex shader
+# reads it by instance index and emits its quad, so nothing but the
+# projection and the shared options lives in uniforms. The glyphs and curves
+# of all fonts of the batch are merged into one binding each, rebuilt when
+# a font gained glyphs, and have to fit a single curve binding together.
+# Layouts keep the rotation of their glyph transforms, shears such as
+# obliques are not applied.
+
+const instancedShaderSource = embedShader("instanced.wgsl"; appendedTo=fontShaderSource)
+
+const instanceHidden = UInt32(1)
+const instanceAboutCenter = UInt32(2)
+
+# Layout of `GlyphInstance` in instanced.wgsl.
+struct GlyphInstance
+    x::Float32          # pen position
+    y::Float32
+    scale::Float32      # pixels per em
+    rotation::Float32
+    color::UInt32       # rgba8, red in the lowest byte
+    font::UInt32
+    glyph::UInt32
+    flags::UInt32
+end
+
+"""
+    GlyphInstance(font, glyph; position=(0, 0), scale=16, rotation=0, color=(0, 0, 0, 1), hidden=false, aboutCenter=false)
+
+Glyph `glyph`, a buffer index, of the batch font `font` from `fontIndex!`,
+with its pen at `position` in pixels. `scale` is pixels per em, `rotation`
+turns it clockwise in radians about the pen or, with `aboutCenter`, about
+the center of its box. Hidden instances keep their slot without drawing.
+"""
+GlyphInstance(
+        font::Integer, glyph::Integer;
+        position=(0f0, 0f0), scale=16f0, rotation=0f0, color=(0f0, 0f0, 0f0, 1f0), hidden=false, aboutCenter=false
+    ) = GlyphInstance(
+        position..., scale, rotation, packColor(color), font, glyph,
+        (hidden ? instanceHidden : 0x0) | (aboutCenter ? instanceAboutCenter : 0x0)
+    )
+
+# Merged font buffers of a batch, with the sizes of the fonts they were built from.
+struct MergedFonts
+    buffers::Vector{Any}    # glyphs, curves, first glyphs, boxes
+    glyphCounts::Vector{Int}
+    curveCounts::Vector{Int}
+end
+
+mutable struct InstancedText
+    renderer::TextRenderer
+    pipeline::FontPipeline
+    fonts::Vector{FontFace}
+    fontIndices::IdDict{FontFace, Int}
+    merged::Union{Nothing, MergedFonts}
+    instances::Vector{GlyphInstance}
+    instanceBuffer
+    instanceCapacity::Int
+    instanceCount::Int
+    uniformBuffer
+    bindGroup
+end
+
+function getInstancedBindingLayouts()
+    [
+        getBindingLayouts(FontFace)...,
+        (WGPUCore.WGPUBufferEntry => [
+            :binding => i,
+            :visibility => ["Vertex"],
+            :type => "ReadOnlyStorage"
+        ] for i in 3:5)...,
+    ]
+end
+
+"""
+    InstancedText(renderer)
+
+Glyph instances drawn into the targets of `renderer` with one draw per
+frame, see instancedtext.jl.
+"""
+function InstancedText(renderer::TextRenderer)
+    requireCurvesPath(renderer, "InstancedText")
+    pipeline = rendererPipeline(
+        renderer;
+        label="instanced text",
+        shaderSource=getShaderCode() * instancedShaderSource,
+        bindingLayouts=getInstancedBindingLayouts(),
+        vertexEntryPoint="vs_glyphInstance",
+        vertexBuffers=[]
+    )
+    return InstancedText(
+        renderer, pipeline, FontFace[], IdDict{FontFace, Int}(), nothing,
+        GlyphInstance[], nothing, 0, 0, nothing, nothing
+    )
+end
+
+"""
+    fontIndex!(batch, font) -> Int
+
+Index of `font` for `GlyphInstance`s of `batch`, adding it on first use.
+"""
+fontIndex!(batch::InstancedText, font::FontFace) = get!(batch.fontIndices, font) do
+    push!(batch.fonts, font)
+    return length(batch.fonts) - 1
+end
+
+Base.push!(batch::InstancedText, instance::GlyphInstance) = push!(batch.instances, instance)
+
+# One glyph of `font`, `kwargs` as for `GlyphInstance`.
+function Base.push!(batch::InstancedText, font::FontFace, chr::Char; kwargs...)
+    glyph = prepareGlyph(font, requireGlyphIndex(font, chr))
+    return push!(batch.instances, GlyphInstance(fontIndex!(batch, font), glyph.bufferIndex; kwargs...))
+end
+
+# Adds the glyphs of `layout` for the next frame.
+function Base.push!(batch::InstancedText, layout::TextLayout)
+    for pg in layout.glyphs
+        pg.glyph.curveCount == 0 && continue
+        m = pg.transform
+        push!(batch.instances, GlyphInstance(
+            fontIndex!(batch, pg.font), pg.glyph.bufferIndex;
+            position=(pg.x, pg.y), scale=pg.size, rotation=atan(m.b, m.a), color=pg.color
+        ))
+    end
+    return batch
+end
+
+Base.push!(batch::InstancedText, text::TextInput, position, style::TextStyle) =
+    push!(batch, layoutText(text, style; origin=Float32.(position)))
+
+Base.length(batch::InstancedText) = length(batch.instances)
+
+Base.empty!(batch::InstancedText) = (empty!(batch.instances); batch)
+
+mergedCurrent(merged::MergedFonts, fonts) =
+    merged.glyphCounts == [length(font.bufferGlyphs) for font in fonts] &&
+    merged.curveCounts == [length(font.bufferCurves) for font in fonts]
+
+function mergeFonts(device, fonts)
+    (glyphs, firstGlyphs, curves) = @span "upload" mergeCurves(device, fonts)
+    boxes = NTuple{4, Float32}[]
+    for font in fonts, glyph in font.bufferGlyphs
+        ((x, y), (w, h)) = curveBox(view(font.bufferCurves, (glyph.start + 1):(glyph.start + glyph.count)))
+        push!(boxes, (x, y, x + w, y + h))
+    end
+    buffers = [
+        createStorageBuffer(device, "instanced glyph buffer", nonEmpty(glyphs)),
+        createStorageBuffer(device, "instanced curve buffer", nonEmpty(curves)),
+        createStorageBuffer(device, "instanced font buffer", nonEmpty(firstGlyphs)),
+        createStorageBuffer(device, "instanced box buffer", isempty(boxes) ? [(0f0, 0f0, 0f0, 0f0)] : boxes),
+    ]
+    return MergedFonts(buffers, [length(font.bufferGlyphs) for font in fonts], [length(font.bufferCurves) for font in fonts])
+end
+
+"""
+    prepare!(batch, projection; uniformOptions...)
+
+Uploads the instances pushed since the last `empty!`, and the merged font
+buffers when a font of the batch changed.
+"""
+function prepare!(batch::InstancedText, projection::Projection; transform=identityMat4, kwargs...)
+    renderer = batch.renderer
+    device = renderer.device
+    batch.instanceCount = length(batch.instances)
+    rebind = false
+    if batch.merged === nothing || !mergedCurrent(batch.merged, batch.fonts)
+        batch.merged = mergeFonts(device, batch.fonts)
+        rebind = true
+    end
+    isempty(batch.instances) || @span "upload" begin
+        capacity = batch.instanceCapacity
+        (batch.instanceBuffer, batch.instanceCapacity) = writeDynamic(
+            device, batch.instanceBuffer, batch.instanceCapacity, batch.instances, "instanced text instance buffer", ["Storage", "CopyDst"]
+        )
+        rebind |= batch.instanceCapacity != capacity
+    end
+    uniforms = [FontUniforms(projection; transform=transform, uniformOptions(renderer)..., kwargs...)]
+    (batch.uniformBuffer, _) = writeDynamic(
+        device, batch.uniformBuffer, sizeof(FontUniforms), uniforms, "instanced text uniform buffer", ["Uniform", "CopyDst"]
+    )
+    if (rebind || batch.bindGroup === nothing) && batch.instanceBuffer !== nothing
+        buffers = (batch.uniformBuffer, batch.merged.buffers..., batch.instanceBuffer)
+        batch.bindGroup = WGPUCore.createBindGroup(
+            "instanced text bind group", device,
+            batch.pipeline.bindGroupLayout,
+            [
+                WGPUCore.GPUBuffer => [
+                    :binding => i - 1,
+                    :buffer  => buffer,
+                    :offset  => 0,
+                    :size    => buffer.size
+                ] for (i, buffer) in enumerate(buffers)
+            ]
+        )
+    end
+    return batch
+end
+
+prepare!(batch::InstancedText, targetSize::Tuple; kwargs...) = prepare!(batch, orthographic(targetSize...); kwargs...)
+
+function draw!(batch::InstancedText, renderPass)
+    batch.instanceCount == 0 && return batch
+    @span "encode" withDebugGroup(renderPass, "instanced text") do
+        WGPUCore.setPipeline(renderPass, batch.pipeline.pipeline)
+        WGPUCore.setBindGroup(renderPass, 0, batch.bindGroup, UInt32[], 0, 99)
+        WGPUCore.draw(renderPass, 6; instanceCount=batch.instanceCount, firstVertex=0, firstInstance=0)
+    end
+    return batch
+end
@@ -0,0 +1,29 @@
+# Stage timings are logged as debug records of the `span` group, enable them with
+#
+#     ENV["JULIA_DEBUG"] = "WGPUFontRenderer"
+#
+# Debug groups show up in RenderDoc and Xcode captures around every text draw.
+
+macro span(stage, expr)
+    quote
+        local t0 = time_ns()
+        local result = $(esc(expr))
+        @debug "wgpu font renderer span" stage=$(esc(stage)) milliseconds=(time_ns() - t0)/1e6 _group=:span
+        result
+    end
+end
+
+pushDebugGroup(renderPass, label::AbstractString) =
+    WGPUCore.wgpuRenderPassEncoderPushDebugGroup(renderPass.internal[], label)
+
+popDebugGroup(renderPass) =
+    WGPUCore.wgpuRenderPassEncoderPopDebugGroup(renderPass.internal[])
+
+function withDebugGroup(f, renderPass, label::AbstractString)
+    pushDebugGroup(renderPass, label)
+    try
+        return f()
+    finally
+        popDebugGroup(renderPass)
+    end
+end
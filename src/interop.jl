@@ -0,0 +1,36 @@
+# Interop with external shaping and layout engines.
+# Applications that already shape and position their text keep that stack and
+# hand over positioned glyph ids per run, only rasterization goes through the
+# curve pipeline.
+
+struct RunGlyph
+    index::FT_UInt
+    x::Float32          # pen position in pixels
+    y::Float32          # baseline in pixels, y grows downwards
+    cluster::Int
+end
+
+RunGlyph(index, x, y) = RunGlyph(index, x, y, 0)
+
+struct GlyphRun
+    style::TextStyle
+    glyphs::Vector{RunGlyph}
+end
+
+function layoutFromRuns(runs)
+    positioned = PositionedGlyph[]
+    width = 0f0
+    height = 0f0
+    for run in runs
+        style = run.style
+        font = style.font
+        scale = pixelScale(style)
+        for rg in run.glyphs
+            glyph = prepareGlyph(font, rg.index)
+            push!(positioned, PositionedGlyph(glyph, font, rg.x, rg.y, style.size, style.color, rg.cluster))
+            width = max(width, rg.x + glyph.advance*scale)
+            height = max(height, rg.y - font.metrics.descender*scale)
+        end
+    end
+    return TextLayout(positioned, width, height)
+end
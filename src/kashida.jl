@@ -0,0 +1,76 @@
+# Kashida justification of Arabic lines.
+# Justified lines elongate joins with a tatweel (U+0640) stretched to the
+# slack instead of only widening the spaces. Every word gets one kashida at
+# its best join: after seen and sad, before a final heh or taa marbuta,
+# before a final reh or waw, else after any letter joining the next one.
+# Words without a join leave their share to the spaces.
+
+const tatweel = 'ـ'
+const kashidaAfter = Set("سشصض")
+const kashidaBeforeFinal = (Set("هةتث"), Set("رزوؤ"))
+# stretch of a single kashida in em, anything beyond goes to the spaces
+const maxKashida = 1.5f0
+
+# Priority of the join between letters `a` and `b`, lower is better.
+function kashidaPriority(a::Char, b::Char, bIsFinal)
+    a in kashidaAfter && return 1
+    bIsFinal && b in kashidaBeforeFinal[1] && return 2
+    bIsFinal && b in kashidaBeforeFinal[2] && return 3
+    return 4
+end
+
+# Indices of `line` after which a kashida goes, one per word.
+function kashidaPositions(line, text; clusterOffset=0)
+    charAt(k) = text[line[k][1].cluster - clusterOffset]
+    positions = Int[]
+    best = nothing      # (priority, index) in the current word
+    previous = nothing  # (index of the last glyph of the letter, letter)
+    for k in eachindex(line)
+        chr = charAt(k)
+        if isWordSeparator(chr)
+            best === nothing || push!(positions, best[2])
+            (best, previous) = (nothing, nothing)
+            continue
+        end
+        type = joiningType(chr)
+        if type == :T
+            previous === nothing || (previous = (k, previous[2]))
+            continue
+        end
+        if previous !== nothing && joiningType(previous[2]) == :D && type in (:D, :R) && previous[2] != tatweel
+            next = findfirst(j -> joiningType(charAt(j)) != :T, (k + 1):lastindex(line))
+            isFinal = type == :R || next === nothing || !(joiningType(charAt(k + next)) in (:D, :R, :C))
+            candidate = (kashidaPriority(previous[2], chr, isFinal), previous[1])
+            (best === nothing || candidate[1] < best[1]) && (best = candidate)
+        end
+        previous = (k, chr)
+    end
+    best === nothing || push!(positions, best[2])
+    return positions
+end
+
+# `line` with kashidas taking up to all of `slack` pixels, and the slack left.
+function insertKashidas(line, style::TextStyle, text, slack; clusterOffset=0)
+    font = style.font
+    glyphIdx = glyphIndex(font, tatweel)
+    glyphIdx == 0 && return (line, slack)
+    positions = kashidaPositions(line, text; clusterOffset=clusterOffset)
+    isempty(positions) && return (line, slack)
+    tatweelAdvance = prepareGlyph(font, glyphIdx).advance
+    stretched = line[1:0]
+    used = 0f0
+    next = 1
+    for (k, g) in enumerate(line)
+        push!(stretched, g)
+        next <= length(positions) && positions[next] == k || continue
+        next += 1
+        (s, size, _, glyphTransform) = g
+        width = tatweelAdvance*size/font.emSize
+        extra = min(slack/length(positions), maxKashida*size)
+        width > 0 && extra > 0 || continue
+        kashida = ShapedGlyph(glyphIdx, s.cluster, tatweelAdvance, 0, 0)
+        push!(stretched, (kashida, size, extra, glyphTransform*scaling(extra/width, 1)))
+        used += extra
+    end
+    return (stretched, slack - used)
+end
@@ -0,0 +1,217 @@
+# Many small labels, e.g. tick marks or scatter plot annotations.
+#
+#     batch = LabelBatch(renderer, style)
+#     for (x, value) in ticks
+#         push!(batch, string(value), (x, 580); anchor=(0.5, 0))
+#     end
+#     prepare!(batch, surface.size)
+#     draw!(batch, renderPass)
+#
+# Every distinct string is shaped once and kept as a template of glyph
+# quads, labels only place a template. Labels outside the viewport are
+# culled on the cpu, the glyphs of all others go into one instance buffer
+# drawn with a single instanced draw. Templates are axis aligned, glyph
+# transforms such as obliques are not applied.
+
+# Layout of `QuadInstance` in font.wgsl.
+struct QuadInstance
+    x0::Float32
+    y0::Float32
+    x1::Float32
+    y1::Float32
+    u0::Float32
+    v0::Float32
+    u1::Float32
+    v1::Float32
+    bufferIndex::Int32
+    color::UInt32       # rgba8, red in the lowest byte
+end
+
+function getVertexBufferLayout(::Type{QuadInstance}; offset=0)
+    WGPUCore.GPUVertexBufferLayout => [
+        :arrayStride => sizeof(QuadInstance),
+        :stepMode => "Instance",
+        :attributes => [
+            :attribute => [
+                :format => "Float32x4",
+                :offset => fieldoffset(QuadInstance, 1),
+                :shaderLocation => offset + 0
+            ],
+            :attribute => [
+                :format => "Float32x4",
+                :offset => fieldoffset(QuadInstance, 5),
+                :shaderLocation => offset + 1
+            ],
+            :attribute => [
+                :format => "Sint32",
+                :offset => fieldoffset(QuadInstance, 9),
+                :shaderLocation => offset + 2
+            ],
+            :attribute => [
+                :format => "Unorm8x4",
+                :offset => fieldoffset(QuadInstance, 10),
+                :shaderLocation => offset + 3
+            ],
+        ]
+    ]
+end
+
+# Glyph quads of one string relative to the top left corner of its box.
+struct LabelTemplate
+    quads::Vector{QuadInstance}
+    width::Float32
+    height::Float32
+end
+
+struct BatchedLabel
+    template::Int32
+    x::Float32          # top left corner
+    y::Float32
+    color::UInt32
+end
+
+mutable struct LabelBatch
+    renderer::TextRenderer
+    style::TextStyle
+    pipeline::FontPipeline
+    templates::Vector{LabelTemplate}
+    templateIndex::Dict{String, Int32}
+    # templates are dropped all at once past this many strings
+    maxTemplates::Int
+    labels::Vector{BatchedLabel}
+    instances::Vector{QuadInstance}
+    instanceBuffer
+    instanceCapacity::Int
+    instanceCount::Int
+    uniformBuffer
+    bindGroup
+end
+
+"""
+    LabelBatch(renderer, style; maxTemplates=65536)
+
+Labels of `style` drawn with the pipeline layout of `renderer`, so they
+take its font buffers and bind groups.
+"""
+function LabelBatch(renderer::TextRenderer, style::TextStyle; maxTemplates=65536)
+    requireCurvesPath(renderer, "LabelBatch")
+    pipeline = rendererPipeline(
+        renderer;
+        layouts=pipelineLayouts(renderer.pipeline),
+        label="label batch",
+        vertexEntryPoint="vs_instanced",
+        vertexBuffers=[getVertexBufferLayout(QuadInstance)]
+    )
+    return LabelBatch(
+        renderer, resolvedStyle(style), pipeline, LabelTemplate[], Dict{String, Int32}(), maxTemplates,
+        BatchedLabel[], QuadInstance[], nothing, 0, 0, nothing, nothing
+    )
+end
+
+function labelTemplate(style::TextStyle, text::String)
+    layout = layoutText(text, style)
+    quads = QuadInstance[]
+    for pg in layout.glyphs
+        pg.glyph.curveCount == 0 && continue
+        (x0, y0, x1, y1, u0, v0, u1, v1) = glyphQuad(pg)
+        push!(quads, QuadInstance(x0, y0, x1, y1, u0, v0, u1, v1, pg.glyph.bufferIndex, 0))
+    end
+    return LabelTemplate(quads, layout.width, layout.height)
+end
+
+# Templates of label batches and text pools, keyed on the validated text so
+# invalid UTF-8 is shaped once too.
+function templateFor(owner, text::AbstractString)
+    text = validText(text)
+    index = get(owner.templateIndex, text, nothing)
+    index === nothing || return index
+    push!(owner.templates, @span "shaping" labelTemplate(owner.style, text))
+    return owner.templateIndex[text] = Int32(length(owner.templates))
+end
+
+"""
+    push!(batch, text, position; color=style.color, anchor=(0, 0))
+
+Adds a label for this frame. `anchor` is the point of the label's box at
+`position`, in fractions of its size, e.g. (0.5, 0) for the top center.
+"""
+function Base.push!(batch::LabelBatch, text::AbstractString, position; color=nothing, anchor=(0f0, 0f0))
+    index = templateFor(batch, text)
+    template = batch.templates[index]
+    packed = packColor(something(color, batch.style.color))
+    (x, y) = position
+    push!(batch.labels, BatchedLabel(index, x - anchor[1]*template.width, y - anchor[2]*template.height, packed))
+    return batch
+end
+
+# Starts the next frame, templates stay unless there are too many.
+function Base.empty!(batch::LabelBatch)
+    empty!(batch.labels)
+    if length(batch.templates) > batch.maxTemplates
+        empty!(batch.templates)
+        empty!(batch.templateIndex)
+    end
+    return batch
+end
+
+Base.length(batch::LabelBatch) = length(batch.labels)
+
+# Glyph instances of the labels whose box meets `viewport`, (x, y, width, height).
+function batchInstances!(instances, batch::LabelBatch, viewport)
+    empty!(instances)
+    (vx0, vy0, vx1, vy1) = viewport === nothing ? (-Inf32, -Inf32, Inf32, Inf32) :
+        (viewport[1], viewport[2], viewport[1] + viewport[3], viewport[2] + viewport[4])
+    templates = batch.templates
+    for label in batch.labels
+        template = templates[label.template]
+        (x, y) = (label.x, label.y)
+        (x > vx1 || y > vy1 || x + template.width < vx0 || y + template.height < vy0) && continue
+        for q in template.quads
+            push!(instances, QuadInstance(q.x0 + x, q.y0 + y, q.x1 + x, q.y1 + y, q.u0, q.v0, q.u1, q.v1, q.bufferIndex, label.color))
+        end
+    end
+    return instances
+end
+
+"""
+    prepare!(batch, projection; viewport=nothing, uniformOptions...)
+
+Uploads the glyphs of the labels pushed since the last `empty!`, culled
+against `viewport` (x, y, width, height) in layout pixels when given.
+"""
+function prepare!(batch::LabelBatch, projection::Projection; viewport=nothing, transform=identityMat4, kwargs...)
+    renderer = batch.renderer
+    device = renderer.device
+    instances = @span "layout" batchInstances!(batch.instances, batch, viewport)
+    batch.instanceCount = length(instances)
+    fontBuffers = fontBuffersFor(renderer, batch.style.font)
+    chunkCount(fontBuffers) == 1 || throw(FontRenderError(
+        deviceLimitError,
+        "$(fontLabel(fontBuffers.font)) needs $(chunkCount(fontBuffers)) curve bindings, label batches draw from one"
+    ))
+    isempty(instances) || @span "upload" begin
+        (batch.instanceBuffer, batch.instanceCapacity) = writeDynamic(
+            device, batch.instanceBuffer, batch.instanceCapacity, instances, "label batch instance buffer", ["Vertex", "CopyDst"]
+        )
+    end
+    uniforms = [FontUniforms(projection; transform=transform, uniformOptions(renderer)..., kwargs...)]
+    (batch.uniformBuffer, _) = writeDynamic(
+        device, batch.uniformBuffer, sizeof(FontUniforms), uniforms, "label batch uniform buffer", ["Uniform", "CopyDst"]
+    )
+    batch.bindGroup = cachedBindGroup(renderer, fontBuffers, batch.uniformBuffer, 1)
+    return batch
+end
+
+prepare!(batch::LabelBatch, targetSize::Tuple; kwargs...) =
+    prepare!(batch, orthographic(targetSize...); viewport=(0f0, 0f0, Float32.(targetSize)...), kwargs...)
+
+function draw!(batch::LabelBatch, renderPass)
+    batch.instanceCount == 0 && return batch
+    @span "encode" withDebugGroup(renderPass, "label batch") do
+        WGPUCore.setPipeline(renderPass, batch.pipeline.pipeline)
+        WGPUCore.setVertexBuffer(renderPass, 0, batch.instanceBuffer)
+        WGPUCore.setBindGroup(renderPass, 0, batch.bindGroup, UInt32[], 0, 99)
+        WGPUCore.draw(renderPass, 6; instanceCount=batch.instanceCount, firstVertex=0, firstInstance=0)
+    end
+    return batch
+end
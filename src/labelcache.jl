@@ -0,0 +1,126 @@
+# Static label cache.
+# Text that never changes, like axis titles or hud labels, is rendered once
+# into a texture of its own and drawn as a single textured quad afterwards.
+# Labels are keyed by the section's text, style, wrapping, halo and
+# highlights, the scale and the color encoding, so a changed style or dpi
+# scale renders a new texture instead of stretching the old one.
+# Labels not drawn for `labelStaleFrames` prepares are released.
+#
+#     queueStatic!(renderer, "Temperature [K]", (16, 16), style; scale=2)
+
+const labelFormat = WGPUCore.WGPUTextureFormat_RGBA8Unorm
+const labelPadding = 2          # texels around the layout box, for overhanging glyphs
+const labelStaleFrames = 600
+
+mutable struct CachedLabel
+    texture
+    view
+    sampler
+    width::Float32              # layout pixels covered by the quad
+    height::Float32
+    padding::Float32            # layout pixels of the texel padding
+    lastUse::Int
+    bindGroups::IdDict{Any, Any}    # per uniform buffer
+end
+
+# Everything of a section but its position, with the scale and color encoding.
+const LabelKey = Tuple{String, TextStyle, Float32, TextAlign, Union{Nothing, Halo}, Vector{HighlightSpan}, Float32, Bool}
+
+labelKey(section, scale, linear) =
+    (section.text, section.style, section.maxWidth, section.align, section.halo, section.highlights, scale, linear)
+
+mutable struct LabelCache
+    labels::Dict{LabelKey, CachedLabel}
+    pipeline::Union{Nothing, FontPipeline}  # curve pipeline drawing into label textures
+    frame::Int
+end
+
+LabelCache() = LabelCache(Dict{LabelKey, CachedLabel}(), nothing, 0)
+
+# `section` in texels, placed after the padding.
+function scaledSection(section, scale)
+    halo = section.halo === nothing ? nothing : setfields(section.halo; width=section.halo.width*scale)
+    return setfields(
+        section;
+        position=(Float32(labelPadding), Float32(labelPadding)),
+        style=setfields(section.style; size=section.style.size*scale),
+        maxWidth=section.maxWidth*scale,
+        halo=halo
+    )
+end
+
+function renderLabel(renderer, section, scale::Float32, linear::Bool)
+    device = renderer.device
+    cache = renderer.labelCache
+    cache.pipeline === nothing && (cache.pipeline = createFontPipeline(device, labelFormat; label="label"))
+    scaled = scaledSection(section, scale)
+    layout = finishLayout(scaled, layoutSection(scaled))
+    (width, height) = (ceil(Int, layout.width), ceil(Int, layout.height)) .+ 2labelPadding
+    texture = WGPUCore.createTexture(
+        device, "static label",
+        (width, height, 1),
+        1, 1,
+        WGPUCore.WGPUTextureDimension_2D,
+        labelFormat,
+        WGPUCore.getEnum(WGPUCore.WGPUTextureUsage, ["RenderAttachment", "TextureBinding"])
+    )
+    # colors are stored the way the target expects them, the quad copies texels through
+    textDraws = prepareChunkedText(
+        cache.pipeline, font -> fontBuffersFor(renderer, font), layout, orthographic(width, height);
+        linearizeColors=linear
+    )
+    @span "label" begin
+        encoder = WGPUCore.createCommandEncoder(device, "static label encoder")
+        renderPass = WGPUCore.beginRenderPass(
+            encoder,
+            colorAttachmentOptions(WGPUCore.createView(texture), (0.0, 0.0, 0.0, 0.0)) |> Ref;
+            label="static label pass"
+        )
+        drawText(renderPass, cache.pipeline, textDraws)
+        WGPUCore.endEncoder(renderPass)
+        WGPUCore.submit(device.queue, [WGPUCore.finish(encoder),])
+    end
+    return CachedLabel(
+        texture, createArrayView(texture), createAtlasSampler(device),
+        width/scale, height/scale, labelPadding/scale,
+        cache.frame,
+        IdDict{Any, Any}()
+    )
+end
+
+function cachedLabel(target, section, scale)
+    renderer = sharedRenderer(target)
+    cache = renderer.labelCache
+    linear = linearizeColors(target.options)
+    label = get!(cache.labels, labelKey(section, scale, linear)) do
+        renderLabel(renderer, section, scale, linear)
+    end
+    label.lastUse = cache.frame
+    return label
+end
+
+function appendLabelQuad!(vertices::Vector{BufferVertex}, indices::Vector{UInt32}, label::CachedLabel, (x, y))
+    base = UInt32(length(vertices))
+    color = packColor((1f0, 1f0, 1f0, 1f0))
+    (x, y) = (x, y) .- label.padding
+    for (dx, dy, u, v) in ((0, 0, 0, 0), (1, 0, 1, 0), (1, 1, 1, 1), (0, 1, 0, 1))
+        push!(vertices, BufferVertex(x + dx*label.width, y + dy*label.height, u, v, 0, 0, 0, colorBitmapFlag, color))
+    end
+    append!(indices, base .+ UInt32[0, 1, 2, 2, 3, 0])
+end
+
+function labelBindGroup(target, label::CachedLabel, uniformBuffer)
+    renderer = sharedRenderer(target)
+    get!(label.bindGroups, uniformBuffer) do
+        WGPUCore.createBindGroup(
+            "static label bind group", renderer.device,
+            pipelineVariant(renderer, target.options; kind=:color).bindGroupLayout,
+            getAtlasBindings(label, uniformBuffer)
+        )
+    end
+end
+
+function dropStaleLabels!(cache::LabelCache)
+    filter!(((_, label),) -> label.lastUse >= cache.frame - labelStaleFrames, cache.labels)
+    return cache
+end
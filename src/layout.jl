@@ -0,0 +1,396 @@
+# Layout positions shaped glyphs in pixel space.
+# x grows to the right and y grows downwards, y of a glyph is its baseline.
+
+struct PositionedGlyph
+    glyph::Glyph
+    font::FontFace
+    x::Float32
+    y::Float32
+    size::Float32
+    color::NTuple{4, Float32}
+    cluster::Int
+    # maps glyph local pixel offsets (relative to the pen position) into layout space
+    transform::Affine2
+    animation::GlyphAnimation
+end
+
+PositionedGlyph(glyph, font, x, y, size, color, cluster) =
+    PositionedGlyph(glyph, font, x, y, size, color, cluster, identityAffine2, noAnimation)
+
+PositionedGlyph(glyph, font, x, y, size, color, cluster, transform::Affine2) =
+    PositionedGlyph(glyph, font, x, y, size, color, cluster, transform, noAnimation)
+
+withTransform(pg::PositionedGlyph, transform::Affine2) = setfields(pg; transform=transform)
+
+struct TextLayout
+    glyphs::Vector{PositionedGlyph}
+    width::Float32
+    height::Float32
+end
+
+# Synthesized small caps are widened a little and tracked apart so they do
+# not look lighter than the lowercase around them.
+const smallCapsWidening = 1.06f0
+const smallCapsTracking = 0.04f0    # em of the small cap size
+
+# Shapes `text` with the features of `style` into (shaped glyph, pixel size,
+# pixel advance, glyph transform) tuples. Synthesized small caps and super- or
+# subscripts show up as smaller sizes, offsets and horizontal scales, oblique
+# styles as a shear in every glyph transform, monospace styles as advances
+# fixed to whole cells.
+function shapeStyled(style::TextStyle, text::AbstractString; clusterOffset=0)
+    scale = pixelScale(style)
+    cased = caseMapping(style, text)
+    synthetic = syntheticCaps(style)
+    capitals = synthetic === nothing ? cased : (idx, chr) -> (c = cased(idx, chr); synthetic(c) ? uppercase(c) : c)
+    mapChar(idx, chr) = isControlCharacter(chr) ? controlGlyph(style, chr) : notdefGlyph(style, capitals(idx, chr))
+    shaped = shapeText(
+        style.font, text;
+        clusterOffset=clusterOffset, features=styleFeatures(style), mapChar=mapChar,
+        script=openTypeScript(style.language, text), language=openTypeLanguage(style.language)
+    )
+    (sizeScale, widthScale, xOffset, yOffset) = syntheticPosition(style)
+    oblique = obliqueTransform(style)
+    synthetic === nothing && sizeScale == widthScale == 1 &&
+        return monospaced(style, [(s, style.size, s.xAdvance*scale, oblique) for s in shaped], text; clusterOffset=clusterOffset)
+    capScale = synthetic === nothing ? 1f0 : smallCapsScale(style.font)
+    glyphs = map(shaped) do s
+        idx = s.cluster - clusterOffset
+        small = synthetic !== nothing && synthetic(cased(idx, text[idx]))
+        glyphScale = sizeScale*(small ? capScale : 1f0)
+        widening = widthScale/sizeScale*(small ? smallCapsWidening : 1f0)
+        size = style.size*glyphScale
+        advance = s.xAdvance*scale*glyphScale*widening + (small ? smallCapsTracking*size : 0f0)
+        s = ShapedGlyph(s.index, s.cluster, s.xAdvance, s.xOffset + xOffset, s.yOffset + yOffset)
+        (s, size, advance, widening == 1 ? oblique : oblique*scaling(widening, 1))
+    end
+    return monospaced(style, glyphs, text; clusterOffset=clusterOffset)
+end
+
+# Pushes the glyphs of `line` with the pen starting at `x` on baseline `y`,
+# returns the pen position after the line.
+function placeLine!(positioned, style::TextStyle, line, x, y; clusterOffset=0)
+    shapedLine = @span "shaping" shapeStyled(style, line; clusterOffset=clusterOffset)
+    return placeShaped!(positioned, style, shapedLine, x, y)
+end
+
+# `placeLine!` for glyphs shaped with `shapeStyled`.
+function placeShaped!(positioned, style::TextStyle, shapedLine, x, y)
+    font = style.font
+    scale = pixelScale(style)
+    for (shaped, size, advance, glyphTransform) in shapedLine
+        glyph = prepareGlyph(font, shaped.index)
+        push!(
+            positioned,
+            PositionedGlyph(
+                glyph, font,
+                x + shaped.xOffset*scale,
+                y - shaped.yOffset*scale,
+                size,
+                style.color,
+                shaped.cluster,
+                glyphTransform
+            )
+        )
+        x += advance
+    end
+    return x
+end
+
+const softHyphen = '\uad'
+
+# Glyph drawn where a line breaks at a soft hyphen.
+hyphenGlyph(font::FontFace) = prepareGlyph(font, glyphIndex(font, '-'))
+
+# Splits the shaped glyphs of a paragraph into lines no wider than
+# `maxWidth`, breaking at the `lineBreaks` of `text` greedily or, for styles
+# with `optimalBreaking`, by total fit (see totalfit.jl). Spaces at the
+# end of a broken line are dropped, soft hyphens vanish unless a line breaks
+# after one and then turn into a hyphen. Words longer than a line overflow.
+# Lines come with true if they were wrapped rather than ended by the text.
+function wrapLines(shaped, style::TextStyle, text, maxWidth; clusterOffset=0)
+    charAt(k) = text[shaped[k][1].cluster - clusterOffset]
+    isSoftHyphen(k) = charAt(k) == softHyphen
+    advanceOf(k) = isSoftHyphen(k) ? 0f0 : shaped[k][3]
+    hyphenAdvance(k) = hyphenGlyph(style.font).advance*shaped[k][2]/style.font.emSize
+
+    opportunities = Dict(lineBreaks(text; strictness=style.lineBreak))
+    breaks = Dict{Int, Bool}()
+    for (k, (s, _, _, _)) in enumerate(shaped)
+        # a ligature spans several characters, break only between glyphs
+        k > 1 && s.cluster == shaped[k - 1][1].cluster && continue
+        opportunity = get(opportunities, s.cluster - clusterOffset, nothing)
+        opportunity === nothing || (breaks[k] = opportunity)
+    end
+
+    function finishLine(range, broken)
+        stop = last(range)
+        while broken && stop >= first(range) && isspace(charAt(stop))
+            stop -= 1
+        end
+        line = eltype(shaped)[]
+        for k in first(range):stop
+            if isSoftHyphen(k)
+                broken && k == stop || continue
+                (s, size, _, t) = shaped[k]
+                hyphen = ShapedGlyph(hyphenGlyph(style.font).index, s.cluster, hyphenGlyph(style.font).advance, s.xOffset, s.yOffset)
+                push!(line, (hyphen, size, hyphenAdvance(k), t))
+            else
+                push!(line, shaped[k])
+            end
+        end
+        return line
+    end
+
+    if style.lineBreaker == optimalBreaking
+        starts = optimalBreaks(
+            length(shaped), breaks, maxWidth;
+            advanceOf=advanceOf, isSpace=k -> isspace(charAt(k)), isGlue=k -> isWordSeparator(charAt(k)),
+            hyphenAdvance=k -> isSoftHyphen(k) ? hyphenAdvance(k) : 0f0
+        )
+        lines = Tuple{Vector{eltype(shaped)}, Bool}[]
+        for (a, (b, mandatory)) in zip([1; first.(starts)], starts)
+            push!(lines, (finishLine(a:(b - 1), true), !mandatory))
+        end
+        push!(lines, (finishLine((isempty(starts) ? 1 : starts[end][1]):lastindex(shaped), false), false))
+        return lines
+    end
+
+    lines = Tuple{Vector{eltype(shaped)}, Bool}[]
+    (start, lastBreak, width, visible) = (1, 0, 0f0, 0f0)
+    for k in eachindex(shaped)
+        opportunity = get(breaks, k, nothing)
+        if opportunity === true
+            push!(lines, (finishLine(start:(k - 1), true), false))
+            (start, lastBreak, width, visible) = (k, 0, 0f0, 0f0)
+        elseif opportunity === false && k > start
+            # breaking after a soft hyphen has to leave room for the hyphen
+            fits = !isSoftHyphen(k - 1) || visible + hyphenAdvance(k - 1) <= maxWidth
+            fits && (lastBreak = k)
+        end
+        width += advanceOf(k)
+        isspace(charAt(k)) || (visible = width)
+        if visible > maxWidth && lastBreak > start
+            push!(lines, (finishLine(start:(lastBreak - 1), true), true))
+            start = lastBreak
+            lastBreak = 0
+            width = sum(advanceOf, start:k; init=0f0)
+            visibleEnd = findlast(j -> !isspace(charAt(j)), start:k)
+            visible = visibleEnd === nothing ? 0f0 : sum(advanceOf, start:(start + visibleEnd - 1); init=0f0)
+        end
+    end
+    push!(lines, (finishLine(start:lastindex(shaped), false), false))
+    return lines
+end
+
+@enum TextAlign alignLeft alignCenter alignRight alignJustify
+
+# Word separators stretch in justified lines, the no break space included.
+# Fixed width spaces, like the narrow no break space French sets before
+# ; ! ? and inside guillemets, keep their width.
+isWordSeparator(chr) = chr == ' ' || chr == '\ua0'
+
+# Pen offset of a line within `maxWidth` and its glyphs. Justified lines take
+# kashidas first when the style asks for them, then widen word separators by
+# up to `maxWordSpacing` and track letters apart by up to `maxLetterSpacing`;
+# what is left goes to the separators, single words stay short of the margin.
+function alignLine(line, style::TextStyle, text, maxWidth, align::TextAlign, wrapped; clusterOffset=0)
+    slack = maxWidth - sum(g -> g[3], line; init=0f0)
+    (isfinite(maxWidth) && slack > 0) || return (0f0, line)
+    align == alignCenter && return (slack/2, line)
+    align == alignRight && return (slack, line)
+    (align == alignJustify && (wrapped || style.justifyLastLine)) || return (0f0, line)
+    style.kashida && ((line, slack) = insertKashidas(line, style, text, slack; clusterOffset=clusterOffset))
+    slack > 0 || return (0f0, line)
+    charOf(g) = text[g[1].cluster - clusterOffset]
+    stretches(g) = isWordSeparator(charOf(g))
+    # joining scripts would come apart, fixed width spaces keep their distance
+    tracks(k) = k < lastindex(line) && !isspace(charOf(line[k])) && !isspace(charOf(line[k + 1])) &&
+        joiningType(charOf(line[k])) == :U && line[k][1].cluster != line[k + 1][1].cluster
+    separators = count(stretches, line)
+    gaps = count(tracks, eachindex(line))
+    wordShare = min(slack, separators*style.maxWordSpacing*style.size)
+    letterShare = min(slack - wordShare, gaps*style.maxLetterSpacing*style.size)
+    separators > 0 && (wordShare = slack - letterShare)
+    (wordShare > 0 || letterShare > 0) || return (0f0, line)
+    return (0f0, map(eachindex(line)) do k
+        (s, size, advance, t) = line[k]
+        stretches(line[k]) && return (s, size, advance + wordShare/separators, t)
+        tracks(k) && return (s, size, advance + letterShare/gaps, t)
+        line[k]
+    end)
+end
+
+"""
+    layoutText(text, style; origin, transform, maxWidth=Inf32, align=alignLeft)
+
+Lines start at explicit newlines and, when the layout feature is on, wherever
+the next word would cross `maxWidth` pixels, see `lineBreaks`. `align` places
+lines within `maxWidth`; justified lines stretch their spaces, except the
+last line of a paragraph unless the style has `justifyLastLine`. Every
+U+FFFC of `text` reserves room for the next of `objects`, see
+`inlineObjectBoxes`. Styles with `verticalRightToLeft` set columns of
+`maxWidth` pixels instead.
+"""
+function layoutText(
+    text::TextInput, style::TextStyle;
+    origin=(0f0, 0f0), transform=nothing, maxWidth=Inf32, align::TextAlign=alignLeft, objects=InlineObject[]
+)
+    text = validText(text)
+    style = resolvedStyle(style)
+    style.writingMode == verticalRightToLeft &&
+        return layoutVertical(text, style; origin=origin, transform=transform, maxWidth=maxWidth, align=align)
+    font = style.font
+    scale = pixelScale(style)
+    lineAdvance = font.metrics.height*scale*style.lineHeight
+    positioned = PositionedGlyph[]
+    (x0, y0) = origin
+    y = y0 + font.metrics.ascender*scale
+    width = 0f0
+    objectAt = objectClusters(text, objects)
+    for paragraph in eachsplit(text, '\n')
+        shaped = @span "shaping" shapeStyled(style, paragraph; clusterOffset=paragraph.offset)
+        shaped = reserveObjects(shaped, objectAt)
+        lines = enableLayout && isfinite(maxWidth) ?
+            wrapLines(shaped, style, paragraph, maxWidth; clusterOffset=paragraph.offset) : [(shaped, false)]
+        for (line, wrapped) in lines
+            (offset, line) = alignLine(line, style, paragraph, maxWidth, align, wrapped; clusterOffset=paragraph.offset)
+            (above, below) = objectExtents(line, objectAt, font.metrics.ascender*scale, -font.metrics.descender*scale)
+            y += above
+            (above > 0 && style.gridOverflow == gridOffGrid) || (y = snapBaseline(style, y))
+            x = placeShaped!(positioned, style, line, x0 + offset, y)
+            width = max(width, x - x0)
+            y += lineAdvance + below
+        end
+    end
+    layout = TextLayout(positioned, width, y - y0 - font.metrics.ascender*scale)
+    return transform === nothing ? layout : transformLayout(layout, about(transform, origin))
+end
+
+# Runs of `text => style` pairs continuing each other's pen, e.g. a formula
+# with `positionSuper` exponents. Clusters index the concatenated text and
+# lines advance by the tallest style.
+function layoutSpans(spans; origin=(0f0, 0f0), transform=nothing)
+    spans = [validText(text) => resolvedStyle(style) for (text, style) in spans]
+    styles = map(last, spans)
+    ascent = maximum(style -> style.font.metrics.ascender*pixelScale(style), styles; init=0f0)
+    lineAdvance = maximum(style -> style.font.metrics.height*pixelScale(style)*style.lineHeight, styles; init=0f0)
+    positioned = PositionedGlyph[]
+    (x0, y0) = origin
+    (x, y) = (x0, y0 + ascent)
+    width = 0f0
+    nLines = 1
+    offset = 0
+    for (text, style) in spans
+        for (i, line) in enumerate(eachsplit(text, '\n'))
+            if i > 1
+                (x, y) = (x0, y + lineAdvance)
+                nLines += 1
+            end
+            x = placeLine!(positioned, style, line, x, y; clusterOffset=offset + line.offset)
+            width = max(width, x - x0)
+        end
+        offset += ncodeunits(text)
+    end
+    layout = TextLayout(positioned, width, nLines*lineAdvance)
+    return transform === nothing ? layout : transformLayout(layout, about(transform, origin))
+end
+
+# Maps `layoutItem` over `items`, on all threads when the parallel feature is on
+# and julia runs with several threads. `textOf(item)` returns `(style, text)`,
+# those glyphs are built up front so the workers only read the glyph caches.
+function mapLayouts(layoutItem, items, textOf)
+    (enableParallel && Threads.nthreads() > 1 && length(items) > 1) || return map(layoutItem, items)
+    for item in items
+        (style, text) = textOf(item)
+        resolved = resolvedStyle(style)
+        (resolved.writingMode == verticalRightToLeft ? shapeVertical : shapeStyled)(resolved, validText(text))
+        hyphenGlyph(resolved.font)
+    end
+    tasks = [Threads.@spawn layoutItem(item) for item in items]
+    return map(fetch, tasks)
+end
+
+# Independent paragraphs stacked from `origin`, e.g. a document reflowed on resize.
+function layoutParagraphs(paragraphs, style::TextStyle; origin=(0f0, 0f0), paragraphSpacing=0f0)
+    layouts = mapLayouts(text -> layoutText(text, style), paragraphs, text -> (style, text))
+    (x, y) = origin
+    placed = TextLayout[]
+    for layout in layouts
+        push!(placed, transformLayout(layout, translation(x, y)))
+        y += layout.height + paragraphSpacing
+    end
+    return placed
+end
+
+# Per run transform, pen positions move and every glyph picks up the linear part.
+function transformLayout(layout::TextLayout, transform::Affine2)
+    glyphs = map(layout.glyphs) do pg
+        (x, y) = transform*(pg.x, pg.y)
+        setfields(pg; x=x, y=y, transform=linearPart(transform)*pg.transform)
+    end
+    return TextLayout(glyphs, layout.width, layout.height)
+end
+
+# Per glyph overrides, `f(pg)` returns the transform applied around each pen position.
+function transformGlyphs(f, layout::TextLayout)
+    glyphs = map(pg -> withTransform(pg, pg.transform*f(pg)), layout.glyphs)
+    return TextLayout(glyphs, layout.width, layout.height)
+end
+
+# `f(pg)` returns the `GlyphAnimation` of every glyph.
+function animateGlyphs(f, layout::TextLayout)
+    glyphs = map(pg -> setfields(pg; animation=f(pg)), layout.glyphs)
+    return TextLayout(glyphs, layout.width, layout.height)
+end
+
+# Same effect on every glyph, started `delay` seconds after the previous one.
+function staggerAnimation(layout::TextLayout, flags; delay=0.05f0, amplitude=4f0)
+    counter = Ref(0)
+    animateGlyphs(layout) do pg
+        offset = counter[]*delay
+        counter[] += 1
+        GlyphAnimation(offset, amplitude, flags)
+    end
+end
+
+# Emits one quad per non empty glyph, dilated by a pixel so that
+# anti-aliased edges are not clipped.
+function glyphQuad(pg::PositionedGlyph)
+    glyph = pg.glyph
+    emSize = pg.font.emSize
+    d = emSize/pg.size
+    u0 = (glyph.bearingX - d)/emSize
+    v0 = (glyph.bearingY - glyph.height - d)/emSize
+    u1 = (glyph.bearingX + glyph.width + d)/emSize
+    v1 = (glyph.bearingY + d)/emSize
+    x0 = pg.x + u0*pg.size
+    x1 = pg.x + u1*pg.size
+    y0 = pg.y - v0*pg.size
+    y1 = pg.y - v1*pg.size
+    return (x0, y0, x1, y1, u0, v0, u1, v1)
+end
+
+packColor((r, g, b, a)) =
+    reduce(|, (UInt32(round(clamp(c, 0, 1)*255)) << (8*(i - 1)) for (i, c) in enumerate((r, g, b, a))))
+
+function appendVertices!(vertices::Vector{BufferVertex}, indices::Vector{UInt32}, layout::TextLayout)
+    for pg in layout.glyphs
+        pg.glyph.curveCount == 0 && continue
+        (x0, y0, x1, y1, u0, v0, u1, v1) = glyphQuad(pg)
+        base = UInt32(length(vertices))
+        idx = pg.glyph.bufferIndex
+        anim = pg.animation
+        color = packColor(pg.color)
+        corner(x, y) = (pg.x, pg.y) .+ pg.transform*(x - pg.x, y - pg.y)
+        vertex(x, y, u, v) = BufferVertex(corner(x, y)..., u, v, idx, anim.timeOffset, anim.amplitude, anim.flags, color)
+        push!(vertices, vertex(x0, y0, u0, v0))
+        push!(vertices, vertex(x1, y0, u1, v0))
+        push!(vertices, vertex(x1, y1, u1, v1))
+        push!(vertices, vertex(x0, y1, u0, v1))
+        append!(indices, base .+ UInt32[0, 1, 2, 2, 3, 0])
+    end
+    return (vertices, indices)
+end
+
+buildVertices(layout::TextLayout) = appendVertices!(BufferVertex[], UInt32[], layout)
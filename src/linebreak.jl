@@ -0,0 +1,124 @@
+# Line break opportunities after Unicode Standard Annex #14.
+# Characters get a reduced set of line breaking classes and the pair rules
+# LB4 to LB31 are evaluated between neighbours, with the class before a run
+# of spaces remembered for the rules that look across spaces. Classes that
+# need dictionaries (SA) break like letters, AI resolves to AL.
+#
+# Japanese and Chinese prose follows kinsoku shori on top: closing brackets,
+# 、。 and the NS marks never start a line, opening brackets never end one.
+# How strictly small kana and iteration marks are kept to the preceding
+# character is chosen like css line-break.
+
+@enum LineBreakStrictness begin
+    lineBreakLoose      # breaks before small kana, ー, iteration marks and 〜
+    lineBreakNormal     # breaks before small kana and ー only
+    lineBreakStrict     # none of them start a line
+end
+
+const breakClasses = Dict{Char, Symbol}(
+    '\n' => :BK, '\v' => :BK, '\f' => :BK, '\r' => :BK, '\u85' => :BK, '\u2028' => :BK, '\u2029' => :BK,
+    ' ' => :SP, '\u200b' => :ZW, '\u2060' => :WJ, '\ufeff' => :WJ, '\u200c' => :CM, '\u200d' => :CM,
+    '\ua0' => :GL, '\u202f' => :GL, '\u2007' => :GL, '\u2011' => :GL, '\u34f' => :GL,
+    '\t' => :BA, '\uad' => :BA, '\u2010' => :BA, '\u2013' => :BA, '|' => :BA, '\u3000' => :BA,
+    '-' => :HY, '\u2014' => :B2,
+    '(' => :OP, '[' => :OP, '{' => :OP, '\ua1' => :OP, '\ubf' => :OP, '\u201a' => :OP, '\u201e' => :OP,
+    ')' => :CP, ']' => :CP, '}' => :CL,
+    '"' => :QU, '\'' => :QU, '\uab' => :QU, '\ubb' => :QU,
+    '\u2018' => :QU, '\u2019' => :QU, '\u201c' => :QU, '\u201d' => :QU, '\u2039' => :QU, '\u203a' => :QU,
+    '!' => :EX, '?' => :EX, '\uff01' => :EX, '\uff1f' => :EX,
+    ',' => :IS, '.' => :IS, ':' => :IS, ';' => :IS, '\u37e' => :IS, '\u589' => :IS, '\u60c' => :IS,
+    '/' => :SY, '\ufffc' => :CB,
+    '$' => :PR, '+' => :PR, '\\' => :PR, '#' => :PR, '\ua3' => :PR, '\ua5' => :PR, '\u20ac' => :PR,
+    '%' => :PO, '\ua2' => :PO, '\ub0' => :PO, '\u2030' => :PO,
+    '\u30fb' => :NS, '\uff1a' => :NS, '\uff1b' => :NS, '\uff65' => :NS, '\u203c' => :NS, '\u2047' => :NS,
+    '\u2048' => :NS, '\u2049' => :NS, '\u309b' => :NS, '\u309c' => :NS, '\u30a0' => :NS,
+)
+
+# Small kana and the prolonged sound mark (CJ), NS only in strict mode.
+const conditionalJapanese = Set(
+    "ぁぃぅぇぉっゃゅょゎゕゖァィゥェォッャュョヮヵヶㇰㇱㇲㇳㇴㇵㇶㇷㇸㇹㇺㇻㇼㇽㇾㇿーｧｨｩｪｫｬｭｮｯｰ"
+)
+# Iteration marks and the wave dash, NS except in loose mode.
+const looseStarters = Set("々〻ゝゞヽヾ〃〜〳〴〵")
+
+# CJK punctuation pairs of opening and closing brackets.
+const cjkOpening = "〈《「『【〔〖〘〚（［｛"
+const cjkClosing = "、。〉》」』】〕〗〙〛），．］｝"
+
+function lineBreakClass(chr::Char, strictness::LineBreakStrictness=lineBreakNormal)
+    # malformed input breaks like letters, see `validText`
+    isvalid(chr) || return :AL
+    chr in conditionalJapanese && return strictness == lineBreakStrict ? :NS : :ID
+    chr in looseStarters && return strictness == lineBreakLoose ? :ID : :NS
+    class = get(breakClasses, chr, nothing)
+    class === nothing || return class
+    chr in cjkOpening && return :OP
+    chr in cjkClosing && return :CL
+    c = UInt32(chr)
+    # spaces of fixed width other than the no break ones allow breaks after them
+    (0x2000 <= c <= 0x200a || c == 0x205f || c == 0x1680) && return :BA
+    Base.Unicode.category_abbrev(chr) in ("Mn", "Mc", "Me") && return :CM
+    Base.Unicode.category_abbrev(chr) == "Nd" && return :NU
+    (0x2e80 <= c <= 0x9fff || 0xac00 <= c <= 0xd7a3 || 0xf900 <= c <= 0xfaff ||
+        0xff00 <= c <= 0xffef || 0x20000 <= c <= 0x3fffd || 0x1f300 <= c <= 0x1faff ||
+        0x2600 <= c <= 0x27bf) && return :ID
+    return :AL
+end
+
+# Pairs that never break: LB23 to LB25 (numbers and affixes) and LB28 to LB30.
+const noBreakPairs = Set([
+    (:AL, :NU), (:NU, :AL), (:PR, :ID), (:ID, :PO), (:PR, :AL), (:PO, :AL), (:AL, :PR), (:AL, :PO),
+    (:CL, :PO), (:CP, :PO), (:CL, :PR), (:CP, :PR), (:NU, :PO), (:NU, :PR), (:PO, :OP), (:PO, :NU),
+    (:PR, :OP), (:PR, :NU), (:HY, :NU), (:IS, :NU), (:NU, :NU), (:SY, :NU),
+    (:AL, :AL), (:IS, :AL), (:AL, :OP), (:NU, :OP), (:CP, :AL), (:CP, :NU),
+])
+
+# Break before `b` following `a`, with `beforeSpaces` the class in front of
+# the spaces ending at `a`; `nothing` for no break, true if mandatory.
+function pairBreak(a, b, beforeSpaces)
+    a == :BK && return true
+    b in (:BK, :SP, :ZW) && return nothing
+    beforeSpaces == :ZW && return false
+    b == :CM && return nothing
+    (a == :WJ || b == :WJ) && return nothing
+    a == :GL && return nothing
+    b == :GL && !(a in (:SP, :BA, :HY)) && return nothing
+    b in (:CL, :CP, :EX, :IS, :SY) && return nothing
+    beforeSpaces == :OP && return nothing
+    beforeSpaces == :QU && b == :OP && return nothing
+    beforeSpaces in (:CL, :CP) && b == :NS && return nothing
+    beforeSpaces == :B2 && b == :B2 && return nothing
+    a == :SP && return false
+    (a == :QU || b == :QU) && return nothing
+    (a == :CB || b == :CB) && return false
+    b in (:BA, :HY, :NS) && return nothing
+    (a, b) in noBreakPairs && return nothing
+    return false
+end
+
+"""
+    lineBreaks(text; strictness=lineBreakNormal) -> Vector{Tuple{Int, Bool}}
+
+String indices a new line may start at, paired with true where the break is
+mandatory. Combining marks take the class of their base (LB9 and LB10).
+`strictness` selects the kinsoku rules for small kana and iteration marks.
+"""
+function lineBreaks(text::AbstractString; strictness::LineBreakStrictness=lineBreakNormal)
+    breaks = Tuple{Int, Bool}[]
+    previous = nothing
+    beforeSpaces = nothing
+    for (idx, chr) in pairs(text)
+        class = lineBreakClass(chr, strictness)
+        if class == :CM
+            previous === nothing || previous in (:SP, :BK, :ZW) || continue
+            class = :AL
+        end
+        if previous !== nothing
+            opportunity = pairBreak(previous, class, beforeSpaces)
+            opportunity === nothing || push!(breaks, (idx, opportunity))
+        end
+        class == :SP || (beforeSpaces = class)
+        previous = class
+    end
+    return breaks
+end
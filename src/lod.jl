@@ -0,0 +1,43 @@
+# Distance based level of detail for labels in 3D scenes.
+# Far away labels are tiny on screen: the analytic curve evaluation is wasted
+# on them, so they fade out over a band of projected em sizes and are culled
+# entirely below it.
+
+struct DistanceLOD
+    fadeStart::Float32      # projected em size in pixels where fading starts
+    fadeEnd::Float32        # below this projected em size labels are culled
+end
+
+DistanceLOD(; fadeStart=8, fadeEnd=4) = DistanceLOD(fadeStart, fadeEnd)
+
+function clipToPixels(clip, viewport)
+    (clip[1]/clip[4]*viewport[1]/2, clip[2]/clip[4]*viewport[2]/2)
+end
+
+# Screen pixels covered by one layout pixel at `point` (layout space).
+function screenScale(projection::Projection, transform::Mat4, viewport, point=(0f0, 0f0))
+    m = projection.matrix*transform
+    (x, y) = point
+    p = clipToPixels(m*SVector{4, Float32}(x, y, 0, 1), viewport)
+    px = clipToPixels(m*SVector{4, Float32}(x + 1, y, 0, 1), viewport)
+    py = clipToPixels(m*SVector{4, Float32}(x, y + 1, 0, 1), viewport)
+    return max(hypot((px .- p)...), hypot((py .- p)...))
+end
+
+projectedEmSize(style::TextStyle, projection::Projection, transform::Mat4, viewport, point=(0f0, 0f0)) =
+    style.size*screenScale(projection, transform, viewport, point)
+
+function lodOpacity(lod::DistanceLOD, emPixels)
+    emPixels >= lod.fadeStart && return 1f0
+    emPixels <= lod.fadeEnd && return 0f0
+    return Float32((emPixels - lod.fadeEnd)/(lod.fadeStart - lod.fadeEnd))
+end
+
+# Style to draw a label with at its current distance, or `nothing` when culled.
+function applyLOD(lod::DistanceLOD, style::TextStyle, emPixels)
+    opacity = lodOpacity(lod, emPixels)
+    opacity == 0 && return nothing
+    opacity == 1 && return style
+    (r, g, b, a) = style.color
+    return restyle(style; color=(r, g, b, a*opacity))
+end
@@ -0,0 +1,283 @@
+# Loop-Blinn backend, a comparison and fallback path for gpus that struggle
+# with the per fragment curve loops of font.wgsl.
+# Glyph interiors are ear clipped into triangles, holes bridged into their
+# outer contour, and every curved edge becomes a triangle over its control
+# points shaded by the implicit test of Loop and Blinn. Controls outside the
+# ink fill the curve's inner side and stay out of the interior polygon,
+# controls inside fill the outer side and join it. Straight edges alias
+# without multisampling, and curve triangles of very thin features may
+# overlap, so use a sample count of 4 where the backend is compared.
+#
+#     backend = LoopBlinnText(renderer)
+#     queue!(backend, layoutText("Hello", style; origin=(20, 40)))
+#     prepare!(backend, surface.size)
+#     draw!(backend, renderPass)
+#
+# Triangles are built once per glyph in em units and kept on the backend.
+
+# Layout of `VertexInput` in loopblinn.wgsl.
+struct LoopBlinnVertex
+    x::Float32
+    y::Float32
+    u::Float32
+    v::Float32
+    side::Float32       # 0 interior, 1 fills u^2 < v, -1 fills u^2 > v
+    color::UInt32       # rgba8, red in the lowest byte
+end
+
+function getVertexBufferLayout(::Type{LoopBlinnVertex}; offset=0)
+    WGPUCore.GPUVertexBufferLayout => [
+        :arrayStride => sizeof(LoopBlinnVertex),
+        :stepMode => "Vertex",
+        :attributes => [
+            :attribute => [
+                :format => "Float32x2",
+                :offset => fieldoffset(LoopBlinnVertex, 1),
+                :shaderLocation => offset + 0
+            ],
+            :attribute => [
+                :format => "Float32x2",
+                :offset => fieldoffset(LoopBlinnVertex, 3),
+                :shaderLocation => offset + 1
+            ],
+            :attribute => [
+                :format => "Float32",
+                :offset => fieldoffset(LoopBlinnVertex, 5),
+                :shaderLocation => offset + 2
+            ],
+            :attribute => [
+                :format => "Unorm8x4",
+                :offset => fieldoffset(LoopBlinnVertex, 6),
+                :shaderLocation => offset + 3
+            ],
+        ]
+    ]
+end
+
+# Layout of `LoopBlinnUniforms` in loopblinn.wgsl, padded to 16 bytes.
+struct LoopBlinnUniforms
+    projection::Mat4
+    transform::Mat4
+    linearizeColors::UInt32
+    padding::NTuple{3, UInt32}
+end
+
+const loopBlinnShaderSource = embedShader("loopblinn.wgsl")
+
+# (x, y, u, v, side) of a triangle corner in em units, y up.
+const EmCorner = NTuple{5, Float32}
+
+cross2(a, b) = a[1]*b[2] - a[2]*b[1]
+
+polygonArea(polygon) = sum(k -> cross2(polygon[k], polygon[mod1(k + 1, length(polygon))]), eachindex(polygon); init=0f0)/2
+
+contourArea(contour) = sum(c -> (c.x0*c.y1 - c.x1*c.y0) + (c.x1*c.y2 - c.x2*c.y1), contour; init=0f0)/2
+
+insideTriangle(p, a, b, c) = cross2(b .- a, p .- a) >= 0 && cross2(c .- b, p .- b) >= 0 && cross2(a .- c, p .- c) >= 0
+
+function insidePolygon(p, polygon)
+    inside = false
+    n = length(polygon)
+    for k in 1:n
+        (a, b) = (polygon[k], polygon[mod1(k + 1, n)])
+        (a[2] > p[2]) != (b[2] > p[2]) || continue
+        p[1] < a[1] + (p[2] - a[2])/(b[2] - a[2])*(b[1] - a[1]) && (inside = !inside)
+    end
+    return inside
+end
+
+function glyphTriangles(curves)
+    corners = EmCorner[]
+    contours = [curves[range] for range in contourRanges(curves)]
+    isempty(contours) && return corners
+    # nonzero outlines keep the ink on one side of travel, left for counter clockwise outer contours
+    inkLeft = sum(contourArea, contours) >= 0
+    polygons = Vector{NTuple{2, Float32}}[]
+    for contour in contours
+        polygon = NTuple{2, Float32}[]
+        for c in contour
+            (p0, p1, p2) = ((c.x0, c.y0), (c.x1, c.y1), (c.x2, c.y2))
+            push!(polygon, p0)
+            turn = cross2(p2 .- p0, p1 .- p0)
+            abs(turn) < 1f-7 && continue
+            controlInside = (turn > 0) == inkLeft
+            controlInside && push!(polygon, p1)
+            side = controlInside ? -1f0 : 1f0
+            push!(corners, (p0..., 0f0, 0f0, side), (p1..., 0.5f0, 0f0, side), (p2..., 1f0, 1f0, side))
+        end
+        length(polygon) >= 3 && push!(polygons, inkLeft ? polygon : reverse(polygon))
+    end
+    # outer polygons run counter clockwise now, holes clockwise
+    fillPolygons!(polygons) do a, b, c
+        push!(corners, (a..., 0f0, 0f0, 0f0), (b..., 0f0, 0f0, 0f0), (c..., 0f0, 0f0, 0f0))
+    end
+    return corners
+end
+
+# Triangulates counter clockwise outer polygons with their clockwise holes,
+# `emit(a, b, c)` receives every counter clockwise triangle.
+function fillPolygons!(emit, polygons)
+    outers = filter(p -> polygonArea(p) > 0, polygons)
+    holes = filter(p -> polygonArea(p) < 0, polygons)
+    sort!(outers; by=polygonArea)
+    merged = [[outer] for outer in outers]
+    for hole in holes
+        k = findfirst(outer -> insidePolygon(hole[1], outer), outers)
+        k === nothing || push!(merged[k], hole)
+    end
+    for group in merged
+        polygon = group[1]
+        for hole in sort!(group[2:end]; by=h -> -maximum(first, h))
+            polygon = bridgeHole(polygon, hole)
+        end
+        earClip!(emit, polygon)
+    end
+    return emit
+end
+
+# Joins `hole` into `outer` through the outer vertex the rightmost hole
+# vertex sees, after Eberly's triangulation by ear clipping.
+function bridgeHole(outer, hole)
+    m = argmax(first.(hole))
+    h = hole[m]
+    n = length(outer)
+    (nearest, edge) = (Inf32, 0)
+    for k in 1:n
+        (a, b) = (outer[k], outer[mod1(k + 1, n)])
+        (a[2] - h[2])*(b[2] - h[2]) <= 0 && a[2] != b[2] || continue
+        x = a[1] + (h[2] - a[2])/(b[2] - a[2])*(b[1] - a[1])
+        h[1] <= x < nearest && ((nearest, edge) = (x, k))
+    end
+    edge == 0 && return outer
+    k = outer[edge][1] > outer[mod1(edge + 1, n)][1] ? edge : mod1(edge + 1, n)
+    # an outer vertex inside the triangle to the hit would block the bridge, take the closest such vertex to the ray
+    hit = (nearest, h[2])
+    blockers = [
+        j for j in 1:n
+        if j != k && outer[j][1] >= h[1] && (insideTriangle(outer[j], h, hit, outer[k]) || insideTriangle(outer[j], h, outer[k], hit))
+    ]
+    isempty(blockers) ||
+        (k = argmin(j -> (abs(atan(outer[j][2] - h[2], outer[j][1] - h[1])), outer[j][1] - h[1]), blockers))
+    return [outer[1:k]; hole[m:end]; hole[1:m]; outer[k:end]]
+end
+
+# Counter clockwise `polygon` into triangles, degenerate rests are dropped.
+function earClip!(emit, polygon)
+    remaining = collect(eachindex(polygon))
+    while length(remaining) > 3
+        n = length(remaining)
+        clipped = false
+        for j in 1:n
+            (ia, ib, ic) = (remaining[mod1(j - 1, n)], remaining[j], remaining[mod1(j + 1, n)])
+            (a, b, c) = (polygon[ia], polygon[ib], polygon[ic])
+            turn = cross2(b .- a, c .- b)
+            if abs(turn) < 1f-9
+                # collinear or doubled bridge vertex
+                deleteat!(remaining, j)
+                clipped = true
+                break
+            end
+            turn > 0 || continue
+            any(i -> !(polygon[i] in (a, b, c)) && insideTriangle(polygon[i], a, b, c), remaining) && continue
+            emit(a, b, c)
+            deleteat!(remaining, j)
+            clipped = true
+            break
+        end
+        clipped || return emit
+    end
+    if length(remaining) == 3
+        (a, b, c) = polygon[remaining]
+        cross2(b .- a, c .- b) > 0 && emit(a, b, c)
+    end
+    return emit
+end
+
+mutable struct LoopBlinnText
+    renderer::TextRenderer
+    pipeline::FontPipeline
+    triangles::IdDict{FontFace, Dict{FT_UInt, Vector{EmCorner}}}
+    vertices::Vector{LoopBlinnVertex}
+    vertexBuffer
+    vertexCapacity::Int
+    vertexCount::Int
+    uniformBuffer
+    bindGroup
+end
+
+"""
+    LoopBlinnText(renderer)
+
+Loop-Blinn drawing into the targets of `renderer`, see loopblinn.jl.
+"""
+function LoopBlinnText(renderer::TextRenderer)
+    pipeline = rendererPipeline(
+        renderer;
+        label="loop blinn",
+        shaderSource=loopBlinnShaderSource,
+        bindingLayouts=getBindingLayouts(FontFace)[1:1],
+        vertexBuffers=[getVertexBufferLayout(LoopBlinnVertex)]
+    )
+    return LoopBlinnText(
+        renderer, pipeline, IdDict{FontFace, Dict{FT_UInt, Vector{EmCorner}}}(),
+        LoopBlinnVertex[], nothing, 0, 0, nothing, nothing
+    )
+end
+
+trianglesFor(backend::LoopBlinnText, pg::PositionedGlyph) =
+    get!(get!(Dict{FT_UInt, Vector{EmCorner}}, backend.triangles, pg.font), pg.glyph.index) do
+        glyphTriangles(collect(glyphCurves(pg.font, pg.glyph)))
+    end
+
+# Adds the glyphs of `layout` for the next frame.
+function queue!(backend::LoopBlinnText, layout::TextLayout)
+    for pg in layout.glyphs
+        pg.glyph.curveCount == 0 && continue
+        color = packColor(pg.color)
+        for (x, y, u, v, side) in trianglesFor(backend, pg)
+            (px, py) = (pg.x, pg.y) .+ pg.transform*(x*pg.size, -y*pg.size)
+            push!(backend.vertices, LoopBlinnVertex(px, py, u, v, side, color))
+        end
+    end
+    return backend
+end
+
+queue!(backend::LoopBlinnText, text::TextInput, position, style::TextStyle) =
+    queue!(backend, layoutText(text, style; origin=Float32.(position)))
+
+function prepare!(backend::LoopBlinnText, projection::Projection; transform=identityMat4)
+    renderer = backend.renderer
+    device = renderer.device
+    backend.vertexCount = length(backend.vertices)
+    isempty(backend.vertices) || @span "upload" begin
+        (backend.vertexBuffer, backend.vertexCapacity) = writeDynamic(
+            device, backend.vertexBuffer, backend.vertexCapacity, backend.vertices, "loop blinn vertex buffer", ["Vertex", "CopyDst"]
+        )
+    end
+    empty!(backend.vertices)
+    uniforms = [LoopBlinnUniforms(projection.matrix, transform, linearizeColors(renderer.options), (0, 0, 0))]
+    if backend.uniformBuffer === nothing
+        (backend.uniformBuffer, _) = WGPUCore.createBufferWithData(device, "loop blinn uniform buffer", uniforms, ["Uniform", "CopyDst"])
+        backend.bindGroup = WGPUCore.createBindGroup(
+            "loop blinn bind group", device,
+            backend.pipeline.bindGroupLayout,
+            [WGPUCore.GPUBuffer => [:binding => 0, :buffer => backend.uniformBuffer, :offset => 0, :size => backend.uniformBuffer.size]]
+        )
+    else
+        WGPUCore.writeBuffer(device.queue, backend.uniformBuffer, uniforms)
+    end
+    return backend
+end
+
+prepare!(backend::LoopBlinnText, targetSize::Tuple; kwargs...) = prepare!(backend, orthographic(targetSize...); kwargs...)
+
+function draw!(backend::LoopBlinnText, renderPass)
+    backend.vertexCount == 0 && return backend
+    @span "encode" withDebugGroup(renderPass, "loop blinn") do
+        WGPUCore.setPipeline(renderPass, backend.pipeline.pipeline)
+        WGPUCore.setVertexBuffer(renderPass, 0, backend.vertexBuffer)
+        WGPUCore.setBindGroup(renderPass, 0, backend.bindGroup, UInt32[], 0, 99)
+        WGPUCore.draw(renderPass, backend.vertexCount; instanceCount=1, firstVertex=0, firstInstance=0)
+    end
+    return backend
+end
@@ -0,0 +1,261 @@
+# Map labels.
+# Candidate labels come with an anchor, a priority and the size of the
+# symbol they label, e.g. a town dot. Every label is tried at the eight
+# classic positions around its symbol, right, left, above and below first,
+# then the corners, and labels that would overlap a placed label or another
+# symbol are hidden. Greedy placement goes by priority, annealing also
+# moves placed labels aside to fit more of them in.
+#
+#     placer = LabelPlacer()
+#     setLabel!(placer, :paris, MapLabel("Paris", (2.35, 48.86), style; priority=10))
+#     placeLabels!(placer, lonLat -> toScreen(lonLat); viewport=(0, 0, 1280, 720))
+#     queueLabels!(renderer, placer)
+#
+# Label sizes are measured once per text and style. Labels shown in the last
+# frame go first and keep their position while it fits, so labels do not
+# jump around while a map is panned or zoomed.
+
+@enum LabelPlacement greedyPlacement annealingPlacement
+
+struct MapLabel
+    text::String
+    # anything the projection of `placeLabels!` maps into screen pixels
+    anchor
+    style::TextStyle
+    priority::Float32
+    # pixels of the labelled symbol, centered on the anchor
+    symbolSize::NTuple{2, Float32}
+    # pixels between the symbol and the label
+    gap::Float32
+    halo::Union{Nothing, Halo}
+end
+
+MapLabel(text, anchor, style; priority=0, symbolSize=(0, 0), gap=2, halo=Halo((1f0, 1f0, 1f0, 1f0))) =
+    MapLabel(validText(text), anchor, style, priority, symbolSize, gap, halo)
+
+# Screen box (x, y, width, height) of a shown label, its text starts at the top left corner.
+struct PlacedLabel
+    key
+    box::NTuple{4, Float32}
+end
+
+mutable struct LabelEntry
+    label::MapLabel
+    size::NTuple{2, Float32}
+    # position of the last frame, 0 when hidden
+    candidate::Int
+end
+
+mutable struct LabelPlacer
+    placement::LabelPlacement
+    entries::Dict{Any, LabelEntry}
+    placed::Vector{PlacedLabel}
+    # annealing steps per label
+    annealingSteps::Int
+    seed::UInt64
+end
+
+LabelPlacer(; placement=greedyPlacement, annealingSteps=100, seed=0x9e3779b97f4a7c15) =
+    LabelPlacer(placement, Dict{Any, LabelEntry}(), PlacedLabel[], annealingSteps, seed)
+
+function labelSize(label::MapLabel)
+    layout = layoutText(label.text, label.style)
+    return (layout.width, layout.height)
+end
+
+# Labels keep their size and position while text and style stay the same.
+function setLabel!(placer::LabelPlacer, key, label::MapLabel)
+    previous = get(placer.entries, key, nothing)
+    if previous !== nothing && previous.label.text == label.text && previous.label.style == label.style
+        previous.label = label
+    else
+        placer.entries[key] = LabelEntry(label, labelSize(label), 0)
+    end
+    return placer
+end
+
+removeLabel!(placer::LabelPlacer, key) = (delete!(placer.entries, key); placer)
+
+# Offsets of a label's top left corner from its anchor, in order of preference.
+function candidateOffsets(label::MapLabel, (w, h))
+    (sx, sy) = label.symbolSize./2 .+ label.gap
+    return (
+        (sx, -h/2), (-sx - w, -h/2), (-w/2, -sy - h), (-w/2, sy),
+        (sx, -sy - h), (-sx - w, -sy - h), (sx, sy), (-sx - w, sy),
+    )
+end
+
+overlaps((ax, ay, aw, ah), (bx, by, bw, bh)) = ax < bx + bw && bx < ax + aw && ay < by + bh && by < ay + ah
+
+inside((x, y, w, h), (vx, vy, vw, vh)) = x >= vx && y >= vy && x + w <= vx + vw && y + h <= vy + vh
+
+# Uniform grid of boxes, so overlap tests only look at nearby labels.
+struct CollisionGrid
+    cellSize::Float32
+    cells::Dict{Tuple{Int, Int}, Vector{Int}}
+end
+
+CollisionGrid(cellSize) = CollisionGrid(max(Float32(cellSize), 1f0), Dict{Tuple{Int, Int}, Vector{Int}}())
+
+function gridCells(grid::CollisionGrid, (x, y, w, h))
+    c = grid.cellSize
+    return Iterators.product(floor(Int, x/c):floor(Int, (x + w)/c), floor(Int, y/c):floor(Int, (y + h)/c))
+end
+
+Base.push!(grid::CollisionGrid, id, box) =
+    (foreach(cell -> push!(get!(Vector{Int}, grid.cells, cell), id), gridCells(grid, box)); grid)
+
+Base.delete!(grid::CollisionGrid, id, box) =
+    (foreach(cell -> filter!(!=(id), get(grid.cells, cell, Int[])), gridCells(grid, box)); grid)
+
+# Ids of the boxes of `grid` overlapping `box`, each once.
+function colliding(grid::CollisionGrid, boxes, box)
+    ids = Set{Int}()
+    for cell in gridCells(grid, box), id in get(grid.cells, cell, ())
+        overlaps(boxes[id], box) && push!(ids, id)
+    end
+    return ids
+end
+
+"""
+    placeLabels!(placer, project=identity; viewport=nothing) -> Vector{PlacedLabel}
+
+Places the labels for the current view, `project(anchor)` returns the screen
+position of an anchor or `nothing` when it is out of view. Labels have to
+fit into `viewport`, (x, y, width, height) in pixels, when given.
+"""
+function placeLabels!(placer::LabelPlacer, project=identity; viewport=nothing)
+    labelKeys = collect(keys(placer.entries))
+    anchors = [project(placer.entries[key].label.anchor) for key in labelKeys]
+    visible = [k for k in eachindex(labelKeys) if anchors[k] !== nothing]
+    entries = [placer.entries[labelKeys[k]] for k in visible]
+    anchors = [Float32.(anchors[k]) for k in visible]
+    boxesOf(i) = [
+        (anchors[i][1] + dx, anchors[i][2] + dy, entries[i].size...)
+        for (dx, dy) in candidateOffsets(entries[i].label, entries[i].size)
+    ]
+    candidates = [
+        filter!(((_, box),) -> viewport === nothing || inside(box, viewport), collect(enumerate(boxesOf(i))))
+        for i in eachindex(entries)
+    ]
+    symbols = [(a[1] - e.label.symbolSize[1]/2, a[2] - e.label.symbolSize[2]/2, e.label.symbolSize...) for (a, e) in zip(anchors, entries)]
+    cellSize = isempty(entries) ? 1f0 : 2*maximum(e -> max(e.size...), entries)
+
+    choice = greedyLabels(entries, candidates, symbols, cellSize)
+    placer.placement == annealingPlacement &&
+        (choice = annealLabels(placer, entries, candidates, symbols, cellSize, choice))
+
+    empty!(placer.placed)
+    for (i, c) in enumerate(choice)
+        entries[i].candidate = c == 0 ? 0 : candidates[i][c][1]
+        c == 0 || push!(placer.placed, PlacedLabel(labelKeys[visible[i]], candidates[i][c][2]))
+    end
+    for k in setdiff(eachindex(labelKeys), visible)
+        placer.entries[labelKeys[k]].candidate = 0
+    end
+    return placer.placed
+end
+
+# Symbols other than the label's own are obstacles for every label.
+symbolGrid(symbols, cellSize) = foldl(
+    (grid, (i, box)) -> box[3]*box[4] > 0 ? push!(grid, i, box) : grid, enumerate(symbols); init=CollisionGrid(cellSize)
+)
+
+function blockedBySymbol(symbolIndex, symbols, i, box)
+    ids = colliding(symbolIndex, symbols, box)
+    return !isempty(ids) && ids != Set((i,))
+end
+
+# Index into `candidates[i]` of every label, 0 for hidden ones.
+function greedyLabels(entries, candidates, symbols, cellSize)
+    symbolIndex = symbolGrid(symbols, cellSize)
+    # labels shown in the last frame first, then by priority
+    order = sortperm(eachindex(entries); by=i -> (entries[i].candidate == 0, -entries[i].label.priority))
+    choice = zeros(Int, length(entries))
+    boxes = Dict{Int, NTuple{4, Float32}}()
+    labelIndex = CollisionGrid(cellSize)
+    for i in order
+        tries = sortperm(candidates[i]; by=((position, _),) -> position != entries[i].candidate)
+        for c in tries
+            box = candidates[i][c][2]
+            isempty(colliding(labelIndex, boxes, box)) || continue
+            blockedBySymbol(symbolIndex, symbols, i, box) && continue
+            choice[i] = c
+            boxes[i] = box
+            push!(labelIndex, i, box)
+            break
+        end
+    end
+    return choice
+end
+
+function nextRandom!(placer::LabelPlacer)
+    x = placer.seed
+    x ⊻= x << 13
+    x ⊻= x >> 7
+    x ⊻= x << 17
+    placer.seed = x
+    return x
+end
+
+# Costs of annealing: hiding a label costs its priority plus one, corners a
+# little more than the sides, overlapping pairs far more than any hidden label.
+const hiddenCost = 1f0
+const positionCost = 0.05f0
+const overlapCost = 100f0
+
+# Simulated annealing after Christensen, Marks and Shieber: one label at a
+# time moves to a random candidate or is hidden, moves making the layout
+# worse are taken with a probability falling with the temperature. Overlaps
+# left at the end are removed by hiding the lower priority label.
+function annealLabels(placer::LabelPlacer, entries, candidates, symbols, cellSize, choice)
+    n = length(entries)
+    n == 0 && return choice
+    symbolIndex = symbolGrid(symbols, cellSize)
+    choice = copy(choice)
+    boxes = Dict{Int, NTuple{4, Float32}}(i => candidates[i][c][2] for (i, c) in enumerate(choice) if c != 0)
+    labelIndex = CollisionGrid(cellSize)
+    foreach(((i, box),) -> push!(labelIndex, i, box), boxes)
+    blocked(i, box) = blockedBySymbol(symbolIndex, symbols, i, box)
+    function cost(i, c)
+        c == 0 && return hiddenCost + max(entries[i].label.priority, 0f0)
+        box = candidates[i][c][2]
+        others = length(setdiff(colliding(labelIndex, boxes, box), (i,)))
+        return positionCost*(candidates[i][c][1] - 1) + overlapCost*(others + blocked(i, box))
+    end
+    uniform() = (nextRandom!(placer) >> 11)/Float32(2^53)
+    steps = placer.annealingSteps*n
+    temperature = overlapCost/10
+    for step in 1:steps
+        i = Int(nextRandom!(placer) % n) + 1
+        c = Int(nextRandom!(placer) % (length(candidates[i]) + 1))
+        c == choice[i] && continue
+        before = cost(i, choice[i])
+        haskey(boxes, i) && (delete!(labelIndex, i, boxes[i]); delete!(boxes, i))
+        after = cost(i, c)
+        accept = after <= before || uniform() < exp((before - after)/temperature)
+        c = accept ? c : choice[i]
+        choice[i] = c
+        c == 0 || (boxes[i] = candidates[i][c][2]; push!(labelIndex, i, boxes[i]))
+        temperature = overlapCost/10*(1 - step/steps) + 1f-3
+    end
+    for i in sortperm(eachindex(entries); by=i -> entries[i].label.priority)
+        haskey(boxes, i) || continue
+        box = boxes[i]
+        if length(colliding(labelIndex, boxes, box)) > 1 || blocked(i, box)
+            delete!(labelIndex, i, box)
+            delete!(boxes, i)
+            choice[i] = 0
+        end
+    end
+    return choice
+end
+
+# Queues the placed labels with their halos.
+function queueLabels!(target::TextTarget, placer::LabelPlacer)
+    for placed in placer.placed
+        label = placer.entries[placed.key].label
+        queue!(target, Section(label.text, placed.box[1:2], label.style; halo=label.halo))
+    end
+    return target
+end
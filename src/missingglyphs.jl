@@ -0,0 +1,58 @@
+# Reports of characters a face has no glyph for.
+# Shaping calls every listener the first time a character misses in a face,
+# so hosts can log it, load a face covering it or show diagnostics. Layouts
+# may be built on worker threads, listeners run on whichever thread shaped.
+
+struct MissingGlyph
+    font::FontFace
+    char::Char
+end
+
+const missingGlyphListeners = Any[]
+const missingGlyphLock = ReentrantLock()
+# characters already reported, per face
+const reportedMissing = WeakKeyDict{FontFace, Set{Char}}()
+
+"""
+    onMissingGlyph(f) -> f
+
+Calls `f(report::MissingGlyph)` for every character shaping could not find
+in a face, once per face and character. Remove it with `offMissingGlyph`.
+"""
+onMissingGlyph(f) = (lock(() -> push!(missingGlyphListeners, f), missingGlyphLock); f)
+
+offMissingGlyph(f) = (lock(() -> filter!(!==(f), missingGlyphListeners), missingGlyphLock); nothing)
+
+"""
+    missingGlyphChannel(; size=256) -> Channel{MissingGlyph}
+
+Channel receiving the reports of `onMissingGlyph`. Reports are dropped while
+the channel is full, closing it unsubscribes.
+"""
+function missingGlyphChannel(; size=256)
+    channel = Channel{MissingGlyph}(size)
+    listener = onMissingGlyph() do report
+        isopen(channel) || return offMissingGlyph(listener)
+        # checked and put under the channel lock, so racing layout workers
+        # never block in put! on the last free slot
+        lock(channel) do
+            isopen(channel) && Base.n_avail(channel) < size && put!(channel, report)
+        end
+    end
+    return channel
+end
+
+function reportMissing(font::FontFace, chr::Char)
+    listeners = lock(missingGlyphLock) do
+        isempty(missingGlyphListeners) && return nothing
+        reported = get!(Set{Char}, reportedMissing, font)
+        chr in reported && return nothing
+        push!(reported, chr)
+        copy(missingGlyphListeners)
+    end
+    listeners === nothing && return
+    # outside the lock, listeners may subscribe or unsubscribe
+    for f in listeners
+        f(MissingGlyph(font, chr))
+    end
+end
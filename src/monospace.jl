@@ -0,0 +1,37 @@
+# Monospace cells for code and terminal text.
+# Styles with a `monospaceAdvance` set every character in cells of exactly
+# that many em, whatever the face reports, so columns line up even across
+# substituted glyphs. East Asian wide and fullwidth characters (UAX #11)
+# take two cells, combining marks and other zero width characters none.
+# Glyphs are centered in their cells, ligatures keep the cells of all the
+# characters they replace.
+
+# Cells `chr` takes, after the wcwidth of utf8proc.
+monospaceCells(chr::Char) = textwidth(chr)
+
+# Glyphs of one cluster split the cluster's cells by their natural advances.
+function monospaced(style::TextStyle, glyphs, text; clusterOffset=0)
+    style.monospaceAdvance > 0 && !isempty(glyphs) || return glyphs
+    cell = style.monospaceAdvance*style.size
+    starts = sort!(unique(s.cluster - clusterOffset for (s, _, _, _) in glyphs))
+    # cells of the characters from each cluster start up to the next one
+    cells = Dict(
+        start => sum(monospaceCells, SubString(text, start, prevind(text, k < length(starts) ? starts[k + 1] : ncodeunits(text) + 1)); init=0)
+        for (k, start) in enumerate(starts)
+    )
+    natural = Dict{Int, Float32}()
+    for (s, _, advance, _) in glyphs
+        natural[s.cluster] = get(natural, s.cluster, 0f0) + advance
+    end
+    seen = Set{Int}()
+    return map(glyphs) do (s, size, advance, glyphTransform)
+        width = cells[s.cluster - clusterOffset]*cell
+        total = natural[s.cluster]
+        firstOfCluster = !(s.cluster in seen)
+        push!(seen, s.cluster)
+        fixed = total > 0 ? width*advance/total : firstOfCluster ? width : 0f0
+        # half the difference moves the glyph into the middle of its cells
+        shift = round(FT_Pos, (fixed - advance)/2*style.font.emSize/size)
+        (ShapedGlyph(s.index, s.cluster, s.xAdvance, s.xOffset + shift, s.yOffset), size, fixed, glyphTransform)
+    end
+end
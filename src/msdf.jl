@@ -0,0 +1,156 @@
+# Multi channel distance fields generated on the gpu.
+# The compute pass in msdf.wgsl reads the same glyph and curve storage
+# buffers the analytic path draws from and writes each cell of the atlas
+# directly, so no cpu rasterization happens. Only the edge coloring that
+# tells the shader which channels an edge contributes to is done here.
+
+const msdfShaderSource = embedShader("msdf.wgsl")
+
+const msdfRed = UInt32(1)
+const msdfGreen = UInt32(2)
+const msdfBlue = UInt32(4)
+const msdfWhite = msdfRed | msdfGreen | msdfBlue
+
+# Layout of `Job` in msdf.wgsl.
+struct MSDFJob
+    bufferIndex::UInt32
+    colorStart::UInt32
+    originX::UInt32
+    originY::UInt32
+    layer::UInt32
+    width::UInt32
+    height::UInt32
+    left::Float32
+    top::Float32
+    texel::Float32
+    spread::Float32
+end
+
+startTangent(c::BufferCurve) = (c.x1, c.y1) == (c.x0, c.y0) ? (c.x2 - c.x0, c.y2 - c.y0) : (c.x1 - c.x0, c.y1 - c.y0)
+endTangent(c::BufferCurve) = (c.x2, c.y2) == (c.x1, c.y1) ? (c.x2 - c.x0, c.y2 - c.y0) : (c.x2 - c.x1, c.y2 - c.y1)
+
+# Sharp when the direction turns by more than about 172°, or backwards.
+function isCorner(a, b)
+    (ax, ay) = a
+    (bx, by) = b
+    norm = sqrt((ax*ax + ay*ay)*(bx*bx + by*by))
+    norm == 0 && return false
+    return ax*bx + ay*by <= 0 || abs(ax*by - ay*bx) > 0.141f0*norm
+end
+
+# Contours are runs of connected curves; edges switch between the three
+# two-channel colors at every corner so both sides of a corner differ in one
+# channel. Smooth contours are white, they only need the plain distance.
+function edgeColors(curves)
+    colors = (msdfRed | msdfGreen, msdfGreen | msdfBlue, msdfRed | msdfBlue)
+    masks = Vector{UInt32}(undef, length(curves))
+    contourStart = 1
+    color = 1
+    corners = false
+    for (i, curve) in enumerate(curves)
+        if i > 1
+            prev = curves[i - 1]
+            if (prev.x2, prev.y2) != (curve.x0, curve.y0)
+                corners || (masks[contourStart:i - 1] .= msdfWhite)
+                contourStart = i
+                color = 1
+                corners = false
+            elseif isCorner(endTangent(prev), startTangent(curve))
+                color = mod1(color + 1, 3)
+                corners = true
+            end
+        end
+        masks[i] = colors[color]
+    end
+    isempty(curves) || corners || (masks[contourStart:end] .= msdfWhite)
+    return masks
+end
+
+function getMSDFBindingLayouts(; binding=0)
+    [
+        (WGPUCore.WGPUBufferEntry => [
+            :binding => binding + i,
+            :visibility => ["Compute"],
+            :type => "ReadOnlyStorage"
+        ] for i in 0:3)...,
+        WGPUCore.WGPUStorageTextureEntry => [
+            :binding => binding + 4,
+            :visibility => ["Compute"],
+            :access => "WriteOnly",
+            :format => WGPUCore.WGPUTextureFormat_RGBA8Unorm,
+            :viewDimension => "2DArray"
+        ],
+    ]
+end
+
+function createMSDFPipeline(device)
+    shader = compileShader(device, "msdf shader", msdfShaderSource)
+    bindGroupLayout = WGPUCore.createBindGroupLayout(device, "msdf bind group layout", getMSDFBindingLayouts())
+    pipelineLayout = WGPUCore.createPipelineLayout(device, "msdf pipeline layout", bindGroupLayout)
+    pipeline = WGPUCore.createComputePipeline(device, "msdf pipeline", pipelineLayout, shader, "cs_main")
+    return (bindGroupLayout=bindGroupLayout, pipeline=pipeline)
+end
+
+function msdfBindGroup(atlas::SDFAtlas, fontBuffers::FontBuffers, chunk, colorBuffer, jobBuffer)
+    buffers = (fontBuffers.glyphBuffer, fontBuffers.curveBuffers[chunk], colorBuffer, jobBuffer)
+    WGPUCore.createBindGroup(
+        "msdf bind group", atlas.device,
+        atlas.generator.bindGroupLayout,
+        [
+            (WGPUCore.GPUBuffer => [
+                :binding => i - 1,
+                :buffer  => buffer,
+                :offset  => 0,
+                :size    => buffer.size
+            ] for (i, buffer) in enumerate(buffers))...,
+            WGPUCore.GPUTextureView => [
+                :binding => 4,
+                :textureView => atlas.view
+            ],
+        ]
+    )
+end
+
+# Fills all pending cells, one dispatch per font buffer chunk with one z slice
+# per glyph.
+function generateMSDF!(renderer, atlas::SDFAtlas)
+    isempty(atlas.pending) && return atlas
+    atlas.generator === nothing && (atlas.generator = createMSDFPipeline(atlas.device))
+    batches = Dict{Tuple{FontFace, Int}, Vector{Tuple{Glyph, AtlasEntry}}}()
+    for (font, glyph, entry) in atlas.pending
+        fontBuffers = fontBuffersFor(renderer, font)
+        push!(get!(Vector{Tuple{Glyph, AtlasEntry}}, batches, (font, glyphChunk(fontBuffers, glyph))), (glyph, entry))
+    end
+    @span "msdf" begin
+        encoder = WGPUCore.createCommandEncoder(atlas.device, "msdf encoder")
+        pass = WGPUCore.beginComputePass(encoder)
+        WGPUCore.setPipeline(pass, atlas.generator.pipeline)
+        for ((font, chunk), cells) in batches
+            colors = UInt32[]
+            jobs = MSDFJob[]
+            for (glyph, entry) in cells
+                push!(jobs, MSDFJob(
+                    glyph.bufferIndex, length(colors),
+                    entry.x, entry.y, entry.layer, entry.width, entry.height,
+                    entry.left, entry.top, 1f0/sdfTexelsPerEm, sdfSpread
+                ))
+                append!(colors, edgeColors(glyphCurves(font, glyph)))
+            end
+            isempty(colors) && push!(colors, msdfWhite)
+            colorBuffer = createStorageBuffer(atlas.device, "msdf colors", colors)
+            jobBuffer = createStorageBuffer(atlas.device, "msdf jobs", jobs)
+            fontBuffers = fontBuffersFor(renderer, font)
+            WGPUCore.setBindGroup(pass, 0, msdfBindGroup(atlas, fontBuffers, chunk, colorBuffer, jobBuffer), UInt32[], 0, 99)
+            WGPUCore.dispatchWorkgroups(
+                pass,
+                cld(maximum(e.width for (_, e) in cells), 8),
+                cld(maximum(e.height for (_, e) in cells), 8),
+                length(jobs)
+            )
+        end
+        WGPUCore.endComputePass(pass)
+        WGPUCore.submit(atlas.device.queue, [WGPUCore.finish(encoder),])
+    end
+    empty!(atlas.pending)
+    return atlas
+end
@@ -0,0 +1,62 @@
+# Several windows drawn by one renderer. Every `SurfaceView` queues and
+# prepares its own sections with the pipeline variant matching its surface,
+# fonts, curve buffers and pipelines stay shared in the renderer.
+#
+#     main = SurfaceView(renderer, mainSurface)
+#     tools = SurfaceView(renderer, toolSurface)
+#     queue!(tools, Section("layers", (8, 8), style))
+#     prepare!(tools)
+#     draw!(tools, toolPass)
+
+mutable struct SurfaceView <: TextTarget
+    renderer::TextRenderer
+    surface::TextSurface
+    options::RenderOptions
+    pipeline::FontPipeline
+    sections::Vector{Section}
+    staticLabels::Vector{Tuple{Section, Float32}}
+    debugPages::Vector{Tuple{Symbol, Int, Float32, Float32, Float32}}
+    frames::Vector{FrameBuffers}
+    frame::Int
+    ranges::Vector{DrawRange}
+    atlasDraws::Vector{AtlasDraw}
+    pathStats::PathStats
+    drawStats::DrawStats
+end
+
+function SurfaceView(renderer::TextRenderer, surface::TextSurface; kwargs...)
+    options = resolveTessellation(setfields(renderer.options; format=surface.format, kwargs...), renderer.device)
+    return SurfaceView(
+        renderer, surface, options,
+        pipelineVariant(renderer, options; kind=curvesKind(options)),
+        Section[],
+        Tuple{Section, Float32}[],
+        Tuple{Symbol, Int, Float32, Float32, Float32}[],
+        frameRing(options), 0,
+        DrawRange[],
+        AtlasDraw[],
+        PathStats(),
+        DrawStats()
+    )
+end
+
+sharedRenderer(view::SurfaceView) = view.renderer
+
+# Projects onto the current size of the surface.
+prepare!(view::SurfaceView; kwargs...) = prepare!(view, view.surface.size; kwargs...)
+
+# Picks up a format change of the surface, e.g. after moving to an hdr monitor.
+function reconfigure!(view::SurfaceView, surface::TextSurface; kwargs...)
+    view.surface = surface
+    reconfigure!(view; format=surface.format, kwargs...)
+end
+
+# After `recreate!` of the renderer the view drops its draws and pipeline.
+function recreate!(view::SurfaceView)
+    view.pipeline = pipelineVariant(view.renderer, view.options; kind=curvesKind(view.options))
+    empty!(view.ranges)
+    view.frames = frameRing(view.options)
+    view.frame = 0
+    empty!(view.atlasDraws)
+    return view
+end
@@ -0,0 +1,510 @@
+# Minimal OpenType GSUB engine.
+# Parses the glyph substitution table of a face into lookups keyed by glyph
+# and applies the lookups of requested feature tags to a glyph run. Single,
+# multiple, alternate, ligature and chained context substitutions are
+# supported, extension lookups are unwrapped; other lookup types are skipped. Tables come from
+# `sfntTable(provider, tag)`, providers without one shape by cmap only.
+# The superscript and subscript metrics of the OS/2 table and strings of the
+# name table are read here too.
+
+# Big endian reads at zero based offsets.
+u16(data, offset) = UInt16(data[offset + 1]) << 8 | data[offset + 2]
+i16(data, offset) = reinterpret(Int16, u16(data, offset))
+u32(data, offset) = UInt32(u16(data, offset)) << 16 | u16(data, offset + 2)
+tagString(data, offset) = String(data[(offset + 1):(offset + 4)])
+
+struct SingleSubst
+    map::Dict{UInt16, UInt16}
+end
+
+struct MultipleSubst
+    map::Dict{UInt16, Vector{UInt16}}
+end
+
+struct AlternateSubst
+    map::Dict{UInt16, Vector{UInt16}}
+end
+
+# First component to (remaining components, ligature glyph), longest first.
+struct LigatureSubst
+    map::Dict{UInt16, Vector{Tuple{Vector{UInt16}, UInt16}}}
+end
+
+# Glyph set, true to match every glyph outside of it (class 0 of a class def).
+const GlyphMatch = Tuple{Set{UInt16}, Bool}
+
+# Backtrack runs from the glyph before the input outwards, actions are
+# (input position, lookup index) pairs, both one based.
+struct ChainRule
+    backtrack::Vector{GlyphMatch}
+    input::Vector{GlyphMatch}
+    lookahead::Vector{GlyphMatch}
+    actions::Vector{Tuple{Int, Int}}
+end
+
+struct ChainContextSubst
+    rules::Vector{ChainRule}
+end
+
+struct GSUBLookup
+    type::Int
+    flag::UInt16
+    subtables::Vector{Any}
+end
+
+struct GSUBTable
+    # script tag => language tag => feature indices, "dflt" is the default language system
+    scripts::Dict{String, Dict{String, Vector{Int}}}
+    features::Vector{Tuple{String, Vector{Int}}}    # tag and lookup indices, one based
+    lookups::Vector{GSUBLookup}
+    # name table ids of the stylistic set feature params
+    featureNames::Dict{String, Int}
+end
+
+# Glyphs in coverage index order.
+function coverageGlyphs(data, offset)
+    format = u16(data, offset)
+    count = u16(data, offset + 2)
+    format == 1 && return [u16(data, offset + 4 + 2i) for i in 0:(count - 1)]
+    glyphs = UInt16[]
+    for i in 0:(count - 1)
+        record = offset + 4 + 6i
+        append!(glyphs, u16(data, record):u16(data, record + 2))
+    end
+    return glyphs
+end
+
+glyphArray(data, offset) = [u16(data, offset + 2 + 2i) for i in 0:(u16(data, offset) - 1)]
+
+# Counted u16 array at `offset` mapped through `f`, and the offset after it.
+# Input sequences of glyph and class rules leave out their first entry.
+function countedArray(f, data, offset; skipFirst=false)
+    count = u16(data, offset) - skipFirst
+    return ([f(u16(data, offset + 2 + 2k)) for k in 0:(count - 1)], offset + 2 + 2count)
+end
+
+function parseClassDef(data, offset)
+    classes = Dict{UInt16, UInt16}()
+    if u16(data, offset) == 1
+        start = u16(data, offset + 2)
+        for i in 0:(u16(data, offset + 4) - 1)
+            classes[start + i] = u16(data, offset + 6 + 2i)
+        end
+    else
+        for i in 0:(u16(data, offset + 2) - 1)
+            record = offset + 4 + 6i
+            for g in u16(data, record):u16(data, record + 2)
+                classes[g] = u16(data, record + 4)
+            end
+        end
+    end
+    return classes
+end
+
+function classMatcher(classes)
+    cache = Dict{Int, GlyphMatch}()
+    return c -> get!(cache, c) do
+        c == 0 ? (Set(keys(classes)), true) : (Set(g for (g, k) in classes if k == c), false)
+    end
+end
+
+parseActions(data, offset) =
+    [(Int(u16(data, offset + 2 + 4k)) + 1, Int(u16(data, offset + 4 + 4k)) + 1) for k in 0:(u16(data, offset) - 1)]
+
+function parseChainRule(data, offset, first::GlyphMatch, backtrackMatch, inputMatch, lookaheadMatch)
+    (backtrack, offset) = countedArray(backtrackMatch, data, offset)
+    (input, offset) = countedArray(inputMatch, data, offset; skipFirst=true)
+    (lookahead, offset) = countedArray(lookaheadMatch, data, offset)
+    return ChainRule(backtrack, pushfirst!(input, first), lookahead, parseActions(data, offset))
+end
+
+# Formats 1 and 2 group rules by first glyph or class, every group becomes
+# rules whose first position matches the glyphs of the group.
+function parseChainContext(data, offset)
+    format = u16(data, offset)
+    if format == 3
+        coverage = value -> (Set(coverageGlyphs(data, offset + value)), false)
+        (backtrack, next) = countedArray(coverage, data, offset + 2)
+        (input, next) = countedArray(coverage, data, next)
+        (lookahead, next) = countedArray(coverage, data, next)
+        return ChainContextSubst([ChainRule(backtrack, input, lookahead, parseActions(data, next))])
+    end
+    coverage = coverageGlyphs(data, offset + u16(data, offset + 2))
+    rules = ChainRule[]
+    if format == 1
+        glyph = g -> (Set((UInt16(g),)), false)
+        for (k, g) in enumerate(coverage)
+            set = offset + u16(data, offset + 6 + 2(k - 1))
+            for j in 0:(u16(data, set) - 1)
+                push!(rules, parseChainRule(data, set + u16(data, set + 2 + 2j), glyph(g), glyph, glyph, glyph))
+            end
+        end
+    elseif format == 2
+        (backtrack, input, lookahead) = (classMatcher(parseClassDef(data, offset + u16(data, offset + k))) for k in (4, 6, 8))
+        inputClasses = parseClassDef(data, offset + u16(data, offset + 6))
+        for c in 0:(u16(data, offset + 10) - 1)
+            setOffset = u16(data, offset + 12 + 2c)
+            setOffset == 0 && continue
+            set = offset + setOffset
+            first = (Set(g for g in coverage if get(inputClasses, g, 0) == c), false)
+            for j in 0:(u16(data, set) - 1)
+                push!(rules, parseChainRule(data, set + u16(data, set + 2 + 2j), first, backtrack, input, lookahead))
+            end
+        end
+    end
+    return ChainContextSubst(rules)
+end
+
+function parseSubtable(data, offset, type)
+    if type == 7
+        # extension, the real subtable sits behind a 32 bit offset
+        return parseSubtable(data, offset + u32(data, offset + 4), u16(data, offset + 2))
+    end
+    type == 6 && return parseChainContext(data, offset)
+    format = u16(data, offset)
+    coverage = coverageGlyphs(data, offset + u16(data, offset + 2))
+    if type == 1
+        if format == 1
+            delta = i16(data, offset + 4)
+            return SingleSubst(Dict(g => UInt16(mod(Int(g) + delta, 65536)) for g in coverage))
+        end
+        return SingleSubst(Dict(g => u16(data, offset + 6 + 2(i - 1)) for (i, g) in enumerate(coverage)))
+    end
+    # sequence, alternate and ligature sets share the layout of their offset arrays
+    sets = [offset + u16(data, offset + 6 + 2i) for i in 0:(length(coverage) - 1)]
+    if type == 2
+        return MultipleSubst(Dict(g => glyphArray(data, set) for (g, set) in zip(coverage, sets)))
+    elseif type == 3
+        return AlternateSubst(Dict(g => glyphArray(data, set) for (g, set) in zip(coverage, sets)))
+    elseif type == 4
+        map = Dict{UInt16, Vector{Tuple{Vector{UInt16}, UInt16}}}()
+        for (g, set) in zip(coverage, sets)
+            ligatures = Tuple{Vector{UInt16}, UInt16}[]
+            for j in 0:(u16(data, set) - 1)
+                ligature = set + u16(data, set + 2 + 2j)
+                components = u16(data, ligature + 2)
+                push!(ligatures, ([u16(data, ligature + 4 + 2k) for k in 0:(components - 2)], u16(data, ligature)))
+            end
+            map[g] = sort!(ligatures; by=l -> -length(l[1]))
+        end
+        return LigatureSubst(map)
+    end
+    return nothing
+end
+
+function parseLangSys(data, offset)
+    required = u16(data, offset + 2)
+    indices = [Int(u16(data, offset + 6 + 2i)) + 1 for i in 0:(u16(data, offset + 4) - 1)]
+    required == 0xffff || pushfirst!(indices, Int(required) + 1)
+    return indices
+end
+
+function parseGSUB(data::Vector{UInt8})
+    scriptList = u16(data, 4)
+    featureList = u16(data, 6)
+    lookupList = u16(data, 8)
+
+    scripts = Dict{String, Dict{String, Vector{Int}}}()
+    for i in 0:(u16(data, scriptList) - 1)
+        record = scriptList + 2 + 6i
+        script = scriptList + u16(data, record + 4)
+        languages = Dict{String, Vector{Int}}()
+        defaultLangSys = u16(data, script)
+        defaultLangSys == 0 || (languages["dflt"] = parseLangSys(data, script + defaultLangSys))
+        for j in 0:(u16(data, script + 2) - 1)
+            langRecord = script + 4 + 6j
+            languages[rstrip(tagString(data, langRecord))] = parseLangSys(data, script + u16(data, langRecord + 4))
+        end
+        scripts[rstrip(tagString(data, record))] = languages
+    end
+
+    featureNames = Dict{String, Int}()
+    features = map(0:(u16(data, featureList) - 1)) do i
+        record = featureList + 2 + 6i
+        feature = featureList + u16(data, record + 4)
+        tag = tagString(data, record)
+        params = u16(data, feature)
+        startswith(tag, "ss") && params != 0 && (featureNames[tag] = u16(data, feature + params + 2))
+        (tag, [Int(u16(data, feature + 4 + 2k)) + 1 for k in 0:(u16(data, feature + 2) - 1)])
+    end
+
+    lookups = map(0:(u16(data, lookupList) - 1)) do i
+        lookup = lookupList + u16(data, lookupList + 2 + 2i)
+        type = u16(data, lookup)
+        subtables = [parseSubtable(data, lookup + u16(data, lookup + 6 + 2k), type) for k in 0:(u16(data, lookup + 4) - 1)]
+        GSUBLookup(type, u16(data, lookup + 2), filter(!isnothing, subtables))
+    end
+    return GSUBTable(scripts, features, lookups, featureNames)
+end
+
+function loadGSUB(provider::FontProvider)
+    data = sfntTable(provider, "GSUB")
+    data === nothing && return nothing
+    try
+        return parseGSUB(data)
+    catch err
+        @warn "Ignoring malformed GSUB table" exception=err
+        return nothing
+    end
+end
+
+# Feature indices of the language system, falling back to the default script.
+function languageFeatures(gsub::GSUBTable, script, language)
+    for s in (script, "DFLT", "latn")
+        languages = get(gsub.scripts, s, nothing)
+        languages === nothing && continue
+        return get(languages, language, get(languages, "dflt", Int[]))
+    end
+    return isempty(gsub.scripts) ? collect(eachindex(gsub.features)) : Int[]
+end
+
+# Lookup indices of the enabled feature tags, in lookup list order.
+function featureLookups(gsub::GSUBTable, tags; script="DFLT", language="dflt")
+    lookups = Int[]
+    for index in languageFeatures(gsub, script, language)
+        (tag, indices) = gsub.features[index]
+        tag in tags && append!(lookups, indices)
+    end
+    return sort!(unique!(lookups))
+end
+
+hasFeature(gsub::GSUBTable, tag; kwargs...) = !isempty(featureLookups(gsub, (tag,); kwargs...))
+
+# Runs are vectors of (glyph index, cluster) pairs; custom glyphs never match
+# a lookup since their indices lie outside the 16 bit glyph range. A matching
+# `substitute` returns the number of run entries the result covers, the
+# lookup continues after them.
+
+asGlyph16(glyph) = glyph <= 0xffff ? UInt16(glyph) : nothing
+
+matches((glyphs, complement)::GlyphMatch, glyph) =
+    (g = asGlyph16(glyph); g !== nothing && (g in glyphs) != complement)
+
+function substitute(subtable::SingleSubst, run, i, gsub)
+    g = asGlyph16(run[i][1])
+    (g === nothing || !haskey(subtable.map, g)) && return nothing
+    run[i] = (FT_UInt(subtable.map[g]), run[i][2])
+    return 1
+end
+
+function substitute(subtable::AlternateSubst, run, i, gsub; alternate=1)
+    g = asGlyph16(run[i][1])
+    (g === nothing || !haskey(subtable.map, g)) && return nothing
+    alternates = subtable.map[g]
+    run[i] = (FT_UInt(alternates[clamp(alternate, 1, length(alternates))]), run[i][2])
+    return 1
+end
+
+function substitute(subtable::MultipleSubst, run, i, gsub)
+    g = asGlyph16(run[i][1])
+    (g === nothing || !haskey(subtable.map, g)) && return nothing
+    cluster = run[i][2]
+    sequence = subtable.map[g]
+    splice!(run, i, [(FT_UInt(s), cluster) for s in sequence])
+    # an empty sequence deletes the glyph
+    return length(sequence)
+end
+
+# Positions of the components following `i`, skipping the glyph of unmapped
+# joiners when `skipJoiners` is set, `nothing` if they don't match.
+function ligatureComponents(run, i, components, skipJoiners)
+    positions = Int[]
+    j = i
+    for component in components
+        j += 1
+        while skipJoiners && j <= length(run) && run[j][1] == joinerGlyph
+            j += 1
+        end
+        (j <= length(run) && run[j][1] == component) || return nothing
+        push!(positions, j)
+    end
+    return positions
+end
+
+# A ZWNJ keeps its own glyph between the components and so never ligates, a
+# ZWJ the face does not map is looked through.
+function substitute(subtable::LigatureSubst, run, i, gsub)
+    g = asGlyph16(run[i][1])
+    (g === nothing || !haskey(subtable.map, g)) && return nothing
+    for skipJoiners in (false, true), (components, ligature) in subtable.map[g]
+        positions = ligatureComponents(run, i, components, skipJoiners)
+        positions === nothing && continue
+        # the ligature keeps the cluster of its first component
+        deleteat!(run, positions)
+        run[i] = (FT_UInt(ligature), run[i][2])
+        return 1
+    end
+    return nothing
+end
+
+# Nested lookups run at single input positions of the matched rule; glyphs
+# they add or merge move the rest of the input along, and their clusters are
+# inherited as in the plain substitutions.
+function substitute(subtable::ChainContextSubst, run, i, gsub)
+    for rule in subtable.rules
+        n = length(rule.input)
+        (i > length(rule.backtrack) && i + n - 1 + length(rule.lookahead) <= length(run)) || continue
+        all(k -> matches(rule.backtrack[k], run[i - k][1]), eachindex(rule.backtrack)) || continue
+        all(k -> matches(rule.input[k], run[i + k - 1][1]), 1:n) || continue
+        all(k -> matches(rule.lookahead[k], run[i + n - 1 + k][1]), eachindex(rule.lookahead)) || continue
+        for (position, lookup) in rule.actions
+            (position <= n && lookup <= length(gsub.lookups)) || continue
+            before = length(run)
+            at = i + position - 1
+            applyLookup!(run, gsub, gsub.lookups[lookup]; range=at:at)
+            n += length(run) - before
+        end
+        return max(n, 1)
+    end
+    return nothing
+end
+
+# Applies one lookup over run[range], the first matching subtable wins.
+# Lookups never reorder a run, so clusters stay non decreasing.
+function applyLookup!(run, gsub::GSUBTable, lookup::GSUBLookup; range=eachindex(run), alternate=1)
+    i = first(range)
+    stop = last(range)
+    while i <= min(stop, length(run))
+        before = length(run)
+        covered = nothing
+        for subtable in lookup.subtables
+            covered = subtable isa AlternateSubst ?
+                substitute(subtable, run, i, gsub; alternate=alternate) :
+                substitute(subtable, run, i, gsub)
+            covered === nothing || break
+        end
+        stop += length(run) - before
+        i += something(covered, 1)
+    end
+    return run
+end
+
+function applyFeatures!(run, gsub::GSUBTable, tags; range=eachindex(run), alternate=1, kwargs...)
+    for index in featureLookups(gsub, tags; kwargs...)
+        applyLookup!(run, gsub, gsub.lookups[index]; range=range, alternate=alternate)
+    end
+    return run
+end
+
+# Synthetic super- and subscripts follow the OS/2 table, as
+# (x size, y size, x offset, y offset) in font units with y up.
+struct ScriptMetrics
+    superscript::NTuple{4, Int}
+    subscript::NTuple{4, Int}
+end
+
+# Advance heights and top side bearings from vhea and vmtx, in font units.
+struct VerticalMetrics
+    advances::Vector{Int}
+    topBearings::Vector{Int}
+end
+
+function loadVerticalMetrics(provider::FontProvider)
+    vhea = sfntTable(provider, "vhea")
+    vmtx = sfntTable(provider, "vmtx")
+    (vhea === nothing || vmtx === nothing || length(vhea) < 36) && return nothing
+    longMetrics = Int(u16(vhea, 34))
+    (longMetrics == 0 || length(vmtx) < 4longMetrics) && return nothing
+    advances = [Int(u16(vmtx, 4k)) for k in 0:(longMetrics - 1)]
+    topBearings = [Int(i16(vmtx, 4k + 2)) for k in 0:(longMetrics - 1)]
+    # glyphs past the long metrics share the last advance
+    append!(topBearings, Int(i16(vmtx, offset)) for offset in (4longMetrics):2:(length(vmtx) - 2))
+    return VerticalMetrics(advances, topBearings)
+end
+
+verticalAdvance(metrics::VerticalMetrics, glyphIdx) = metrics.advances[min(glyphIdx + 1, end)]
+topBearing(metrics::VerticalMetrics, glyphIdx) = get(metrics.topBearings, glyphIdx + 1, nothing)
+
+function loadScriptMetrics(provider::FontProvider, unitsPerEm)
+    data = sfntTable(provider, "OS/2")
+    if data === nothing || length(data) < 26 || i16(data, 12) <= 0 || i16(data, 20) <= 0
+        size = round(Int, 0.65*unitsPerEm)
+        return ScriptMetrics((size, size, 0, round(Int, 0.35*unitsPerEm)), (size, size, 0, -round(Int, 0.14*unitsPerEm)))
+    end
+    subscript = (Int(i16(data, 10)), Int(i16(data, 12)), Int(i16(data, 14)), -Int(i16(data, 16)))
+    superscript = (Int(i16(data, 18)), Int(i16(data, 20)), Int(i16(data, 22)), Int(i16(data, 24)))
+    return ScriptMetrics(superscript, subscript)
+end
+
+# English string `nameID` of the name table, preferring the Windows unicode records.
+function nameString(provider::FontProvider, nameID)
+    data = sfntTable(provider, "name")
+    data === nothing && return nothing
+    strings = u16(data, 4)
+    fallback = nothing
+    for i in 0:(u16(data, 2) - 1)
+        record = 6 + 12i
+        u16(data, record + 6) == nameID || continue
+        (platform, language) = (u16(data, record), u16(data, record + 4))
+        start = strings + u16(data, record + 10)
+        bytes = data[(start + 1):(start + u16(data, record + 8))]
+        if platform == 3 && language == 0x0409
+            return transcode(String, [UInt16(bytes[k]) << 8 | bytes[k + 1] for k in 1:2:(length(bytes) - 1)])
+        elseif platform == 1 && fallback === nothing
+            fallback = String(map(b -> b < 0x80 ? b : UInt8('?'), bytes))
+        end
+    end
+    return fallback
+end
+
+# OpenType language system tags for BCP 47 tags, e.g. "sr-Cyrl" shapes with
+# the "SRB" language of the "cyrl" script. Scripts come from the script
+# subtag or else the first character of the run with a known script.
+
+const openTypeLanguages = Dict(
+    "ar" => "ARA", "az" => "AZE", "bg" => "BGR", "ca" => "CAT", "cs" => "CSY",
+    "da" => "DAN", "de" => "DEU", "el" => "ELL", "es" => "ESP", "fa" => "FAR",
+    "fi" => "FIN", "fr" => "FRA", "he" => "IWR", "hi" => "HIN", "hu" => "HUN",
+    "it" => "ITA", "ja" => "JAN", "kk" => "KAZ", "ko" => "KOR", "mk" => "MKD",
+    "mn" => "MNG", "mo" => "MOL", "mr" => "MAR", "nb" => "NOR", "ne" => "NEP",
+    "nl" => "NLD", "no" => "NOR", "pl" => "PLK", "pt" => "PTG", "ro" => "ROM",
+    "ru" => "RUS", "sk" => "SKY", "sr" => "SRB", "sv" => "SVE", "th" => "THA",
+    "tr" => "TRK", "tt" => "TAT", "uk" => "UKR", "ur" => "URD", "vi" => "VIT",
+)
+
+const openTypeScripts = Dict(
+    "latn" => "latn", "cyrl" => "cyrl", "grek" => "grek", "arab" => "arab",
+    "hebr" => "hebr", "hani" => "hani", "hans" => "hani", "hant" => "hani",
+    "jpan" => "kana", "kana" => "kana", "hira" => "kana", "hang" => "hang",
+    "kore" => "hang", "deva" => "dev2", "thai" => "thai",
+)
+
+# Chinese forms differ by region more than by script subtag.
+function chineseLanguage(subtags)
+    any(in(("hant", "tw")), subtags) && return "ZHT"
+    any(in(("hk", "mo")), subtags) && return "ZHH"
+    return "ZHS"
+end
+
+function openTypeLanguage(tag::AbstractString)
+    subtags = lowercase.(split(tag, ('-', '_')))
+    isempty(subtags[1]) && return "dflt"
+    subtags[1] == "zh" && return chineseLanguage(subtags[2:end])
+    return get(openTypeLanguages, subtags[1], "dflt")
+end
+
+function characterScript(chr::Char)
+    c = UInt32(chr)
+    0x0041 <= c <= 0x024f && return isletter(chr) ? "latn" : nothing
+    0x0370 <= c <= 0x03ff && return "grek"
+    0x0400 <= c <= 0x052f && return "cyrl"
+    0x0590 <= c <= 0x05ff && return "hebr"
+    0x0600 <= c <= 0x06ff && return "arab"
+    0x0900 <= c <= 0x097f && return "dev2"
+    0x0e00 <= c <= 0x0e7f && return "thai"
+    0x3040 <= c <= 0x30ff && return "kana"
+    (0xac00 <= c <= 0xd7af || 0x1100 <= c <= 0x11ff) && return "hang"
+    (0x4e00 <= c <= 0x9fff || 0x3400 <= c <= 0x4dbf) && return "hani"
+    return nothing
+end
+
+function openTypeScript(tag::AbstractString, text::AbstractString)
+    for subtag in lowercase.(split(tag, ('-', '_'))[2:end])
+        haskey(openTypeScripts, subtag) && return openTypeScripts[subtag]
+    end
+    for chr in text
+        script = characterScript(chr)
+        script === nothing || return script
+    end
+    return "DFLT"
+end
@@ -0,0 +1,113 @@
+# Exact glyph geometry for CAD, plotting, SVG or physics, apart from the gpu.
+# Outlines come as path segments in font units with y growing upwards, the
+# origin on the pen position and baseline. Every contour starts with a move
+# and ends on its start point, cubic outlines of CFF faces stay cubic.
+#
+#     for segment in glyphOutline(font, glyphIndex(font, 'g'))
+#         segment.kind == segmentCubic && plotCubic(pen, segment.controls..., segment.to)
+#         pen = segment.to
+#     end
+#
+# Providers may implement
+#
+#     glyphOutline(provider, glyphIdx) -> Vector{PathSegment}
+#
+# the others are read back from the quadratic curves of `loadGlyph!`.
+
+@enum SegmentKind segmentMove segmentLine segmentQuad segmentCubic
+
+struct PathSegment
+    kind::SegmentKind
+    to::NTuple{2, Float32}
+    # control points in order, one for quads and two for cubics; unused ones repeat `to`
+    controls::NTuple{2, NTuple{2, Float32}}
+end
+
+moveSegment(p) = PathSegment(segmentMove, p, (p, p))
+lineSegment(p) = PathSegment(segmentLine, p, (p, p))
+quadSegment(c, p) = PathSegment(segmentQuad, p, (c, c))
+cubicSegment(c1, c2, p) = PathSegment(segmentCubic, p, (c1, c2))
+
+"""
+    glyphOutline(font, glyphIdx) -> Vector{PathSegment}
+    glyphOutline(font, chr)
+
+Outline of a glyph in font units. Registered custom glyphs and hex boxes
+give their quadratic curves, invisible glyphs an empty path.
+"""
+function glyphOutline(font::FontFace, glyphIdx)
+    isInvisibleGlyph(glyphIdx) && return PathSegment[]
+    isCustomGlyph(glyphIdx) || return glyphOutline(font.provider, glyphIdx)
+    return curvesOutline(glyphCurves(font, prepareGlyph(font, glyphIdx)), font.emSize)
+end
+
+glyphOutline(font::FontFace, chr::Char) = glyphOutline(font, glyphIndex(font, chr))
+
+function glyphOutline(provider::FontProvider, glyphIdx)
+    curves = BufferCurve[]
+    loadGlyph!(curves, provider, glyphIdx)
+    return curvesOutline(curves, fontMetrics(provider).unitsPerEm)
+end
+
+# Em space quadratics in font units, straight ones, control point halfway, as lines.
+function curvesOutline(curves, unitsPerEm)
+    segments = PathSegment[]
+    scale = Float32(unitsPerEm)
+    for range in contourRanges(curves)
+        push!(segments, moveSegment((curves[first(range)].x0, curves[first(range)].y0).*scale))
+        for c in curves[range]
+            (p0, p1, p2) = ((c.x0, c.y0), (c.x1, c.y1), (c.x2, c.y2))
+            straight = all(isapprox.(p1, (p0 .+ p2)./2; atol=1f-6))
+            push!(segments, straight ? lineSegment(p2.*scale) : quadSegment(p1.*scale, p2.*scale))
+        end
+    end
+    return segments
+end
+
+function glyphOutline(provider::FreeTypeProvider, glyphIdx)
+    err = FT_Load_Glyph(provider.face, glyphIdx, provider.loadFlags)
+    err == 0 || throw(FontRenderError(glyphMissingError, "Could not load glyph $glyphIdx : Errored $err"))
+    outline = (provider.face.glyph |> unsafe_load).outline
+    segments = PathSegment[]
+    outline.n_points == 0 && return segments
+    tags = [tag & 0x03 for tag in unsafe_wrap(Array, outline.tags, outline.n_points)]
+    points = [(Float32(p.x), Float32(p.y)) for p in unsafe_wrap(Array, outline.points, outline.n_points)]
+    # contour end points are zero based indices into the outline points
+    start = 1
+    for last in unsafe_wrap(Array, outline.contours, outline.n_contours)
+        appendContour!(segments, view(points, start:(last + 1)), view(tags, start:(last + 1)))
+        start = last + 2
+    end
+    return segments
+end
+
+# One TrueType or CFF contour, consecutive conic control points imply the
+# on curve point halfway between them.
+function appendContour!(segments, points, tags)
+    n = length(points)
+    n < 2 && return segments
+    k = findfirst(==(FT_CURVE_TAG_ON), tags)
+    # contours of conic points only start halfway between the last and the first
+    (start, order) = k === nothing ? ((points[end] .+ points[1])./2, 1:n) : (points[k], (mod1(k + i, n) for i in 1:(n - 1)))
+    push!(segments, moveSegment(start))
+    pending = NTuple{2, Float32}[]
+    function segmentTo(p)
+        push!(segments,
+            isempty(pending) ? lineSegment(p) :
+            length(pending) == 1 ? quadSegment(pending[1], p) : cubicSegment(pending[1], pending[2], p))
+        empty!(pending)
+    end
+    for i in order
+        (p, tag) = (points[i], tags[i])
+        if tag == FT_CURVE_TAG_ON
+            segmentTo(p)
+        elseif tag == FT_CURVE_TAG_CONIC && !isempty(pending)
+            segmentTo((pending[1] .+ p)./2)
+            push!(pending, p)
+        else
+            push!(pending, p)
+        end
+    end
+    (isempty(pending) && segments[end].to == start) || segmentTo(start)
+    return segments
+end
@@ -0,0 +1,54 @@
+# Per glyph choice between the analytic curve path and the atlases.
+# The default policy looks at the pixels per em a glyph is drawn at, after
+# its per glyph transform: below `RenderOptions.rasterTextThreshold` axis
+# aligned glyphs are bitmaps, below `smallTextThreshold` distance fields and
+# everything else curves. Zoomable sections always take curves, atlas cells
+# would be magnified. A custom `RenderOptions.pathPolicy` receives
+#
+#     pathPolicy(options, pg, pixelSize, zoomable) -> RenderPath
+#
+# Decisions of the last prepare! are counted in `pathStats(target)`.
+
+@enum RenderPath pathCurves pathSDF pathBitmap
+
+effectivePixelSize(pg::PositionedGlyph) = pg.size*sqrt(abs(pg.transform.a*pg.transform.d - pg.transform.b*pg.transform.c))
+
+# Bitmaps are rasterized upright, rotated or skewed glyphs resample badly.
+isAxisAligned(t::Affine2) = t.b == 0 && t.c == 0
+
+function defaultPathPolicy(options, pg::PositionedGlyph, pixelSize, zoomable)
+    enableAtlas && !zoomable || return pathCurves
+    pixelSize < options.rasterTextThreshold && isAxisAligned(pg.transform) && return pathBitmap
+    pixelSize < options.smallTextThreshold && return pathSDF
+    return pathCurves
+end
+
+mutable struct PathStats
+    curves::Int
+    sdf::Int
+    bitmap::Int
+    # decisions per rounded pixel size, (curves, sdf, bitmap)
+    bySize::Dict{Int, NTuple{3, Int}}
+end
+
+PathStats() = PathStats(0, 0, 0, Dict{Int, NTuple{3, Int}}())
+
+function reset!(stats::PathStats)
+    (stats.curves, stats.sdf, stats.bitmap) = (0, 0, 0)
+    empty!(stats.bySize)
+    return stats
+end
+
+function record!(stats::PathStats, path::RenderPath, pixelSize)
+    hit = (path == pathCurves, path == pathSDF, path == pathBitmap)
+    stats.curves += hit[1]
+    stats.sdf += hit[2]
+    stats.bitmap += hit[3]
+    key = round(Int, pixelSize)
+    stats.bySize[key] = get(stats.bySize, key, (0, 0, 0)) .+ hit
+    return stats
+end
+
+function Base.show(io::IO, stats::PathStats)
+    print(io, "PathStats(curves=$(stats.curves), sdf=$(stats.sdf), bitmap=$(stats.bitmap))")
+end
@@ -0,0 +1,66 @@
+# Projection and model transforms uploaded with every draw.
+# Layout works in pixels, the projection maps those into clip space.
+
+using StaticArrays
+
+const Mat4 = SMatrix{4, 4, Float32, 16}
+
+const identityMat4 = one(Mat4)
+
+struct Projection
+    matrix::Mat4
+end
+
+# Pixel space with the origin in the top left corner and y growing downwards.
+# `depth` is the clip space depth of the text plane, use the near plane depth
+# of the active convention to draw overlays on top of a depth tested scene.
+function orthographic(width, height; depth=0)
+    Projection(
+        Mat4(
+            2/width, 0, 0, 0,
+            0, -2/height, 0, 0,
+            0, 0, 1, 0,
+            -1, 1, depth, 1
+        )
+    )
+end
+
+# For text placed in a scene, the camera already provides the projection.
+fromCamera(viewProj::AbstractMatrix) = Projection(Mat4(viewProj))
+
+# Model matrix placing layout pixels on a plane in the scene. `xAxis` and
+# `yAxis` span the plane, layout y grows downwards so it runs against `yAxis`.
+function textPlane(origin, xAxis, yAxis; unitsPerPixel=1)
+    x = Float32.(xAxis).*unitsPerPixel
+    y = -Float32.(yAxis).*unitsPerPixel
+    n = (x[2]*y[3] - x[3]*y[2], x[3]*y[1] - x[1]*y[3], x[1]*y[2] - x[2]*y[1])
+    Mat4(
+        x[1], x[2], x[3], 0,
+        y[1], y[2], y[3], 0,
+        n[1], n[2], n[3], 0,
+        origin[1], origin[2], origin[3], 1
+    )
+end
+
+# Billboards keep text facing the camera. The layout is expanded around
+# `anchor` in view space, so `transform` is expected to be view*model and the
+# projection the bare camera projection.
+#   billboardConstantSize   layout pixels stay screen pixels at any distance
+#   billboardDistanceScaled layout pixels become `unitsPerPixel` view units
+@enum BillboardMode::UInt32 begin
+    billboardNone = 0
+    billboardConstantSize = 1
+    billboardDistanceScaled = 2
+end
+
+struct Billboard
+    anchor::NTuple{3, Float32}
+    mode::BillboardMode
+    unitsPerPixel::Float32
+    viewport::NTuple{2, Float32}
+end
+
+Billboard(anchor, viewport; mode=billboardConstantSize, unitsPerPixel=1) =
+    Billboard(anchor, mode, unitsPerPixel, viewport)
+
+const noBillboard = Billboard((0, 0, 0), billboardNone, 1, (1, 1))
@@ -0,0 +1,64 @@
+# A `FontProvider` supplies glyph outlines and metrics to the renderer core.
+# FreeType faces are the default source, custom binary formats, font servers
+# or procedurally generated glyphs plug in by implementing
+#
+#     fontMetrics(provider) -> FontMetrics
+#     glyphIndex(provider, chr) -> glyph index, 0 for missing characters
+#     loadGlyph!(curves, provider, glyphIdx) -> GlyphMetrics
+#
+# `loadGlyph!` appends the outline to `curves` as quadratic `BufferCurve`s in
+# em units. Kerning, the resource label and
+#
+#     rasterizeGlyph(provider, glyphIdx, pixelSize; color) -> (bitmap, left, top, ppem)
+#
+#     sfntTable(provider, tag) -> Vector{UInt8}
+#
+# and for variable fonts
+#
+#     variationAxes(provider) -> Vector{VariationAxis}
+#     variationInstance(provider, coordinates) -> FontProvider
+#
+# are optional. Without a rasterizer the coverage atlas samples the curves,
+# without sfnt tables like GSUB text is shaped by cmap lookup alone.
+# `coordinates` maps axis tags to design values, missing axes keep their
+# default; instances are new providers, the original one stays unchanged.
+# Bitmaps are gray `UInt8` coverage or, if `color` is set and the glyph has
+# one, premultiplied rgba `NTuple{4, UInt8}` texels.
+
+abstract type FontProvider end
+
+# All metrics are in font units, y grows upwards.
+struct FontMetrics
+    unitsPerEm::Int
+    ascender::Int
+    descender::Int
+    height::Int         # baseline to baseline distance
+end
+
+struct GlyphMetrics
+    width::Int
+    height::Int
+    bearingX::Int
+    bearingY::Int
+    advance::Int
+end
+
+# Design space range of one axis, e.g. "opsz" or "wght".
+struct VariationAxis
+    tag::String
+    minimum::Float32
+    default::Float32
+    maximum::Float32
+end
+
+function fontMetrics end
+function glyphIndex end
+function loadGlyph! end
+
+hasKerning(::FontProvider) = false
+kerning(::FontProvider, left, right) = 0
+fontLabel(::FontProvider) = "font"
+rasterizeGlyph(::FontProvider, glyphIdx, pixelSize; color=false) = nothing
+sfntTable(::FontProvider, tag) = nothing
+variationAxes(::FontProvider) = VariationAxis[]
+variationInstance(::FontProvider, coordinates) = nothing
@@ -0,0 +1,606 @@
+# GPU side of the curve renderer.
+# Every font owns a glyph and a curve storage buffer which are bound together
+# with a per draw uniform buffer in bind group 0.
+
+struct FontUniforms
+    projection::Mat4
+    transform::Mat4
+    color::NTuple{4, Float32}
+    antiAliasingWindowSize::Float32
+    enableSuperSamplingAntiAliasing::UInt32
+    billboardMode::UInt32
+    billboardScale::Float32
+    billboardAnchor::NTuple{4, Float32}
+    viewport::NTuple{2, Float32}
+    antiAliasingMode::UInt32
+    time::Float32
+    waveFrequency::Float32
+    shakeRate::Float32
+    fadeDuration::Float32
+    linearizeColors::UInt32
+    heatmapScale::Float32
+    padding::NTuple{3, UInt32}
+end
+
+# Isotropic windows come from fwidth(uv) and over blur text seen at grazing
+# angles, anisotropic windows use the full uv jacobian per ray direction.
+@enum AntiAliasingMode::UInt32 begin
+    antiAliasingIsotropic = 0
+    antiAliasingAnisotropic = 1
+end
+
+# Vertex colors carry the style colors, `tint` multiplies all of them.
+function FontUniforms(
+        projection::Projection;
+        tint=(1f0, 1f0, 1f0, 1f0),
+        linearizeColors=false,
+        transform=identityMat4,
+        antiAliasingWindowSize=1.0f0,
+        enableSuperSamplingAntiAliasing=true,
+        antiAliasingMode=antiAliasingIsotropic,
+        billboard=nothing,
+        time=0f0,
+        waveFrequency=6f0,
+        shakeRate=20f0,
+        fadeDuration=0.25f0,
+        heatmapScale=64f0
+    )
+    billboard = something(billboard, noBillboard)
+    FontUniforms(
+        projection.matrix,
+        transform,
+        tint,
+        antiAliasingWindowSize,
+        enableSuperSamplingAntiAliasing,
+        UInt32(billboard.mode),
+        billboard.unitsPerPixel,
+        (billboard.anchor..., 1),
+        billboard.viewport,
+        UInt32(antiAliasingMode),
+        time,
+        waveFrequency,
+        shakeRate,
+        fadeDuration,
+        linearizeColors,
+        heatmapScale,
+        (0, 0, 0)
+    )
+end
+
+# Debug views replace the coverage of curve glyphs. The heatmap shows the
+# curves every fragment evaluates on a ramp from blue over green and yellow
+# to red at `heatmapScale` curves, the quad view tints and outlines every
+# glyph quad and the overdraw view brightens with the quads covering a pixel.
+@enum DebugView begin
+    debugViewNone
+    debugViewHeatmap
+    debugViewQuads
+    debugViewOverdraw
+end
+
+# Premultiplied is regular over compositing, additive suits glowing overlays.
+@enum BlendMode begin
+    blendPremultiplied
+    blendAdditive
+end
+
+mutable struct FontPipeline
+    device
+    format
+    sampleCount::Int
+    blendMode::BlendMode
+    depthFormat
+    depthConvention
+    shader
+    bindGroupLayout
+    pipelineLayout
+    pipeline
+end
+
+# Curves are split into chunks that each fit one storage binding. Glyph starts
+# are relative to the chunk of the glyph, every chunk is drawn with its own
+# bind group.
+mutable struct FontBuffers
+    font::FontFace
+    glyphBuffer
+    curveBuffers::Vector{Any}
+    glyphChunks::Vector{Int32}      # chunk of every buffer glyph
+    glyphCount::Int
+    curveCount::Int
+end
+
+mutable struct TextDraw
+    vertexBuffer
+    indexBuffer
+    indexCount::Int
+    uniformBuffer
+    bindGroup
+end
+
+# Shaders are embedded at precompile time and validated with naga when the
+# cli is installed, so typos fail precompilation instead of pipeline creation.
+const shaderDir = joinpath(@__DIR__, "shaders")
+
+function validateShader(path)
+    naga = Sys.which("naga")
+    if naga === nothing
+        @debug "naga not found, skipping validation of $path"
+        return
+    end
+    output = IOBuffer()
+    success(pipeline(`$naga $path`; stdout=output, stderr=output)) ||
+        throw(FontRenderError(shaderCompileError, "Invalid shader $path :\n$(String(take!(output)))"))
+end
+
+# embedded source of every shader by file name, see hotreload.jl
+const embeddedShaders = Dict{String, String}()
+
+# Fragments appended to another shader only validate together with it.
+function embedShader(name; appendedTo="")
+    path = joinpath(shaderDir, name)
+    include_dependency(path)
+    source = read(path, String)
+    if isempty(appendedTo)
+        validateShader(path)
+    else
+        mktempdir() do dir
+            combined = joinpath(dir, name)
+            write(combined, appendedTo * source)
+            validateShader(combined)
+        end
+    end
+    return embeddedShaders[name] = source
+end
+
+const fontShaderSource = embedShader("font.wgsl")
+
+getShaderCode() = latestSource(fontShaderSource)
+
+function compileShader(device, label, source::String)
+    source = curveSource(device, source)
+    checkLayouts(source, label)
+    try
+        descriptor = WGPUCore.loadWGSL(source |> Vector{UInt8}) |> first
+        return WGPUCore.createShaderModule(device, label, descriptor, nothing, nothing)
+    catch err
+        throw(FontRenderError(shaderCompileError, "Could not compile $label : $err"))
+    end
+end
+
+function createStorageBuffer(device, label, data)
+    try
+        (buffer, _) = WGPUCore.createBufferWithData(device, label, data, ["Storage", "CopyDst"])
+        return buffer
+    catch err
+        throw(FontRenderError(deviceLimitError, "Could not create $label of $(sizeof(data)) bytes : $err"))
+    end
+end
+
+
+function getVertexBufferLayout(::Type{BufferVertex}; offset = 0)
+    WGPUCore.GPUVertexBufferLayout => [
+        :arrayStride => sizeof(BufferVertex),
+        :stepMode => "Vertex",
+        :attributes => [
+            :attribute => [
+                :format => "Float32x2",
+                :offset => fieldoffset(BufferVertex, 1),
+                :shaderLocation => offset + 0
+            ],
+            :attribute => [
+                :format => "Float32x2",
+                :offset => fieldoffset(BufferVertex, 3),
+                :shaderLocation => offset + 1
+            ],
+            :attribute => [
+                :format => "Sint32",
+                :offset => fieldoffset(BufferVertex, 5),
+                :shaderLocation => offset + 2
+            ],
+            :attribute => [
+                :format => "Float32x2",
+                :offset => fieldoffset(BufferVertex, 6),
+                :shaderLocation => offset + 3
+            ],
+            :attribute => [
+                :format => "Uint32",
+                :offset => fieldoffset(BufferVertex, 8),
+                :shaderLocation => offset + 4
+            ],
+            :attribute => [
+                :format => "Unorm8x4",
+                :offset => fieldoffset(BufferVertex, 9),
+                :shaderLocation => offset + 5
+            ],
+        ]
+    ]
+end
+
+
+function getBindingLayouts(::Type{FontFace}; binding=0)
+    bindingLayouts = [
+        WGPUCore.WGPUBufferEntry => [
+            :binding => binding,
+            :visibility => ["Vertex", "Fragment"],
+            :type => "Uniform"
+        ],
+        WGPUCore.WGPUBufferEntry => [
+            :binding => binding + 1,
+            :visibility => ["Fragment"],
+            :type => "ReadOnlyStorage"
+        ],
+        WGPUCore.WGPUBufferEntry => [
+            :binding => binding + 2,
+            :visibility => ["Fragment"],
+            :type => "ReadOnlyStorage"
+        ],
+    ]
+    return bindingLayouts
+end
+
+
+function getBindings(fontBuffers::FontBuffers, uniformBuffer; binding=0, chunk=1)
+    bindings = [
+        WGPUCore.GPUBuffer => [
+            :binding => binding,
+            :buffer  => uniformBuffer,
+            :offset  => 0,
+            :size    => uniformBuffer.size
+        ],
+        WGPUCore.GPUBuffer => [
+            :binding => binding + 1,
+            :buffer  => fontBuffers.glyphBuffer,
+            :offset  => 0,
+            :size    => fontBuffers.glyphBuffer.size
+        ],
+        WGPUCore.GPUBuffer => [
+            :binding => binding + 2,
+            :buffer  => fontBuffers.curveBuffers[chunk],
+            :offset  => 0,
+            :size    => fontBuffers.curveBuffers[chunk].size
+        ],
+    ]
+end
+
+
+# Depth conventions describe which clip space depth is near and how depth
+# compares, engines with reverse-z infinite projections use `reverseDepth`.
+struct DepthConvention
+    compare
+    clearValue::Float32
+    near::Float32
+    far::Float32
+end
+
+const standardDepth = DepthConvention(WGPUCore.WGPUCompareFunction_LessEqual, 1, 0, 1)
+const reverseDepth = DepthConvention(WGPUCore.WGPUCompareFunction_GreaterEqual, 0, 1, 0)
+
+# Positive bias always pulls text towards the camera, which keeps labels
+# coplanar with scene geometry from z-fighting.
+biasTowardsCamera(convention::DepthConvention, bias) =
+    convention.near < convention.far ? -bias : bias
+
+# Text only writes depth where it has coverage, so glyph quads overlapping
+# their neighbours at the same depth do not occlude them.
+function depthStencilState(
+        depthFormat;
+        depthConvention=standardDepth,
+        depthWrite=true,
+        depthBias=0,
+        depthBiasSlopeScale=0f0,
+        stencil=[]
+    )
+    depthFormat === nothing && return []
+    return [
+        :depthWriteEnabled => depthWrite,
+        :depthCompare => depthConvention.compare,
+        :format => depthFormat,
+        :depthBias => biasTowardsCamera(depthConvention, depthBias),
+        :depthBiasSlopeScale => biasTowardsCamera(depthConvention, depthBiasSlopeScale),
+        stencil...
+    ]
+end
+
+# shader output is premultiplied
+function blendState(mode::BlendMode)
+    dstFactor = mode == blendAdditive ? "One" : "OneMinusSrcAlpha"
+    [
+        :color => [
+            :srcFactor => "One",
+            :dstFactor => dstFactor,
+            :operation => "Add"
+        ],
+        :alpha => [
+            :srcFactor => "One",
+            :dstFactor => dstFactor,
+            :operation => "Add",
+        ]
+    ]
+end
+
+# Variants of one renderer pass `layouts=(shader, bindGroupLayout, pipelineLayout)`
+# of an existing pipeline so bind groups stay valid across all of them.
+# Other paths (the distance field atlas, terminal grids) pass their own shader,
+# bindings and vertex buffers.
+function createFontPipeline(
+        device, format;
+        sampleCount=1,
+        blendMode=blendPremultiplied,
+        depthFormat=nothing,
+        depthConvention=standardDepth,
+        layouts=nothing,
+        label="font",
+        shaderSource=getShaderCode(),
+        bindingLayouts=getBindingLayouts(FontFace),
+        vertexEntryPoint="vs_main",
+        fragmentEntryPoint="fs_main",
+        vertexBuffers=[getVertexBufferLayout(BufferVertex)],
+        depthOptions...
+    )
+    shaderSource = latestSource(shaderSource)
+    (shader, bindGroupLayout, pipelineLayout) = if layouts === nothing
+        shader = compileShader(device, "$label shader", shaderSource)
+        bindGroupLayout = WGPUCore.createBindGroupLayout(device, "$label bind group layout", bindingLayouts)
+        (shader, bindGroupLayout, WGPUCore.createPipelineLayout(device, "$label pipeline layout", bindGroupLayout))
+    else
+        layouts
+    end
+
+    renderpipelineOptions(shader) = [
+        WGPUCore.GPUVertexState => [
+            :_module => shader,
+            :entryPoint => vertexEntryPoint,
+            :buffers => vertexBuffers
+        ],
+        WGPUCore.GPUPrimitiveState => [
+            :topology => "TriangleList",
+            :frontFace => "CCW",
+            :cullMode => "None",
+            :stripIndexFormat => "Undefined"
+        ],
+        WGPUCore.GPUDepthStencilState => depthStencilState(depthFormat; depthConvention=depthConvention, depthOptions...),
+        WGPUCore.GPUMultiSampleState => [
+            :count => sampleCount,
+            :mask => typemax(UInt32),
+            :alphaToCoverageEnabled => false,
+        ],
+        WGPUCore.GPUFragmentState => [
+            :_module => shader,
+            :entryPoint => fragmentEntryPoint,
+            :targets => [
+                WGPUCore.GPUColorTargetState => [
+                    :format => format,
+                    blendState(blendMode)...
+                ],
+            ]
+        ]
+    ]
+
+    buildPipeline(shader) = @span "pipeline" WGPUCore.createRenderPipeline(
+        device, pipelineLayout,
+        renderpipelineOptions(shader);
+        label="$label pipeline"
+    )
+
+    fp = FontPipeline(device, format, sampleCount, blendMode, depthFormat, depthConvention, shader, bindGroupLayout, pipelineLayout, buildPipeline(shader))
+    enableHotReload && watchPipeline!(fp, shaderSource, buildPipeline)
+    return fp
+end
+
+
+# Storage buffers can not be empty, a zeroed element keeps the binding valid.
+nonEmpty(data::Vector{T}) where T = isempty(data) ? [zero(T)] : data
+
+Base.zero(::Type{BufferGlyph}) = BufferGlyph(0, 0)
+Base.zero(::Type{BufferCurve}) = BufferCurve(0, 0, 0, 0, 0, 0)
+
+# WebGPU guarantees 128 MiB, adapters usually report more.
+const defaultStorageBindingLimit = 128 << 20
+
+function maxStorageBufferBindingSize(device)
+    hasproperty(device, :supportedLimits) || return defaultStorageBindingLimit
+    return Int(device.supportedLimits.limits.maxStorageBufferBindingSize)
+end
+
+# Greedy packing in buffer order keeps the chunk of a glyph stable while the font grows.
+function chunkGlyphs(bufferGlyphs::Vector{BufferGlyph}, curveCount, maxCurves)
+    glyphs = BufferGlyph[]
+    glyphChunks = Int32[]
+    ranges = UnitRange{Int}[]
+    chunkStart = 0
+    for glyph in bufferGlyphs
+        if glyph.start + glyph.count - chunkStart > maxCurves && glyph.start > chunkStart
+            push!(ranges, chunkStart:(glyph.start - 1))
+            chunkStart = glyph.start
+        end
+        push!(glyphs, BufferGlyph(glyph.start - chunkStart, glyph.count))
+        push!(glyphChunks, length(ranges) + 1)
+    end
+    push!(ranges, chunkStart:(curveCount - 1))
+    return (glyphs, glyphChunks, ranges)
+end
+
+function uploadFont(device, font::FontFace; maxBindingSize=maxStorageBufferBindingSize(device))
+    label = fontLabel(font)
+    (glyphs, glyphChunks, chunks) = encodeCurves(device, font, maxBindingSize)
+    glyphBuffer = @span "upload" createStorageBuffer(device, "$label glyph buffer", nonEmpty(glyphs))
+    curveBuffers = map(enumerate(chunks)) do (chunk, curves)
+        @span "upload" createStorageBuffer(device, "$label curve buffer $chunk", nonEmpty(curves))
+    end
+    return FontBuffers(font, glyphBuffer, curveBuffers, glyphChunks, length(font.bufferGlyphs), length(font.bufferCurves))
+end
+
+chunkCount(fontBuffers::FontBuffers) = length(fontBuffers.curveBuffers)
+glyphChunk(fontBuffers::FontBuffers, glyph::Glyph) = Int(fontBuffers.glyphChunks[glyph.bufferIndex + 1])
+glyphChunk(fontBuffers::FontBuffers, pg::PositionedGlyph) = glyphChunk(fontBuffers, pg.glyph)
+
+# One layout per face in order of appearance; optical sizes and synthetic
+# styles place glyphs of faces derived from the style's font.
+function splitByFont(layout::TextLayout)
+    isempty(layout.glyphs) && return Tuple{FontFace, TextLayout}[]
+    font = layout.glyphs[1].font
+    all(pg -> pg.font === font, layout.glyphs) && return [(font, layout)]
+    fonts = IdDict{FontFace, Vector{PositionedGlyph}}()
+    order = FontFace[]
+    for pg in layout.glyphs
+        haskey(fonts, pg.font) || push!(order, pg.font)
+        push!(get!(Vector{PositionedGlyph}, fonts, pg.font), pg)
+    end
+    return [(font, TextLayout(fonts[font], layout.width, layout.height)) for font in order]
+end
+
+# One layout per curve chunk, a single chunk keeps the layout as is.
+function splitByChunk(layout::TextLayout, fontBuffers::FontBuffers)
+    chunkCount(fontBuffers) == 1 && return [(1, layout)]
+    chunks = Dict{Int, Vector{PositionedGlyph}}()
+    for pg in layout.glyphs
+        push!(get!(Vector{PositionedGlyph}, chunks, glyphChunk(fontBuffers, pg)), pg)
+    end
+    return [(chunk, TextLayout(chunks[chunk], layout.width, layout.height)) for chunk in sort!(collect(keys(chunks)))]
+end
+
+# Glyphs are built lazily, so buffers are recreated whenever the font grew.
+function updateFont(device, fontBuffers::FontBuffers)
+    font = fontBuffers.font
+    if fontBuffers.glyphCount == length(font.bufferGlyphs) &&
+            fontBuffers.curveCount == length(font.bufferCurves)
+        return fontBuffers
+    end
+    return uploadFont(device, font)
+end
+
+
+function uploadGeometry(device, layout::TextLayout)
+    (vertices, indices) = buildVertices(layout)
+    isempty(indices) && return nothing
+    @span "upload" begin
+        (vertexBuffer, _) = WGPUCore.createBufferWithData(device, "text vertex buffer", vertices, ["Vertex", "CopySrc"])
+        (indexBuffer, _) = WGPUCore.createBufferWithData(device, "text index buffer", indices, ["Index"])
+    end
+    return (vertexBuffer, indexBuffer, length(indices))
+end
+
+# Single bind group draws, fonts split into several curve chunks go through `TextRenderer`.
+function createUniformBindGroup(fp::FontPipeline, fontBuffers::FontBuffers, uniforms::FontUniforms)
+    chunkCount(fontBuffers) == 1 || throw(FontRenderError(
+        deviceLimitError,
+        "$(fontLabel(fontBuffers.font)) needs $(chunkCount(fontBuffers)) curve bindings, draw it with a TextRenderer"
+    ))
+    (uniformBuffer, _) = WGPUCore.createBufferWithData(
+        fp.device, "text uniform buffer",
+        [uniforms],
+        ["Uniform", "CopyDst"]
+    )
+    bindGroup = WGPUCore.createBindGroup(
+        "text bind group", fp.device,
+        fp.bindGroupLayout,
+        getBindings(fontBuffers, uniformBuffer)
+    )
+    return (uniformBuffer, bindGroup)
+end
+
+function prepareText(
+        fp::FontPipeline, fontBuffers::FontBuffers,
+        layout::TextLayout, projection::Projection;
+        transform=identityMat4,
+        billboard=nothing,
+        uniformOptions...
+    )
+    geometry = uploadGeometry(fp.device, layout)
+    geometry === nothing && return nothing
+    (vertexBuffer, indexBuffer, indexCount) = geometry
+
+    uniforms = FontUniforms(projection; transform=transform, billboard=billboard, uniformOptions...)
+    (uniformBuffer, bindGroup) = createUniformBindGroup(fp, fontBuffers, uniforms)
+
+    return TextDraw(vertexBuffer, indexBuffer, indexCount, uniformBuffer, bindGroup)
+end
+
+prepareText(fp::FontPipeline, fontBuffers::FontBuffers, layout::TextLayout, targetSize::Tuple; kwargs...) =
+    prepareText(fp, fontBuffers, layout, orthographic(targetSize...); kwargs...)
+
+
+function drawText(renderPass, fp::FontPipeline, textDraw::TextDraw)
+    withDebugGroup(renderPass, "text") do
+        WGPUCore.setPipeline(renderPass, fp.pipeline)
+        WGPUCore.setIndexBuffer(renderPass, textDraw.indexBuffer, "Uint32")
+        WGPUCore.setVertexBuffer(renderPass, 0, textDraw.vertexBuffer)
+        WGPUCore.setBindGroup(renderPass, 0, textDraw.bindGroup, UInt32[], 0, 99)
+        WGPUCore.drawIndexed(
+            renderPass, textDraw.indexCount;
+            instanceCount=1, firstIndex=0, baseVertex=0, firstInstance=0
+        )
+    end
+end
+
+drawText(renderPass, fp::FontPipeline, ::Nothing) = nothing
+
+# Offscreen draws outside a `TextRenderer`, one per font and curve chunk of
+# `layout` sharing one uniform buffer. `buffersOf` returns the uploaded
+# buffers of a font, after the layout built all its glyphs.
+function prepareChunkedText(fp::FontPipeline, buffersOf, layout::TextLayout, projection::Projection; uniformOptions...)
+    (uniformBuffer, _) = WGPUCore.createBufferWithData(
+        fp.device, "text uniform buffer",
+        [FontUniforms(projection; uniformOptions...)],
+        ["Uniform", "CopyDst"]
+    )
+    draws = TextDraw[]
+    for (font, fontLayout) in splitByFont(layout)
+        fontBuffers = buffersOf(font)
+        for (chunk, chunkLayout) in splitByChunk(fontLayout, fontBuffers)
+            geometry = uploadGeometry(fp.device, chunkLayout)
+            geometry === nothing && continue
+            bindGroup = WGPUCore.createBindGroup(
+                "text bind group", fp.device,
+                fp.bindGroupLayout,
+                getBindings(fontBuffers, uniformBuffer; chunk=chunk)
+            )
+            push!(draws, TextDraw(geometry..., uniformBuffer, bindGroup))
+        end
+    end
+    return draws
+end
+
+drawText(renderPass, fp::FontPipeline, draws::Vector{TextDraw}) =
+    foreach(textDraw -> drawText(renderPass, fp, textDraw), draws)
+
+
+depthClearValue(fp::FontPipeline) = fp.depthConvention.clearValue
+
+function depthAttachmentOptions(depthView; depthClearValue=1.0f0, loadOp=WGPUCore.WGPULoadOp_Clear)
+    depthView === nothing && return []
+    return [
+        :attachments => [
+            WGPUCore.GPUDepthStencilAttachment => [
+                :view => depthView,
+                :depthClearValue => depthClearValue,
+                :depthLoadOp => loadOp,
+                :depthStoreOp => WGPUCore.WGPUStoreOp_Store,
+                :stencilLoadOp => loadOp,
+                :stencilStoreOp => WGPUCore.WGPUStoreOp_Store,
+            ]
+        ]
+    ]
+end
+
+
+function colorAttachmentOptions(view, clearColor; loadOp=WGPUCore.WGPULoadOp_Clear, depthView=nothing, depthClearValue=1.0f0)
+    [
+        WGPUCore.GPUColorAttachments => [
+            :attachments => [
+                WGPUCore.GPUColorAttachment => [
+                    :view => view,
+                    :resolveTarget => C_NULL,
+                    :clearValue => clearColor,
+                    :loadOp => loadOp,
+                    :storeOp => WGPUCore.WGPUStoreOp_Store,
+                ],
+            ]
+        ],
+        WGPUCore.GPUDepthStencilAttachments => depthAttachmentOptions(
+            depthView;
+            depthClearValue=depthClearValue,
+            loadOp=loadOp
+        ),
+    ]
+end
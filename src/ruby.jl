@@ -0,0 +1,83 @@
+# Ruby annotations, furigana over Japanese or pinyin over Chinese.
+# Segments are plain strings or `Ruby` pairs of a base run and the run
+# annotating it. Annotations are set at `rubyScale` of the base size above
+# the base, or to its right in vertical columns, centered on it, and the
+# shorter of the two is centered on the longer one. Lines and columns make
+# room for the annotations whenever they carry any.
+
+struct Ruby
+    base::String
+    annotation::String
+end
+
+"""
+    layoutRuby(segments, style; origin, transform, rubyScale=0.5) -> TextLayout
+
+Lays out strings and `Ruby` pairs continuing each other's pen, newlines of
+the plain strings start new lines. Clusters index the concatenated text of
+the strings and bases, annotation glyphs share the cluster of their base's
+first character.
+"""
+function layoutRuby(segments, style::TextStyle; origin=(0f0, 0f0), transform=nothing, rubyScale=0.5f0)
+    annotationStyle = resolvedStyle(setfields(style; size=style.size*rubyScale))
+    style = resolvedStyle(style)
+    font = style.font
+    scale = pixelScale(style)
+    vertical = style.writingMode == verticalRightToLeft
+    shape = vertical ? shapeVertical : shapeStyled
+    annotated = any(s -> s isa Ruby, segments)
+    annotationFont = annotationStyle.font
+    annotationScale = pixelScale(annotationStyle)
+    # room the annotations take across the line
+    annotationExtent = !annotated ? 0f0 : vertical ? annotationStyle.size :
+        (annotationFont.metrics.ascender - annotationFont.metrics.descender)*annotationScale
+    lineAdvance = font.metrics.height*scale*style.lineHeight + annotationExtent
+    lineCount = 1 + sum(s -> s isa Ruby ? 0 : count(==('\n'), s), segments; init=0)
+
+    positioned = PositionedGlyph[]
+    (x0, y0) = origin
+    width = vertical ? lineCount*lineAdvance : 0f0
+    height = vertical ? 0f0 : lineCount*lineAdvance
+    line = 1
+    # the column center of vertical text, the baseline of horizontal text
+    across() = vertical ? x0 + width - (line - 1)*lineAdvance - annotationExtent - (lineAdvance - annotationExtent)/2 :
+        y0 + (line - 1)*lineAdvance + annotationExtent + font.metrics.ascender*scale
+    pen = vertical ? y0 : x0
+    place!(style, shaped, text, start, position; clusterOffset=0) = vertical ?
+        placeColumn!(positioned, style, shaped, text, position, start; clusterOffset=clusterOffset) :
+        placeShaped!(positioned, style, shaped, start, position)
+    advanceOf(shaped) = sum(g -> g[3], shaped; init=0f0)
+    extend!(pen) = vertical ? (height = max(height, pen - y0)) : (width = max(width, pen - x0))
+
+    offset = 0
+    for segment in segments
+        if segment isa Ruby
+            base = shape(style, segment.base; clusterOffset=offset)
+            annotation = shape(annotationStyle, segment.annotation)
+            (baseLength, annotationLength) = (advanceOf(base), advanceOf(annotation))
+            extent = max(baseLength, annotationLength)
+            place!(style, base, segment.base, pen + (extent - baseLength)/2, across(); clusterOffset=offset)
+            annotationAcross = vertical ? across() + (style.size + annotationStyle.size)/2 :
+                across() - font.metrics.ascender*scale + annotationFont.metrics.descender*annotationScale
+            firstGlyph = length(positioned) + 1
+            place!(annotationStyle, annotation, segment.annotation, pen + (extent - annotationLength)/2, annotationAcross)
+            positioned[firstGlyph:end] .= [setfields(pg; cluster=offset + 1) for pg in positioned[firstGlyph:end]]
+            pen += extent
+            offset += ncodeunits(segment.base)
+        else
+            for (i, part) in enumerate(eachsplit(segment, '\n'))
+                if i > 1
+                    line += 1
+                    pen = vertical ? y0 : x0
+                end
+                shaped = shape(style, part; clusterOffset=offset + part.offset)
+                pen = place!(style, shaped, part, pen, across(); clusterOffset=offset + part.offset)
+                extend!(pen)
+            end
+            offset += ncodeunits(segment)
+        end
+        extend!(pen)
+    end
+    layout = TextLayout(positioned, width, height)
+    return transform === nothing ? layout : transformLayout(layout, about(transform, origin))
+end
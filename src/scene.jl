@@ -0,0 +1,132 @@
+# Retained mode text.
+# Items live in a `TextScene` behind stable handles. Each item keeps its own
+# vertex and index buffers, so only inserted or updated items are shaped and
+# uploaded again, everything else is redrawn from its existing gpu resources.
+
+struct TextHandle
+    id::Int
+end
+
+mutable struct TextItem
+    section::Section
+    geometry        # (font, fontBuffers, vertexBuffer, indexBuffer, indexCount, bindGroup) per face and curve chunk
+    dirty::Bool
+end
+
+mutable struct TextScene
+    renderer::TextRenderer
+    items::Dict{Int, TextItem}
+    order::Vector{Int}          # draw order, in insertion order
+    nextId::Int
+    uniformBuffer
+end
+
+function TextScene(renderer::TextRenderer)
+    requireCurvesPath(renderer, "TextScene")
+    return TextScene(renderer, Dict{Int, TextItem}(), Int[], 1, nothing)
+end
+
+function Base.insert!(scene::TextScene, section::Section)
+    id = scene.nextId
+    scene.nextId += 1
+    scene.items[id] = TextItem(optionSection(scene.renderer.options, section), [], true)
+    push!(scene.order, id)
+    return TextHandle(id)
+end
+
+function update!(scene::TextScene, handle::TextHandle, section::Section)
+    item = scene.items[handle.id]
+    item.section = optionSection(scene.renderer.options, section)
+    item.dirty = true
+    return handle
+end
+
+function Base.delete!(scene::TextScene, handle::TextHandle)
+    delete!(scene.items, handle.id)
+    filter!(!=(handle.id), scene.order)
+    return scene
+end
+
+Base.haskey(scene::TextScene, handle::TextHandle) = haskey(scene.items, handle.id)
+Base.length(scene::TextScene) = length(scene.items)
+
+# Clean items drawn from buffers their font has replaced since, their curve
+# chunks may have moved, so they are split and uploaded again.
+isStale(renderer::TextRenderer, item::TextItem) =
+    any(((font, fontBuffers),) -> renderer.fontBuffers[font] !== fontBuffers, item.geometry)
+
+function uploadItem!(renderer::TextRenderer, item::TextItem, layout::TextLayout, uniformBuffer)
+    item.geometry = []
+    for (font, fontLayout) in splitByFont(layout)
+        fontBuffers = fontBuffersFor(renderer, font)
+        for (chunk, chunkLayout) in splitByChunk(fontLayout, fontBuffers)
+            geometry = uploadGeometry(renderer.device, chunkLayout)
+            geometry === nothing && continue
+            push!(item.geometry, (font, fontBuffers, geometry..., cachedBindGroup(renderer, fontBuffers, uniformBuffer, chunk)))
+        end
+    end
+    item.dirty = false
+    return item
+end
+
+function prepare!(scene::TextScene, projection::Projection; transform=identityMat4, kwargs...)
+    renderer = scene.renderer
+    device = renderer.device
+    uniforms = [FontUniforms(projection; transform=transform, uniformOptions(renderer)..., kwargs...)]
+    if scene.uniformBuffer === nothing
+        (scene.uniformBuffer, _) = WGPUCore.createBufferWithData(device, "text scene uniform buffer", uniforms, ["Uniform", "CopyDst"])
+    else
+        WGPUCore.writeBuffer(device.queue, scene.uniformBuffer, uniforms)
+    end
+
+    dirty = [scene.items[id] for id in scene.order if scene.items[id].dirty]
+    # fonts are uploaded after all dirty items built their glyphs
+    layouts = layoutSections([item.section for item in dirty])
+    for layout in layouts, (font, _) in splitByFont(layout)
+        fontBuffersFor(renderer, font)
+    end
+    for item in values(scene.items), (font, _) in item.geometry
+        fontBuffersFor(renderer, font)
+    end
+    stale = [scene.items[id] for id in scene.order if !scene.items[id].dirty && isStale(renderer, scene.items[id])]
+    # their glyphs exist already, laying them out again grows no font
+    append!(layouts, layoutSections([item.section for item in stale]))
+    for (item, layout) in zip([dirty; stale], layouts)
+        uploadItem!(renderer, item, layout, scene.uniformBuffer)
+    end
+    return scene
+end
+
+prepare!(scene::TextScene, targetSize::Tuple; kwargs...) =
+    prepare!(scene, orthographic(targetSize...); kwargs...)
+
+function draw!(scene::TextScene, renderPass)
+    pipeline = scene.renderer.pipeline
+    @span "encode" withDebugGroup(renderPass, "text scene") do
+        WGPUCore.setPipeline(renderPass, pipeline.pipeline)
+        for id in scene.order
+            item = scene.items[id]
+            for (_, _, vertexBuffer, indexBuffer, indexCount, bindGroup) in item.geometry
+                WGPUCore.setIndexBuffer(renderPass, indexBuffer, "Uint32")
+                WGPUCore.setVertexBuffer(renderPass, 0, vertexBuffer)
+                WGPUCore.setBindGroup(renderPass, 0, bindGroup, UInt32[], 0, 99)
+                WGPUCore.drawIndexed(
+                    renderPass, indexCount;
+                    instanceCount=1, firstIndex=0, baseVertex=0, firstInstance=0
+                )
+            end
+        end
+    end
+    return scene
+end
+
+# Every item is uploaded again on the next prepare! to the new device.
+function recreate!(scene::TextScene, device)
+    recreate!(scene.renderer, device)
+    scene.uniformBuffer = nothing
+    for item in values(scene.items)
+        item.geometry = []
+        item.dirty = true
+    end
+    return scene
+end
@@ -0,0 +1,60 @@
+# User fragment hooks for effects like scanlines, grunge or holographic text.
+# A hook is wgsl defining
+#
+#     fn shadeFragment(fragment: HookFragment) -> vec4<f32>
+#
+# which returns the premultiplied color of a fragment of a curve glyph,
+# fragments returning zero alpha are discarded. It is compiled together with
+# font.wgsl, so `uniforms`, `glyphCoverage` and the other helpers there are
+# available. Set it per renderer or per target:
+#
+#     scanlines = """
+#     fn shadeFragment(fragment: HookFragment) -> vec4<f32> {
+#         let line = 0.75 + 0.25*sin(fragment.position.y*3.14159);
+#         let color = fragment.color;
+#         return vec4<f32>(color.rgb*line*color.a, color.a)*fragment.coverage;
+#     }
+#     """
+#     reconfigure!(renderer; fragmentHook=scanlines)
+#
+# Atlas drawn glyphs and the batch paths keep their own shaders.
+
+const hookPrelude = """
+
+// Inputs of the user fragment hook, see shaderhook.jl.
+struct HookFragment {
+    // Framebuffer position of the fragment in pixels.
+    position: vec4<f32>,
+    // Glyph coverage times the animated opacity, between 0 and 1.
+    coverage: f32,
+    // Straight alpha run color, linear if the target expects it.
+    color: vec4<f32>,
+    // Glyph space position in em units, y up.
+    uv: vec2<f32>,
+    // 0 to 1 across the glyph quad.
+    quad: vec2<f32>,
+    bufferIndex: i32,
+    // Seconds of the animation clock.
+    time: f32,
+};
+
+@fragment
+fn fs_hooked(input: VertexOutput) -> @location(0) vec4<f32> {
+    var fragment: HookFragment;
+    fragment.position = input.position;
+    fragment.coverage = glyphCoverage(input.uv, input.bufferIndex)*input.opacity;
+    fragment.color = input.color;
+    fragment.uv = input.uv;
+    fragment.quad = input.quad;
+    fragment.bufferIndex = input.bufferIndex;
+    fragment.time = uniforms.time;
+    let color = shadeFragment(fragment);
+    if (color.a <= 0.0) {
+        discard;
+    }
+    return color;
+}
+
+"""
+
+hookedShaderSource(hook::AbstractString) = getShaderCode() * hookPrelude * hook
@@ -0,0 +1,87 @@
+// Compute rasterization into a storage texture, see computeraster.jl.
+// Appended to font.wgsl for its glyph and curve buffers and computeCoverage,
+// the render bindings stay unused. Every workgroup fills one 16x16 tile of
+// the target, compositing the glyphs binned into that tile in layout order.
+
+struct RasterUniforms {
+    // Premultiplied color of texels no earlier dispatch wrote.
+    clearColor: vec4<f32>,
+    size: vec2<u32>,
+    antiAliasingWindowSize: f32,
+    enableSuperSamplingAntiAliasing: u32,
+    // 0 - start from clearColor, 1 - start from the base texture
+    useBase: u32,
+};
+
+// One glyph placed in the target.
+struct RasterJob {
+    // Column major 2x2 matrix from pixel offsets to em units.
+    toEm: vec4<f32>,
+    // Pen position in pixels.
+    pen: vec2<f32>,
+    pixelsPerEm: f32,
+    bufferIndex: u32,
+    // Straight alpha.
+    color: vec4<f32>,
+};
+
+struct RasterTile {
+    x: u32,
+    y: u32,
+    // range of tileJobs
+    start: u32,
+    count: u32,
+};
+
+@group(0) @binding(3) var<uniform> raster: RasterUniforms;
+@group(0) @binding(4) var<storage, read> jobs: array<RasterJob>;
+@group(0) @binding(5) var<storage, read> tiles: array<RasterTile>;
+@group(0) @binding(6) var<storage, read> tileJobs: array<u32>;
+// Copy of the target before this dispatch.
+@group(0) @binding(7) var baseColors: texture_2d<f32>;
+@group(0) @binding(8) var rasterTarget: texture_storage_2d<rgba8unorm, write>;
+
+fn rasterCoverage(job: RasterJob, pixel: vec2<f32>) -> f32 {
+    let uv = mat2x2<f32>(job.toEm.xy, job.toEm.zw)*(pixel - job.pen);
+    let inverseDiameter = job.pixelsPerEm/raster.antiAliasingWindowSize;
+
+    var alpha = 0.0;
+    let glyph = glyphs[job.bufferIndex];
+    for (var i = 0u; i < glyph.count; i++) {
+        let curve = loadCurve(glyph, i);
+
+        let p0 = curve.p0 - uv;
+        let p1 = curve.p1 - uv;
+        let p2 = curve.p2 - uv;
+
+        alpha += computeCoverage(inverseDiameter, p0, p1, p2);
+        if (raster.enableSuperSamplingAntiAliasing != 0u) {
+            alpha += computeCoverage(inverseDiameter, rotate(p0), rotate(p1), rotate(p2));
+        }
+    }
+
+    if (raster.enableSuperSamplingAntiAliasing != 0u) {
+        alpha *= 0.5;
+    }
+    return clamp(alpha, 0.0, 1.0);
+}
+
+@compute @workgroup_size(16, 16, 1)
+fn cs_raster(@builtin(workgroup_id) group: vec3<u32>, @builtin(local_invocation_id) local: vec3<u32>) {
+    let tile = tiles[group.x];
+    let texel = vec2<u32>(tile.x, tile.y)*16u + local.xy;
+    if (texel.x >= raster.size.x || texel.y >= raster.size.y) {
+        return;
+    }
+    var color = raster.clearColor;
+    if (raster.useBase != 0u) {
+        color = textureLoad(baseColors, vec2<i32>(texel), 0);
+    }
+    let pixel = vec2<f32>(texel) + 0.5;
+    for (var k = 0u; k < tile.count; k++) {
+        let job = jobs[tileJobs[tile.start + k]];
+        let alpha = rasterCoverage(job, pixel)*job.color.a;
+        color = vec4<f32>(job.color.rgb*alpha, alpha) + color*(1.0 - alpha);
+    }
+    textureStore(rasterTarget, vec2<i32>(texel), color);
+}
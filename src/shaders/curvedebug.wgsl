@@ -0,0 +1,47 @@
+// Debug overlay of glyph curves, control points and boxes, see curvedebug.jl.
+// Lines and points arrive as flat colored triangles in layout pixels.
+
+struct DebugUniforms {
+    // Maps pixel space into clip space.
+    projection: mat4x4<f32>,
+    // Model transform of the text the overlay belongs to.
+    transform: mat4x4<f32>,
+    // Vertex colors are sRGB encoded and the target expects linear values.
+    linearizeColors: u32,
+};
+
+@group(0) @binding(0) var<uniform> uniforms: DebugUniforms;
+
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+};
+
+fn srgbToLinear(c: vec3<f32>) -> vec3<f32> {
+    let low = c/12.92;
+    let high = pow((c + 0.055)/1.055, vec3<f32>(2.4));
+    return select(high, low, c <= vec3<f32>(0.04045));
+}
+
+@vertex
+fn vs_main(input: VertexInput) -> VertexOutput {
+    var output: VertexOutput;
+    output.position = uniforms.projection*uniforms.transform*vec4<f32>(input.position, 0.0, 1.0);
+    var color = input.color;
+    if (uniforms.linearizeColors != 0u) {
+        color = vec4<f32>(srgbToLinear(color.rgb), color.a);
+    }
+    // premultiplied like the text colors
+    output.color = vec4<f32>(color.rgb*color.a, color.a);
+    return output;
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    return input.color;
+}
@@ -0,0 +1,380 @@
+// WGSL port of the gpu-font-rendering shaders.
+// Based on: http://wdobbie.com/post/gpu-text-rendering-with-vector-textures/
+
+struct FontUniforms {
+    // Maps pixel space into clip space.
+    projection: mat4x4<f32>,
+    // Per draw model transform applied before the projection.
+    transform: mat4x4<f32>,
+    // Multiplies every vertex color.
+    tint: vec4<f32>,
+    // Size of the window (in pixels) used for 1-dimensional anti-aliasing along each ray.
+    //   0 - no anti-aliasing
+    //   1 - normal anti-aliasing
+    // >=2 - exaggerated effect
+    antiAliasingWindowSize: f32,
+    // Enable a second ray along the y-axis to achieve 2-dimensional anti-aliasing.
+    enableSuperSamplingAntiAliasing: u32,
+    // 0 - none, 1 - constant pixel size, 2 - distance scaled
+    billboardMode: u32,
+    // View units per layout pixel for distance scaled billboards.
+    billboardScale: f32,
+    billboardAnchor: vec4<f32>,
+    viewport: vec2<f32>,
+    // 0 - isotropic fwidth window, 1 - anisotropic window from the uv jacobian
+    antiAliasingMode: u32,
+    // Seconds driving the per glyph animation channels.
+    time: f32,
+    // Radians per second of the wave effect.
+    waveFrequency: f32,
+    // Jitter updates per second of the shake effect.
+    shakeRate: f32,
+    // Seconds the fade in effect takes per glyph.
+    fadeDuration: f32,
+    // Vertex colors are sRGB encoded and the target expects linear values.
+    linearizeColors: u32,
+    // Curves per fragment at the hot end of the heatmap ramp.
+    heatmapScale: f32,
+};
+
+// Version of the Glyph and Curve layouts, checked by gpulayout.jl.
+const layoutVersion = 1u;
+
+struct Glyph {
+    start: u32,
+    count: u32,
+};
+
+struct Curve {
+    p0: vec2<f32>,
+    p1: vec2<f32>,
+    p2: vec2<f32>,
+};
+
+@group(0) @binding(0) var<uniform> uniforms: FontUniforms;
+@group(0) @binding(1) var<storage, read> glyphs: array<Glyph>;
+@group(0) @binding(2) var<storage, read> curves: array<Curve>;
+
+// Half precision and quantized buffers replace the storage of this function,
+// see curveencoding.jl.
+fn loadCurve(glyph: Glyph, i: u32) -> Curve {
+    return curves[glyph.start + i];
+}
+
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) bufferIndex: i32,
+    // x - time offset, y - amplitude in pixels
+    @location(3) animation: vec2<f32>,
+    // 1 - wave, 2 - shake, 4 - fade in
+    @location(4) animationFlags: u32,
+    @location(5) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) @interpolate(flat) bufferIndex: i32,
+    @location(2) @interpolate(flat) opacity: f32,
+    @location(3) @interpolate(flat) color: vec4<f32>,
+    // 0 to 1 across the glyph quad, for the debug views
+    @location(4) quad: vec2<f32>,
+};
+
+fn srgbToLinear(c: vec3<f32>) -> vec3<f32> {
+    let low = c/12.92;
+    let high = pow((c + 0.055)/1.055, vec3<f32>(2.4));
+    return select(high, low, c <= vec3<f32>(0.04045));
+}
+
+fn hash(n: f32) -> f32 {
+    return fract(sin(n)*43758.5453);
+}
+
+// All vertices of a glyph share their animation channels, so glyphs move rigidly.
+fn animateOffset(animation: vec2<f32>, flags: u32) -> vec2<f32> {
+    let localTime = uniforms.time - animation.x;
+    var offset = vec2<f32>(0.0, 0.0);
+    if ((flags & 1u) != 0u) {
+        offset.y += animation.y*sin(localTime*uniforms.waveFrequency);
+    }
+    if ((flags & 2u) != 0u) {
+        let seed = floor(localTime*uniforms.shakeRate) + animation.x*997.0;
+        offset += animation.y*(2.0*vec2<f32>(hash(seed), hash(seed + 17.0)) - 1.0);
+    }
+    return offset;
+}
+
+fn animateOpacity(animation: vec2<f32>, flags: u32) -> f32 {
+    if ((flags & 4u) == 0u) {
+        return 1.0;
+    }
+    return clamp((uniforms.time - animation.x)/max(uniforms.fadeDuration, 1e-5), 0.0, 1.0);
+}
+
+fn projectVertex(position: vec2<f32>) -> vec4<f32> {
+    if (uniforms.billboardMode == 0u) {
+        return uniforms.projection*uniforms.transform*vec4<f32>(position, 0.0, 1.0);
+    }
+    // Layout pixels are offsets from the anchor, layout y grows downwards.
+    let offset = vec2<f32>(position.x, -position.y);
+    let viewAnchor = uniforms.transform*uniforms.billboardAnchor;
+    if (uniforms.billboardMode == 1u) {
+        let clip = uniforms.projection*viewAnchor;
+        return clip + vec4<f32>(2.0*offset/uniforms.viewport*clip.w, 0.0, 0.0);
+    }
+    return uniforms.projection*(viewAnchor + vec4<f32>(offset*uniforms.billboardScale, 0.0, 0.0));
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex: u32, input: VertexInput) -> VertexOutput {
+    var output: VertexOutput;
+    // glyph quads are four vertices each, clockwise from the top left
+    let k = vertex % 4u;
+    output.quad = vec2<f32>(f32(k == 1u || k == 2u), f32(k >= 2u));
+    let offset = animateOffset(input.animation, input.animationFlags);
+    output.position = projectVertex(input.position + offset);
+    output.opacity = animateOpacity(input.animation, input.animationFlags);
+    var color = input.color;
+    if (uniforms.linearizeColors != 0u) {
+        color = vec4<f32>(srgbToLinear(color.rgb), color.a);
+    }
+    output.color = color*uniforms.tint;
+    output.uv = input.uv;
+    output.bufferIndex = input.bufferIndex;
+    return output;
+}
+
+// Glyph quads of the label batch path, one instance per glyph, see labelbatch.jl.
+struct QuadInstance {
+    // x0, y0, x1, y1 in pixels
+    @location(0) rect: vec4<f32>,
+    // u0, v0, u1, v1 in em units
+    @location(1) uvRect: vec4<f32>,
+    @location(2) bufferIndex: i32,
+    @location(3) color: vec4<f32>,
+};
+
+@vertex
+fn vs_instanced(@builtin(vertex_index) vertex: u32, input: QuadInstance) -> VertexOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0), vec2<f32>(1.0, 0.0), vec2<f32>(1.0, 1.0),
+        vec2<f32>(1.0, 1.0), vec2<f32>(0.0, 1.0), vec2<f32>(0.0, 0.0),
+    );
+    let corner = corners[vertex];
+    var output: VertexOutput;
+    output.quad = corner;
+    output.position = projectVertex(mix(input.rect.xy, input.rect.zw, corner));
+    output.uv = mix(input.uvRect.xy, input.uvRect.zw, corner);
+    output.bufferIndex = input.bufferIndex;
+    output.opacity = 1.0;
+    var color = input.color;
+    if (uniforms.linearizeColors != 0u) {
+        color = vec4<f32>(srgbToLinear(color.rgb), color.a);
+    }
+    output.color = color*uniforms.tint;
+    return output;
+}
+
+// Glyphs of the transient text pool, animated from their spawn parameters
+// against `uniforms.time`, see textpool.jl.
+struct TransientInstance {
+    // x0, y0, x1, y1 in pixels around the origin at a scale of 1
+    @location(0) rect: vec4<f32>,
+    @location(1) uvRect: vec4<f32>,
+    @location(2) bufferIndex: i32,
+    @location(3) color: vec4<f32>,
+    // origin and velocity in pixels per second
+    @location(4) motion: vec4<f32>,
+    // spawn time, lifetime in seconds and gravity in pixels per second squared
+    @location(5) timing: vec3<f32>,
+    // scale at spawn and at the end of life, alpha likewise
+    @location(6) curves: vec4<f32>,
+    // 0 - linear, 1 - ease out, 2 - ease in
+    @location(7) easing: u32,
+};
+
+fn ease(t: f32, easing: u32) -> f32 {
+    if (easing == 1u) {
+        return 1.0 - (1.0 - t)*(1.0 - t);
+    }
+    if (easing == 2u) {
+        return t*t;
+    }
+    return t;
+}
+
+@vertex
+fn vs_transient(@builtin(vertex_index) vertex: u32, input: TransientInstance) -> VertexOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0), vec2<f32>(1.0, 0.0), vec2<f32>(1.0, 1.0),
+        vec2<f32>(1.0, 1.0), vec2<f32>(0.0, 1.0), vec2<f32>(0.0, 0.0),
+    );
+    let corner = corners[vertex];
+    var output: VertexOutput;
+    output.quad = corner;
+    let age = uniforms.time - input.timing.x;
+    if (age < 0.0 || age > input.timing.y) {
+        // degenerate quads of free and expired slots are not rasterized
+        output.position = vec4<f32>(0.0, 0.0, 0.0, 1.0);
+        output.opacity = 0.0;
+        return output;
+    }
+    let t = ease(age/max(input.timing.y, 1e-5), input.easing);
+    let scale = mix(input.curves.x, input.curves.y, t);
+    let origin = input.motion.xy + input.motion.zw*age + vec2<f32>(0.0, 0.5*input.timing.z*age*age);
+    output.position = projectVertex(origin + mix(input.rect.xy, input.rect.zw, corner)*scale);
+    output.uv = mix(input.uvRect.xy, input.uvRect.zw, corner);
+    output.bufferIndex = input.bufferIndex;
+    output.opacity = clamp(mix(input.curves.z, input.curves.w, t), 0.0, 1.0);
+    var color = input.color;
+    if (uniforms.linearizeColors != 0u) {
+        color = vec4<f32>(srgbToLinear(color.rgb), color.a);
+    }
+    output.color = color*uniforms.tint;
+    return output;
+}
+
+fn computeCoverage(inverseDiameter: f32, p0: vec2<f32>, p1: vec2<f32>, p2: vec2<f32>) -> f32 {
+    if (p0.y > 0.0 && p1.y > 0.0 && p2.y > 0.0) { return 0.0; }
+    if (p0.y < 0.0 && p1.y < 0.0 && p2.y < 0.0) { return 0.0; }
+
+    // Note: Simplified from abc formula by extracting a factor of (-2) from b.
+    let a = p0 - 2.0*p1 + p2;
+    let b = p0 - p1;
+    let c = p0;
+
+    var t0: f32;
+    var t1: f32;
+    if (abs(a.y) >= 1e-5) {
+        // Quadratic segment, solve abc formula to find roots.
+        let radicand = b.y*b.y - a.y*c.y;
+        if (radicand <= 0.0) { return 0.0; }
+        let s = sqrt(radicand);
+        t0 = (b.y - s)/a.y;
+        t1 = (b.y + s)/a.y;
+    } else {
+        // Linear segment, avoid division by a.y, which is near zero.
+        // The ray always has to exit the shape at t0 and enter at t1.
+        let t = p0.y/(p0.y - p2.y);
+        if (p0.y < p2.y) {
+            t0 = -1.0;
+            t1 = t;
+        } else {
+            t0 = t;
+            t1 = -1.0;
+        }
+    }
+
+    var alpha = 0.0;
+    if (t0 >= 0.0 && t0 < 1.0) {
+        let x = (a.x*t0 - 2.0*b.x)*t0 + c.x;
+        alpha += clamp(x*inverseDiameter + 0.5, 0.0, 1.0);
+    }
+    if (t1 >= 0.0 && t1 < 1.0) {
+        let x = (a.x*t1 - 2.0*b.x)*t1 + c.x;
+        alpha -= clamp(x*inverseDiameter + 0.5, 0.0, 1.0);
+    }
+    return alpha;
+}
+
+fn rotate(v: vec2<f32>) -> vec2<f32> {
+    return vec2<f32>(v.y, -v.x);
+}
+
+// Pixels covered by one uv unit along the u (horizontal ray) and v (vertical ray) axes.
+// fwidth sums the absolute derivatives, which blows up on oblique planes. Inverting the
+// jacobian J = [dpdx(uv) dpdy(uv)] instead measures the screen distance of a unit step
+// along each ray direction, so the window only widens along the foreshortened axis.
+fn pixelsPerUV(uv: vec2<f32>) -> vec2<f32> {
+    let dx = dpdx(uv);
+    let dy = dpdy(uv);
+    if (uniforms.antiAliasingMode == 0u) {
+        return 1.0/(abs(dx) + abs(dy));
+    }
+    let det = max(abs(dx.x*dy.y - dx.y*dy.x), 1e-12);
+    return vec2<f32>(length(vec2<f32>(dx.y, dy.y)), length(vec2<f32>(dx.x, dy.x)))/det;
+}
+
+// Coverage of the glyph at `uv`, between 0 and 1.
+fn glyphCoverage(uv: vec2<f32>, bufferIndex: i32) -> f32 {
+    var alpha = 0.0;
+
+    // Inverse of the diameter of a pixel in uv units for anti-aliasing.
+    let inverseDiameter = pixelsPerUV(uv)/uniforms.antiAliasingWindowSize;
+
+    let glyph = glyphs[bufferIndex];
+    for (var i = 0u; i < glyph.count; i++) {
+        let curve = loadCurve(glyph, i);
+
+        let p0 = curve.p0 - uv;
+        let p1 = curve.p1 - uv;
+        let p2 = curve.p2 - uv;
+
+        alpha += computeCoverage(inverseDiameter.x, p0, p1, p2);
+        if (uniforms.enableSuperSamplingAntiAliasing != 0u) {
+            alpha += computeCoverage(inverseDiameter.y, rotate(p0), rotate(p1), rotate(p2));
+        }
+    }
+
+    if (uniforms.enableSuperSamplingAntiAliasing != 0u) {
+        alpha *= 0.5;
+    }
+    return clamp(alpha, 0.0, 1.0);
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    let alpha = glyphCoverage(input.uv, input.bufferIndex)*input.opacity;
+    // Keep empty parts of the quad out of the depth buffer.
+    if (alpha <= 0.0) {
+        discard;
+    }
+    let color = input.color;
+    return vec4<f32>(color.rgb*color.a, color.a)*alpha;
+}
+
+fn heatmapColor(t: f32) -> vec3<f32> {
+    let stops = array<vec3<f32>, 4>(
+        vec3<f32>(0.1, 0.2, 1.0), vec3<f32>(0.1, 0.9, 0.3), vec3<f32>(1.0, 0.9, 0.1), vec3<f32>(1.0, 0.1, 0.1),
+    );
+    let x = clamp(t, 0.0, 1.0)*3.0;
+    let i = min(u32(x), 2u);
+    return mix(stops[i], stops[i + 1u], x - f32(i));
+}
+
+// Every fragment of a glyph quad pays for the curve loop of fs_main, so the
+// whole quad is filled, glyph coverage only darkens the empty parts a little.
+@fragment
+fn fs_heatmap(input: VertexOutput) -> @location(0) vec4<f32> {
+    var evaluated = f32(glyphs[input.bufferIndex].count);
+    if (uniforms.enableSuperSamplingAntiAliasing != 0u) {
+        evaluated *= 2.0;
+    }
+    let shade = mix(0.6, 1.0, glyphCoverage(input.uv, input.bufferIndex));
+    return vec4<f32>(heatmapColor(evaluated/uniforms.heatmapScale)*shade, 1.0);
+}
+
+// Glyph quads tinted per glyph with their edges outlined, loose ink bounds
+// show as wide margins and broken batching as missing or doubled quads.
+@fragment
+fn fs_quads(input: VertexOutput) -> @location(0) vec4<f32> {
+    let seed = f32(input.bufferIndex);
+    let tint = 0.3 + 0.7*vec3<f32>(hash(seed), hash(seed + 0.37), hash(seed + 0.71));
+    let toEdge = min(input.quad, 1.0 - input.quad)/max(fwidth(input.quad), vec2<f32>(1e-6));
+    let coverage = glyphCoverage(input.uv, input.bufferIndex);
+    if (min(toEdge.x, toEdge.y) < 1.0) {
+        return vec4<f32>(tint, 1.0);
+    }
+    let color = mix(tint*0.35, vec3<f32>(1.0), coverage);
+    let alpha = mix(0.35, 1.0, coverage);
+    return vec4<f32>(color*alpha, alpha);
+}
+
+// Every quad fragment adds the same amount with additive blending, so the
+// brightness counts the quads covering a pixel, about ten saturate it.
+@fragment
+fn fs_overdraw(input: VertexOutput) -> @location(0) vec4<f32> {
+    return vec4<f32>(0.1, 0.06, 0.02, 0.1);
+}
@@ -0,0 +1,68 @@
+// Appended to font.wgsl for its uniforms, buffers and fs_main. Heterogeneous
+// text in one draw: every instance reads its glyph from storage by instance
+// index, the glyphs and curves of all fonts are merged, see instancedtext.jl.
+
+struct GlyphInstance {
+    // pen position in pixels, y down
+    position: vec2<f32>,
+    // pixels per em
+    scale: f32,
+    // radians, clockwise on screen
+    rotation: f32,
+    // rgba8, red in the lowest byte
+    color: u32,
+    // font of the batch and glyph buffer index within it
+    font: u32,
+    glyph: u32,
+    // 1 - hidden, 2 - rotate about the glyph box center instead of the pen
+    flags: u32,
+};
+
+// first merged glyph of every font
+@group(0) @binding(3) var<storage, read> fontGlyphs: array<u32>;
+// x0, y0, x1, y1 in em units per merged glyph
+@group(0) @binding(4) var<storage, read> glyphBoxes: array<vec4<f32>>;
+@group(0) @binding(5) var<storage, read> instances: array<GlyphInstance>;
+
+@vertex
+fn vs_glyphInstance(@builtin(vertex_index) vertex: u32, @builtin(instance_index) index: u32) -> VertexOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0), vec2<f32>(1.0, 0.0), vec2<f32>(1.0, 1.0),
+        vec2<f32>(1.0, 1.0), vec2<f32>(0.0, 1.0), vec2<f32>(0.0, 0.0),
+    );
+    let corner = corners[vertex];
+    let instance = instances[index];
+    var output: VertexOutput;
+    output.quad = corner;
+    if ((instance.flags & 1u) != 0u) {
+        // degenerate quads are not rasterized
+        output.position = vec4<f32>(0.0, 0.0, 0.0, 1.0);
+        output.opacity = 0.0;
+        return output;
+    }
+    let glyph = fontGlyphs[instance.font] + instance.glyph;
+    // a pixel wider on every side for anti-aliasing
+    let pad = 1.0/max(instance.scale, 1e-5);
+    let box = glyphBoxes[glyph] + vec4<f32>(-pad, -pad, pad, pad);
+    let uv = mix(box.xy, box.zw, corner);
+    var pivot = vec2<f32>(0.0, 0.0);
+    if ((instance.flags & 2u) != 0u) {
+        pivot = 0.5*(box.xy + box.zw);
+    }
+    // em units are y up, pixels y down
+    let toPixels = vec2<f32>(instance.scale, -instance.scale);
+    let local = (uv - pivot)*toPixels;
+    let c = cos(instance.rotation);
+    let s = sin(instance.rotation);
+    let rotated = vec2<f32>(c*local.x - s*local.y, s*local.x + c*local.y);
+    output.position = projectVertex(instance.position + pivot*toPixels + rotated);
+    output.uv = uv;
+    output.bufferIndex = i32(glyph);
+    output.opacity = 1.0;
+    var color = unpack4x8unorm(instance.color);
+    if (uniforms.linearizeColors != 0u) {
+        color = vec4<f32>(srgbToLinear(color.rgb), color.a);
+    }
+    output.color = color*uniforms.tint;
+    return output;
+}
@@ -0,0 +1,69 @@
+// Loop-Blinn backend, see loopblinn.jl.
+// Glyph interiors arrive as plain triangles, curved edges as triangles over
+// the control points of a quadratic with the canonical coordinates (0, 0),
+// (0.5, 0) and (1, 1), where the curve is u^2 - v = 0. No fragment loops
+// over curves, an edge costs one implicit test.
+
+struct LoopBlinnUniforms {
+    // Maps pixel space into clip space.
+    projection: mat4x4<f32>,
+    // Per draw model transform applied before the projection.
+    transform: mat4x4<f32>,
+    // Vertex colors are sRGB encoded and the target expects linear values.
+    linearizeColors: u32,
+};
+
+@group(0) @binding(0) var<uniform> uniforms: LoopBlinnUniforms;
+
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) uv: vec2<f32>,
+    // 0 - interior, 1 - fills u^2 < v, -1 - fills u^2 > v
+    @location(2) side: f32,
+    @location(3) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) @interpolate(flat) side: f32,
+    @location(2) @interpolate(flat) color: vec4<f32>,
+};
+
+fn srgbToLinear(c: vec3<f32>) -> vec3<f32> {
+    let low = c/12.92;
+    let high = pow((c + 0.055)/1.055, vec3<f32>(2.4));
+    return select(high, low, c <= vec3<f32>(0.04045));
+}
+
+@vertex
+fn vs_main(input: VertexInput) -> VertexOutput {
+    var output: VertexOutput;
+    output.position = uniforms.projection*uniforms.transform*vec4<f32>(input.position, 0.0, 1.0);
+    output.uv = input.uv;
+    output.side = input.side;
+    var color = input.color;
+    if (uniforms.linearizeColors != 0u) {
+        color = vec4<f32>(srgbToLinear(color.rgb), color.a);
+    }
+    output.color = color;
+    return output;
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    // The implicit function and its screen space gradient give the signed
+    // distance to the curve in pixels for anti-aliasing.
+    let f = input.uv.x*input.uv.x - input.uv.y;
+    let gradient = vec2<f32>(dpdx(f), dpdy(f));
+    var alpha = 1.0;
+    if (input.side != 0.0) {
+        let distance = input.side*f/max(length(gradient), 1e-6);
+        alpha = clamp(0.5 - distance, 0.0, 1.0);
+    }
+    if (alpha <= 0.0) {
+        discard;
+    }
+    let color = input.color;
+    return vec4<f32>(color.rgb*color.a, color.a)*alpha;
+}
@@ -0,0 +1,94 @@
+// Multi channel distance fields generated from the curve buffers of font.wgsl.
+// Every channel holds the signed distance to the nearest edge of its color,
+// the median of the three channels keeps corners sharp when magnified.
+
+// Version of the Glyph and Curve layouts, checked by gpulayout.jl.
+const layoutVersion = 1u;
+
+struct Glyph {
+    start: u32,
+    count: u32,
+};
+
+struct Curve {
+    p0: vec2<f32>,
+    p1: vec2<f32>,
+    p2: vec2<f32>,
+};
+
+// One glyph cell of the atlas, dispatched as one z slice.
+struct Job {
+    bufferIndex: u32,
+    colorStart: u32,
+    originX: u32,
+    originY: u32,
+    layer: u32,
+    width: u32,
+    height: u32,
+    // em space position of the top left texel corner
+    left: f32,
+    top: f32,
+    // em units per texel
+    texel: f32,
+    // texels of distance encoded on each side of the outline
+    spread: f32,
+};
+
+@group(0) @binding(0) var<storage, read> glyphs: array<Glyph>;
+@group(0) @binding(1) var<storage, read> curves: array<Curve>;
+
+// Half precision and quantized buffers replace the storage of this function,
+// see curveencoding.jl.
+fn loadCurve(glyph: Glyph, i: u32) -> Curve {
+    return curves[glyph.start + i];
+}
+// channel mask per curve, 1 - red, 2 - green, 4 - blue
+@group(0) @binding(2) var<storage, read> colors: array<u32>;
+@group(0) @binding(3) var<storage, read> jobs: array<Job>;
+@group(0) @binding(4) var field: texture_storage_2d_array<rgba8unorm, write>;
+
+fn bezier(curve: Curve, t: f32) -> vec2<f32> {
+    let s = 1.0 - t;
+    return s*s*curve.p0 + 2.0*s*t*curve.p1 + t*t*curve.p2;
+}
+
+// Signed distance to the chord polyline of a curve. Filled regions lie on the
+// right of the curve direction, inside is positive.
+fn signedDistance(p: vec2<f32>, curve: Curve) -> f32 {
+    var best = 1e9;
+    var signedBest = 1e9;
+    var a = curve.p0;
+    for (var i = 1u; i <= 8u; i++) {
+        let b = bezier(curve, f32(i)/8.0);
+        let d = b - a;
+        let h = clamp(dot(p - a, d)/max(dot(d, d), 1e-12), 0.0, 1.0);
+        let distance = length(p - a - h*d);
+        if (distance < best) {
+            best = distance;
+            let side = d.x*(p.y - a.y) - d.y*(p.x - a.x);
+            signedBest = select(distance, -distance, side > 0.0);
+        }
+        a = b;
+    }
+    return signedBest;
+}
+
+@compute @workgroup_size(8, 8, 1)
+fn cs_main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let job = jobs[id.z];
+    if (id.x >= job.width || id.y >= job.height) {
+        return;
+    }
+    let p = vec2<f32>(job.left + (f32(id.x) + 0.5)*job.texel, job.top - (f32(id.y) + 0.5)*job.texel);
+    let glyph = glyphs[job.bufferIndex];
+    var distances = vec3<f32>(1e9, 1e9, 1e9);
+    for (var i = 0u; i < glyph.count; i++) {
+        let d = signedDistance(p, loadCurve(glyph, i));
+        let mask = colors[job.colorStart + i];
+        if ((mask & 1u) != 0u && abs(d) < abs(distances.r)) { distances.r = d; }
+        if ((mask & 2u) != 0u && abs(d) < abs(distances.g)) { distances.g = d; }
+        if ((mask & 4u) != 0u && abs(d) < abs(distances.b)) { distances.b = d; }
+    }
+    let value = clamp(0.5 + distances/(2.0*job.spread*job.texel), vec3<f32>(0.0), vec3<f32>(1.0));
+    textureStore(field, vec2<i32>(i32(job.originX + id.x), i32(job.originY + id.y)), i32(job.layer), vec4<f32>(value, 1.0));
+}
@@ -0,0 +1,125 @@
+// Distance field atlas path for small text, shares the uniforms of font.wgsl.
+
+struct FontUniforms {
+    projection: mat4x4<f32>,
+    transform: mat4x4<f32>,
+    tint: vec4<f32>,
+    antiAliasingWindowSize: f32,
+    enableSuperSamplingAntiAliasing: u32,
+    billboardMode: u32,
+    billboardScale: f32,
+    billboardAnchor: vec4<f32>,
+    viewport: vec2<f32>,
+    antiAliasingMode: u32,
+    time: f32,
+    waveFrequency: f32,
+    shakeRate: f32,
+    fadeDuration: f32,
+    linearizeColors: u32,
+};
+
+@group(0) @binding(0) var<uniform> uniforms: FontUniforms;
+@group(0) @binding(1) var atlas: texture_2d_array<f32>;
+@group(0) @binding(2) var atlasSampler: sampler;
+
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) bufferIndex: i32,
+    @location(3) animation: vec2<f32>,
+    @location(4) animationFlags: u32,
+    @location(5) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) @interpolate(flat) color: vec4<f32>,
+    // atlas page, passed in the bufferIndex attribute
+    @location(2) @interpolate(flat) layer: i32,
+    @location(3) @interpolate(flat) flags: u32,
+};
+
+fn srgbToLinear(c: vec3<f32>) -> vec3<f32> {
+    let low = c/12.92;
+    let high = pow((c + 0.055)/1.055, vec3<f32>(2.4));
+    return select(high, low, c <= vec3<f32>(0.04045));
+}
+
+@vertex
+fn vs_main(input: VertexInput) -> VertexOutput {
+    var output: VertexOutput;
+    output.position = uniforms.projection*uniforms.transform*vec4<f32>(input.position, 0.0, 1.0);
+    var color = input.color;
+    if (uniforms.linearizeColors != 0u) {
+        color = vec4<f32>(srgbToLinear(color.rgb), color.a);
+    }
+    output.color = color*uniforms.tint;
+    output.uv = input.uv;
+    output.layer = input.bufferIndex;
+    output.flags = input.animationFlags;
+    return output;
+}
+
+// 0.5 is the outline, the distance gradient sets the edge width in pixels.
+fn shade(distance: f32, color: vec4<f32>) -> vec4<f32> {
+    let width = max(fwidth(distance)*0.5*uniforms.antiAliasingWindowSize, 1e-4);
+    let alpha = smoothstep(0.5 - width, 0.5 + width, distance);
+    if (alpha <= 0.0) {
+        discard;
+    }
+    return vec4<f32>(color.rgb*color.a, color.a)*alpha;
+}
+
+fn median(a: f32, b: f32, c: f32) -> f32 {
+    return max(min(a, b), min(max(a, b), c));
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    return shade(textureSample(atlas, atlasSampler, input.uv, input.layer).r, input.color);
+}
+
+// Coverage rasterized at the drawn size, see glyphatlas.jl.
+@fragment
+fn fs_alpha(input: VertexOutput) -> @location(0) vec4<f32> {
+    let alpha = textureSample(atlas, atlasSampler, input.uv, input.layer).r;
+    if (alpha <= 0.0) {
+        discard;
+    }
+    let color = input.color;
+    return vec4<f32>(color.rgb*color.a, color.a)*alpha;
+}
+
+// Rgba glyph atlas, coverage is premultiplied white and color bitmaps are
+// flagged with 0x100 to keep their own colors, only faded by the alpha.
+@fragment
+fn fs_color(input: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = textureSample(atlas, atlasSampler, input.uv, input.layer);
+    if (texel.a <= 0.0) {
+        discard;
+    }
+    let color = input.color;
+    if ((input.flags & 0x100u) != 0u) {
+        return texel*color.a;
+    }
+    return vec4<f32>(color.rgb*color.a, color.a)*texel.a;
+}
+
+// Raw texels of an atlas page for `queueAtlasDebug!`, single channel pages
+// are shown in gray and rgba pages flagged with 0x100 as they are.
+@fragment
+fn fs_debug(input: VertexOutput) -> @location(0) vec4<f32> {
+    let texel = textureSample(atlas, atlasSampler, input.uv, input.layer);
+    if ((input.flags & 0x100u) != 0u) {
+        return vec4<f32>(texel.rgb, 1.0);
+    }
+    return vec4<f32>(texel.rrr, 1.0);
+}
+
+// Multi channel fields from msdf.wgsl.
+@fragment
+fn fs_msdf(input: VertexOutput) -> @location(0) vec4<f32> {
+    let s = textureSample(atlas, atlasSampler, input.uv, input.layer).rgb;
+    return shade(median(s.r, s.g, s.b), input.color);
+}
@@ -0,0 +1,64 @@
+// Stencil-then-cover backend, see stenciltext.jl.
+// The stencil pass draws a fan of triangles from one anchor over every
+// curve chord, and curve triangles over the control points with the
+// canonical coordinates (0, 0), (0.5, 0) and (1, 1) of Loop and Blinn. Fan
+// corners use (0, 1), which always passes the implicit test. The cover
+// pass fills the glyph box wherever the stencil is set.
+
+struct StencilUniforms {
+    // Maps pixel space into clip space.
+    projection: mat4x4<f32>,
+    // Per draw model transform applied before the projection.
+    transform: mat4x4<f32>,
+    // Vertex colors are sRGB encoded and the target expects linear values.
+    linearizeColors: u32,
+};
+
+@group(0) @binding(0) var<uniform> uniforms: StencilUniforms;
+
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) @interpolate(flat) color: vec4<f32>,
+};
+
+fn srgbToLinear(c: vec3<f32>) -> vec3<f32> {
+    let low = c/12.92;
+    let high = pow((c + 0.055)/1.055, vec3<f32>(2.4));
+    return select(high, low, c <= vec3<f32>(0.04045));
+}
+
+@vertex
+fn vs_main(input: VertexInput) -> VertexOutput {
+    var output: VertexOutput;
+    output.position = uniforms.projection*uniforms.transform*vec4<f32>(input.position, 0.0, 1.0);
+    output.uv = input.uv;
+    var color = input.color;
+    if (uniforms.linearizeColors != 0u) {
+        color = vec4<f32>(srgbToLinear(color.rgb), color.a);
+    }
+    output.color = color;
+    return output;
+}
+
+// Only the stencil is written, a zero premultiplied color leaves the target
+// as it is under every blend mode.
+@fragment
+fn fs_stencil(input: VertexOutput) -> @location(0) vec4<f32> {
+    if (input.uv.x*input.uv.x - input.uv.y > 0.0) {
+        discard;
+    }
+    return vec4<f32>(0.0);
+}
+
+@fragment
+fn fs_cover(input: VertexOutput) -> @location(0) vec4<f32> {
+    let color = input.color;
+    return vec4<f32>(color.rgb*color.a, color.a);
+}
@@ -0,0 +1,266 @@
+// Terminal grids, one instanced quad per cell, see terminal.jl.
+// The coverage of a glyph is computed like in font.wgsl, against the curves
+// of the cell's glyph in the pixel grid of its cell.
+
+struct GridUniforms {
+    // Maps pixel space into clip space.
+    projection: mat4x4<f32>,
+    // Top left corner of the grid in pixels.
+    origin: vec2<f32>,
+    cellSize: vec2<f32>,
+    // Pixels from the top of a cell down to the baseline.
+    baseline: f32,
+    // Em units per pixel, glyph curves are stored in em units.
+    emPerPixel: f32,
+    columns: u32,
+    antiAliasingWindowSize: f32,
+    enableSuperSamplingAntiAliasing: u32,
+    // Cell colors are sRGB encoded and the target expects linear values.
+    linearizeColors: u32,
+    // Pixels below the baseline and thickness of underlines.
+    underlinePosition: f32,
+    underlineThickness: f32,
+    // Pixels above the baseline of strikethroughs.
+    strikethroughPosition: f32,
+    // Seconds driving blinking cells and cursors.
+    time: f32,
+    // Seconds blinking cells stay visible, and then hidden.
+    blinkInterval: f32,
+    // Index of the cursor cell, 0xffffffff without a cursor.
+    cursorCell: u32,
+    // 0 - block, 1 - underline, 2 - bar, 3 - hollow block
+    cursorShape: u32,
+    cursorColor: u32,
+    // Pixels of underline, bar and hollow block cursors.
+    cursorThickness: f32,
+    cursorBlinks: u32,
+};
+
+// Version of the Glyph and Curve layouts, checked by gpulayout.jl.
+const layoutVersion = 1u;
+
+struct Glyph {
+    start: u32,
+    count: u32,
+};
+
+struct Curve {
+    p0: vec2<f32>,
+    p1: vec2<f32>,
+    p2: vec2<f32>,
+};
+
+struct Cell {
+    // -1 for cells without a glyph
+    bufferIndex: i32,
+    // rgba8, red in the lowest byte
+    foreground: u32,
+    background: u32,
+    // 1 - underline, 2 - strikethrough, 4 - bold, 8 - dim, 16 - reverse, 32 - blink
+    flags: u32,
+    // Underlines take the foreground color when this is transparent.
+    underlineColor: u32,
+};
+
+@group(0) @binding(0) var<uniform> uniforms: GridUniforms;
+@group(0) @binding(1) var<storage, read> glyphs: array<Glyph>;
+@group(0) @binding(2) var<storage, read> curves: array<Curve>;
+@group(0) @binding(3) var<storage, read> cells: array<Cell>;
+
+// Half precision and quantized buffers replace the storage of this function,
+// see curveencoding.jl.
+fn loadCurve(glyph: Glyph, i: u32) -> Curve {
+    return curves[glyph.start + i];
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    // Pixels from the top left corner of the cell.
+    @location(0) local: vec2<f32>,
+    @location(1) @interpolate(flat) cell: u32,
+};
+
+fn srgbToLinear(c: vec3<f32>) -> vec3<f32> {
+    let low = c/12.92;
+    let high = pow((c + 0.055)/1.055, vec3<f32>(2.4));
+    return select(high, low, c <= vec3<f32>(0.04045));
+}
+
+fn cellColor(packed: u32) -> vec4<f32> {
+    let color = unpack4x8unorm(packed);
+    if (uniforms.linearizeColors != 0u) {
+        return vec4<f32>(srgbToLinear(color.rgb), color.a);
+    }
+    return color;
+}
+
+@vertex
+fn vs_main(@builtin(vertex_index) vertex: u32, @builtin(instance_index) cell: u32) -> VertexOutput {
+    var corners = array<vec2<f32>, 6>(
+        vec2<f32>(0.0, 0.0), vec2<f32>(1.0, 0.0), vec2<f32>(1.0, 1.0),
+        vec2<f32>(1.0, 1.0), vec2<f32>(0.0, 1.0), vec2<f32>(0.0, 0.0),
+    );
+    let corner = corners[vertex];
+    let column = f32(cell % uniforms.columns);
+    let row = f32(cell / uniforms.columns);
+    let local = corner*uniforms.cellSize;
+    var output: VertexOutput;
+    output.position = uniforms.projection*vec4<f32>(uniforms.origin + vec2<f32>(column, row)*uniforms.cellSize + local, 0.0, 1.0);
+    output.local = local;
+    output.cell = cell;
+    return output;
+}
+
+fn computeCoverage(inverseDiameter: f32, p0: vec2<f32>, p1: vec2<f32>, p2: vec2<f32>) -> f32 {
+    if (p0.y > 0.0 && p1.y > 0.0 && p2.y > 0.0) { return 0.0; }
+    if (p0.y < 0.0 && p1.y < 0.0 && p2.y < 0.0) { return 0.0; }
+
+    let a = p0 - 2.0*p1 + p2;
+    let b = p0 - p1;
+    let c = p0;
+
+    var t0: f32;
+    var t1: f32;
+    if (abs(a.y) >= 1e-5) {
+        let radicand = b.y*b.y - a.y*c.y;
+        if (radicand <= 0.0) { return 0.0; }
+        let s = sqrt(radicand);
+        t0 = (b.y - s)/a.y;
+        t1 = (b.y + s)/a.y;
+    } else {
+        let t = p0.y/(p0.y - p2.y);
+        if (p0.y < p2.y) {
+            t0 = -1.0;
+            t1 = t;
+        } else {
+            t0 = t;
+            t1 = -1.0;
+        }
+    }
+
+    var alpha = 0.0;
+    if (t0 >= 0.0 && t0 < 1.0) {
+        let x = (a.x*t0 - 2.0*b.x)*t0 + c.x;
+        alpha += clamp(x*inverseDiameter + 0.5, 0.0, 1.0);
+    }
+    if (t1 >= 0.0 && t1 < 1.0) {
+        let x = (a.x*t1 - 2.0*b.x)*t1 + c.x;
+        alpha -= clamp(x*inverseDiameter + 0.5, 0.0, 1.0);
+    }
+    return alpha;
+}
+
+fn rotate(v: vec2<f32>) -> vec2<f32> {
+    return vec2<f32>(v.y, -v.x);
+}
+
+// Cells are axis aligned and unscaled, so a pixel spans the same uv distance everywhere.
+fn glyphCoverage(bufferIndex: i32, local: vec2<f32>) -> f32 {
+    if (bufferIndex < 0) {
+        return 0.0;
+    }
+    let uv = vec2<f32>(local.x, uniforms.baseline - local.y)*uniforms.emPerPixel;
+    let inverseDiameter = 1.0/(uniforms.emPerPixel*uniforms.antiAliasingWindowSize);
+    let glyph = glyphs[bufferIndex];
+    var alpha = 0.0;
+    for (var i = 0u; i < glyph.count; i++) {
+        let curve = loadCurve(glyph, i);
+        let p0 = curve.p0 - uv;
+        let p1 = curve.p1 - uv;
+        let p2 = curve.p2 - uv;
+        alpha += computeCoverage(inverseDiameter, p0, p1, p2);
+        if (uniforms.enableSuperSamplingAntiAliasing != 0u) {
+            alpha += computeCoverage(inverseDiameter, rotate(p0), rotate(p1), rotate(p2));
+        }
+    }
+    if (uniforms.enableSuperSamplingAntiAliasing != 0u) {
+        alpha *= 0.5;
+    }
+    return clamp(alpha, 0.0, 1.0);
+}
+
+// Bold cells are overstruck, the glyph is covered a second time shifted right.
+fn cellCoverage(cell: Cell, local: vec2<f32>) -> f32 {
+    var coverage = glyphCoverage(cell.bufferIndex, local);
+    if ((cell.flags & 4u) != 0u) {
+        let offset = max(1.0, round(0.04/uniforms.emPerPixel));
+        coverage = max(coverage, glyphCoverage(cell.bufferIndex, local - vec2<f32>(offset, 0.0)));
+    }
+    return coverage;
+}
+
+// Coverage of a horizontal bar of `thickness` pixels whose top is at `top`.
+fn barCoverage(y: f32, top: f32, thickness: f32) -> f32 {
+    return clamp(min(y - top + 0.5, top + thickness - y + 0.5), 0.0, 1.0);
+}
+
+fn blinkVisible() -> bool {
+    return u32(floor(uniforms.time/max(uniforms.blinkInterval, 1e-5))) % 2u == 0u;
+}
+
+// Coverage of the underline, bar and hollow block cursor shapes.
+fn cursorCoverage(local: vec2<f32>) -> f32 {
+    let thickness = uniforms.cursorThickness;
+    let size = uniforms.cellSize;
+    if (uniforms.cursorShape == 1u) {
+        return barCoverage(local.y, size.y - thickness, thickness);
+    }
+    if (uniforms.cursorShape == 2u) {
+        return barCoverage(local.x, 0.0, thickness);
+    }
+    let edge = min(min(local.x, size.x - local.x), min(local.y, size.y - local.y));
+    return clamp(thickness - edge + 0.5, 0.0, 1.0);
+}
+
+fn over(top: vec4<f32>, alpha: f32, bottom: vec4<f32>) -> vec4<f32> {
+    let a = top.a*alpha;
+    return vec4<f32>(top.rgb*a, a) + bottom*(1.0 - a);
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    let cell = cells[input.cell];
+    var foreground = cellColor(cell.foreground);
+    var background = cellColor(cell.background);
+    if ((cell.flags & 8u) != 0u) {
+        foreground = vec4<f32>(foreground.rgb*(2.0/3.0), foreground.a);
+    }
+    if ((cell.flags & 16u) != 0u) {
+        let swapped = foreground;
+        foreground = background;
+        background = swapped;
+    }
+    var underlineColor = foreground;
+    if (unpack4x8unorm(cell.underlineColor).a > 0.0) {
+        underlineColor = cellColor(cell.underlineColor);
+    }
+
+    let cursor = input.cell == uniforms.cursorCell && (uniforms.cursorBlinks == 0u || blinkVisible());
+    let cursorColor = cellColor(uniforms.cursorColor);
+    if (cursor && uniforms.cursorShape == 0u) {
+        // the glyph under a block cursor takes the cell background
+        foreground = vec4<f32>(background.rgb, 1.0);
+        background = cursorColor;
+    }
+
+    var glyph = cellCoverage(cell, input.local);
+    if ((cell.flags & 32u) != 0u && !blinkVisible()) {
+        glyph = 0.0;
+    }
+    var lines = 0.0;
+    if ((cell.flags & 1u) != 0u) {
+        lines = barCoverage(input.local.y, uniforms.baseline + uniforms.underlinePosition, uniforms.underlineThickness);
+    }
+    var strike = 0.0;
+    if ((cell.flags & 2u) != 0u) {
+        strike = barCoverage(input.local.y, uniforms.baseline - uniforms.strikethroughPosition, uniforms.underlineThickness);
+    }
+
+    var color = vec4<f32>(background.rgb*background.a, background.a);
+    color = over(foreground, max(glyph, strike), color);
+    color = over(underlineColor, lines, color);
+    if (cursor && uniforms.cursorShape != 0u) {
+        color = over(cursorColor, cursorCoverage(input.local), color);
+    }
+    return color;
+}
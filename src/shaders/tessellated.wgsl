@@ -0,0 +1,61 @@
+// Tessellated fallback for adapters without storage buffers in fragment
+// shaders, shares the uniforms of font.wgsl. Glyphs arrive as plain
+// triangles of their flattened outlines, edges antialias by multisampling.
+
+struct FontUniforms {
+    projection: mat4x4<f32>,
+    transform: mat4x4<f32>,
+    tint: vec4<f32>,
+    antiAliasingWindowSize: f32,
+    enableSuperSamplingAntiAliasing: u32,
+    billboardMode: u32,
+    billboardScale: f32,
+    billboardAnchor: vec4<f32>,
+    viewport: vec2<f32>,
+    antiAliasingMode: u32,
+    time: f32,
+    waveFrequency: f32,
+    shakeRate: f32,
+    fadeDuration: f32,
+    linearizeColors: u32,
+};
+
+@group(0) @binding(0) var<uniform> uniforms: FontUniforms;
+
+struct VertexInput {
+    @location(0) position: vec2<f32>,
+    @location(1) uv: vec2<f32>,
+    @location(2) bufferIndex: i32,
+    @location(3) animation: vec2<f32>,
+    @location(4) animationFlags: u32,
+    @location(5) color: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) @interpolate(flat) color: vec4<f32>,
+};
+
+fn srgbToLinear(c: vec3<f32>) -> vec3<f32> {
+    let low = c/12.92;
+    let high = pow((c + 0.055)/1.055, vec3<f32>(2.4));
+    return select(high, low, c <= vec3<f32>(0.04045));
+}
+
+@vertex
+fn vs_main(input: VertexInput) -> VertexOutput {
+    var output: VertexOutput;
+    output.position = uniforms.projection*uniforms.transform*vec4<f32>(input.position, 0.0, 1.0);
+    var color = input.color;
+    if (uniforms.linearizeColors != 0u) {
+        color = vec4<f32>(srgbToLinear(color.rgb), color.a);
+    }
+    output.color = color*uniforms.tint;
+    return output;
+}
+
+@fragment
+fn fs_main(input: VertexOutput) -> @location(0) vec4<f32> {
+    let color = input.color;
+    return vec4<f32>(color.rgb*color.a, color.a);
+}
@@ -0,0 +1,171 @@
+# Shaping maps characters to glyph indices and horizontal advances.
+# Cmap lookup, then the GSUB lookups of the requested OpenType features (see
+# opentype.jl), then pair kerning; all values are in font units.
+
+struct ShapedGlyph
+    index::FT_UInt
+    cluster::Int        # string index of the source character
+    xAdvance::FT_Pos
+    xOffset::FT_Pos
+    yOffset::FT_Pos
+end
+
+# Text as the layouts accept it, bytes are decoded as UTF-8.
+const TextInput = Union{AbstractString, AbstractVector{UInt8}}
+
+"""
+    validText(text) -> String
+
+`text` with every malformed UTF-8 sequence and every surrogate code point,
+as left behind by UTF-16 conversions of clipboards or input methods,
+replaced by U+FFFD. Layouts of invalid text have clusters indexing this
+string; valid text is returned unchanged.
+"""
+validText(text::AbstractString) = isvalid(text) ? String(text) : map(chr -> isvalid(chr) ? chr : '\ufffd', String(text))
+validText(bytes::AbstractVector{UInt8}) = validText(String(copy(bytes)))
+
+# Digit runs around a slash as (numerator range, slash index, denominator range).
+function fractionRanges(run, text)
+    ranges = Tuple{UnitRange{Int}, Int, UnitRange{Int}}[]
+    isDigitAt(i) = i <= length(run) && isdigit(text[run[i][2]])
+    i = 1
+    while i <= length(run)
+        start = i
+        while isDigitAt(i)
+            i += 1
+        end
+        if i > start && i < length(run) && text[run[i][2]] in ('/', '⁄') && isDigitAt(i + 1)
+            slash = i
+            i += 1
+            while isDigitAt(i)
+                i += 1
+            end
+            push!(ranges, (start:(slash - 1), slash, (slash + 1):(i - 1)))
+        else
+            i = max(i, start + 1)
+        end
+    end
+    return ranges
+end
+
+# Numerator and denominator forms around a fraction slash. Fonts without
+# numr and dnom get their frac lookups over the whole fraction instead.
+# `system` holds the script and language keywords of `featureLookups`.
+function applyFractions!(run, font::FontFace, gsub::GSUBTable, text; system...)
+    fractionSlash = glyphIndex(font, '⁄')
+    split = hasFeature(gsub, "numr"; system...) && hasFeature(gsub, "dnom"; system...)
+    for (numerator, slash, denominator) in reverse(fractionRanges(run, text))
+        if split
+            applyFeatures!(run, gsub, ("dnom",); range=denominator, system...)
+            fractionSlash == 0 || (run[slash] = (fractionSlash, run[slash][2]))
+            applyFeatures!(run, gsub, ("numr",); range=numerator, system...)
+        else
+            applyFeatures!(run, gsub, ("frac",); range=first(numerator):last(denominator), system...)
+        end
+    end
+    return run
+end
+
+# x-height over cap height, the size of synthesized small caps.
+function smallCapsScale(font::FontFace)
+    x = prepareGlyph(font, glyphIndex(font, 'x'))
+    h = prepareGlyph(font, glyphIndex(font, 'H'))
+    return x.height > 0 && h.height > 0 ? Float32(x.height/h.height) : 0.7f0
+end
+
+# Format characters that never show ink. Joiners keep the glyph of faces that
+# map them, emoji sequences ligate over it; everything else missing from the
+# face, and the soft hyphen always, becomes the invisible glyph.
+isDefaultIgnorable(chr::Char) =
+    chr in ('\uad', '\u34f', '\u200b', '\u200c', '\u200d', '\u200e', '\u200f', '\ufeff') ||
+    '\u202a' <= chr <= '\u202e' || '\u2060' <= chr <= '\u2069' || '\ufe00' <= chr <= '\ufe0f' ||
+    '\u180b' <= chr <= '\u180d' || '\U0e0100' <= chr <= '\U0e01ef'
+
+function cmapGlyph(font::FontFace, chr::Char)
+    # inline objects are drawn by the host, see inline.jl
+    chr in ('\uad', '\ufffc') && return invisibleGlyph
+    glyphIdx = glyphIndex(font, chr)
+    glyphIdx == 0 || return glyphIdx
+    isDefaultIgnorable(chr) || (reportMissing(font, chr); return glyphIdx)
+    return chr == '\u200d' ? joinerGlyph : invisibleGlyph
+end
+
+# Arabic joining types: dual (D), right (R), join causing (C) and
+# transparent marks (T); everything else does not join (U), ZWNJ included.
+const rightJoining = Set([
+    0x0622:0x0625; 0x0627; 0x0629; 0x062f:0x0632; 0x0648; 0x0671:0x0673; 0x0675:0x0677;
+    0x0688:0x0699; 0x06c0; 0x06c3:0x06cb; 0x06cd; 0x06cf; 0x06d2; 0x06d3; 0x06d5; 0x06ee; 0x06ef
+])
+const dualJoining = Set([
+    0x0620; 0x0626; 0x0628; 0x062a:0x062e; 0x0633:0x063f; 0x0641:0x0647; 0x0649; 0x064a;
+    0x066e; 0x066f; 0x0678:0x0687; 0x069a:0x06bf; 0x06c1; 0x06c2; 0x06cc; 0x06ce; 0x06d0; 0x06d1;
+    0x06fa:0x06fc; 0x06ff
+])
+
+function joiningType(chr::Char)
+    chr in ('\u200d', '\u0640') && return :C
+    UInt16(min(UInt32(chr), 0xffff)) in dualJoining && return :D
+    UInt16(min(UInt32(chr), 0xffff)) in rightJoining && return :R
+    Base.Unicode.category_abbrev(chr) in ("Mn", "Me") && return :T
+    return :U
+end
+
+# Picks isol, init, medi or fina for every joining letter from its non
+# transparent neighbours in logical order and applies that feature to its
+# glyph; a ZWJ forces joining and a ZWNJ breaks it.
+function applyJoining!(run, gsub::GSUBTable, text; system...)
+    any(((_, idx),) -> joiningType(text[idx]) in (:D, :R), run) || return run
+    types = [joiningType(text[idx]) for (_, idx) in run]
+    joining = findall(!=(:T), types)
+    forms = Pair{Int, String}[]
+    for (j, k) in enumerate(joining)
+        types[k] in (:D, :R) || continue
+        previous = j > 1 ? types[joining[j - 1]] : :U
+        next = j < length(joining) ? types[joining[j + 1]] : :U
+        joinsPrevious = previous in (:D, :C)
+        joinsNext = types[k] == :D && next in (:D, :R, :C)
+        push!(forms, k => joinsPrevious ? (joinsNext ? "medi" : "fina") : (joinsNext ? "init" : "isol"))
+    end
+    # later forms first, a substitution changing the run length leaves earlier indices valid
+    for (k, form) in reverse(forms)
+        applyFeatures!(run, gsub, (form,); range=k:k, system...)
+    end
+    return run
+end
+
+# `mapChar(idx, chr)` replaces characters before the cmap lookup, or returns
+# a glyph index to use directly; clusters keep pointing at the original ones. `script` and `language` are OpenType
+# tags selecting the language system of the features, see `openTypeScript`.
+function shapeText(
+    font::FontFace, text::AbstractString;
+    clusterOffset=0, features=(), mapChar=(idx, chr) -> chr, script="DFLT", language="dflt"
+)
+    mapped(idx, chr) = (m = mapChar(idx, chr); m isa Char ? cmapGlyph(font, m) : FT_UInt(m))
+    run = Tuple{FT_UInt, Int}[(mapped(idx, chr), idx) for (idx, chr) in pairs(text) if chr != '\r']
+    gsub = enableShaping && !isempty(features) ? gsubTable(font) : nothing
+    if gsub !== nothing
+        applyJoining!(run, gsub, text; script=script, language=language)
+        "frac" in features && applyFractions!(run, font, gsub, text; script=script, language=language)
+        applyFeatures!(run, gsub, filter(!=("frac"), collect(features)); script=script, language=language)
+    end
+
+    shaped = ShapedGlyph[]
+    useKerning = enableShaping && hasKerning(font)
+    previous = FT_UInt(0)
+    for (glyphIdx, idx) in run
+        glyph = prepareGlyph(font, glyphIdx)
+        if useKerning && previous != 0 && glyphIdx != 0 && !isempty(shaped)
+            prev = shaped[end]
+            shaped[end] = ShapedGlyph(
+                prev.index, prev.cluster,
+                prev.xAdvance + kerning(font, previous, glyphIdx),
+                prev.xOffset, prev.yOffset
+            )
+        end
+        # control boxes and pictures substituted for ignorables keep their width
+        advance = isInvisibleGlyph(glyphIdx) ? FT_Pos(0) : glyph.advance
+        push!(shaped, ShapedGlyph(glyphIdx, clusterOffset + idx, advance, 0, 0))
+        previous = glyphIdx
+    end
+    return shaped
+end
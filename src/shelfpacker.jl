@@ -0,0 +1,87 @@
+# Shelf packing for texture atlases.
+# Rectangles go onto horizontal shelves; a rectangle takes the lowest shelf
+# it fits on that wastes the least height, otherwise a new shelf is opened
+# below the last one. Glyphs of one size have similar heights, so shelves
+# fill up densely without the bookkeeping of a general rectangle packer.
+# Released rectangles become free slots of their shelf, a shelf without
+# rectangles is reopened for any height.
+
+mutable struct Shelf
+    y::Int
+    height::Int
+    cursorX::Int
+    used::Int                           # rectangles currently on the shelf
+    free::Vector{Tuple{Int, Int}}       # released (x, width) slots left of the cursor
+end
+
+Shelf(y, height) = Shelf(y, height, 0, 0, Tuple{Int, Int}[])
+
+mutable struct ShelfPacker
+    width::Int
+    height::Int
+    shelves::Vector{Shelf}
+    padding::Int            # empty texels kept around every rectangle
+end
+
+ShelfPacker(width, height; padding=1) = ShelfPacker(width, height, Shelf[], padding)
+
+shelfBottom(packer::ShelfPacker) = isempty(packer.shelves) ? 0 : packer.shelves[end].y + packer.shelves[end].height
+
+fitsSlot(shelf::Shelf, w) = any(((_, width),) -> width >= w, shelf.free)
+fits(packer::ShelfPacker, shelf::Shelf, w, h) =
+    h <= shelf.height && (shelf.cursorX + w <= packer.width || fitsSlot(shelf, w))
+
+function takeSlot!(shelf::Shelf, w)
+    for (i, (x, width)) in enumerate(shelf.free)
+        width >= w || continue
+        width == w ? deleteat!(shelf.free, i) : (shelf.free[i] = (x + w, width - w))
+        return x
+    end
+    return nothing
+end
+
+# Top left corner of the rectangle, zero based, or `nothing` when the packer is full.
+function pack!(packer::ShelfPacker, width, height)
+    (w, h) = (width + packer.padding, height + packer.padding)
+    best = nothing
+    for shelf in packer.shelves
+        fits(packer, shelf, w, h) || continue
+        (best === nothing || shelf.height < best.height) && (best = shelf)
+    end
+    if best === nothing
+        y = shelfBottom(packer)
+        (y + h > packer.height || w > packer.width) && return nothing
+        best = Shelf(y, h)
+        push!(packer.shelves, best)
+    end
+    best.used += 1
+    x = takeSlot!(best, w)
+    x === nothing || return (x, best.y)
+    x = best.cursorX
+    best.cursorX += w
+    return (x, best.y)
+end
+
+# Gives back a rectangle returned by `pack!`.
+function release!(packer::ShelfPacker, x, y, width)
+    index = findfirst(shelf -> shelf.y == y, packer.shelves)
+    index === nothing && return packer
+    shelf = packer.shelves[index]
+    shelf.used -= 1
+    if shelf.used == 0
+        # reopen the shelf, the last one gives its height back as well
+        index == length(packer.shelves) ? pop!(packer.shelves) : (shelf.cursorX = 0; empty!(shelf.free))
+    elseif x + width + packer.padding == shelf.cursorX
+        shelf.cursorX = x
+    else
+        push!(shelf.free, (x, width + packer.padding))
+    end
+    return packer
+end
+
+isEmpty(packer::ShelfPacker) = all(shelf -> shelf.used == 0, packer.shelves)
+
+reset!(packer::ShelfPacker) = (empty!(packer.shelves); packer)
+
+# Fraction of the area covered by shelves, for atlas statistics.
+occupancy(packer::ShelfPacker) = sum(s.cursorX*s.height for s in packer.shelves; init=0)/(packer.width*packer.height)
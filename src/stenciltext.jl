@@ -0,0 +1,261 @@
+# Stencil-then-cover backend, a correctness baseline for the curve shader and
+# an option for gpus where the storage buffer loops of font.wgsl are slow.
+# The stencil pass fans triangles from one anchor over every curve chord and
+# adds curve triangles over the control points, counting winding per sample.
+# The cover pass fills the glyph box where the stencil is set and zeroes it
+# again. Arbitrary windings come out exact, edges only antialias through
+# multisampling. The renderer needs a depth format with stencil:
+#
+#     builder = setDepth!(TextRendererBuilder(; sampleCount=4), WGPUCore.WGPUTextureFormat_Depth24PlusStencil8)
+#     backend = StencilText(build(builder))
+#     queue!(backend, layoutText("Hello", style; origin=(20, 40)))
+#     prepare!(backend, surface.size)
+#     draw!(backend, renderPass)
+#
+# Glyphs whose boxes overlap go into separate stencil and cover batches, so
+# each keeps its own color.
+
+@enum FillRule fillNonZero fillEvenOdd
+
+# Layout of `VertexInput` in stencilcover.wgsl.
+struct StencilVertex
+    x::Float32
+    y::Float32
+    u::Float32
+    v::Float32
+    color::UInt32       # rgba8, red in the lowest byte
+end
+
+function getVertexBufferLayout(::Type{StencilVertex}; offset=0)
+    WGPUCore.GPUVertexBufferLayout => [
+        :arrayStride => sizeof(StencilVertex),
+        :stepMode => "Vertex",
+        :attributes => [
+            :attribute => [
+                :format => "Float32x2",
+                :offset => fieldoffset(StencilVertex, 1),
+                :shaderLocation => offset + 0
+            ],
+            :attribute => [
+                :format => "Float32x2",
+                :offset => fieldoffset(StencilVertex, 3),
+                :shaderLocation => offset + 1
+            ],
+            :attribute => [
+                :format => "Unorm8x4",
+                :offset => fieldoffset(StencilVertex, 5),
+                :shaderLocation => offset + 2
+            ],
+        ]
+    ]
+end
+
+# Layout of `StencilUniforms` in stencilcover.wgsl, padded to 16 bytes.
+struct StencilUniforms
+    projection::Mat4
+    transform::Mat4
+    linearizeColors::UInt32
+    padding::NTuple{3, UInt32}
+end
+
+const stencilShaderSource = embedShader("stencilcover.wgsl")
+
+hasStencil(format) = format !== nothing && occursin("Stencil8", textureFormatName(format))
+
+stencilFace(compare, passOp; depthFailOp="Keep") = [
+    :compare => compare,
+    :failOp => "Keep",
+    :depthFailOp => depthFailOp,
+    :passOp => passOp,
+]
+
+# Front faces count up and back faces down for nonzero, even odd only flips.
+function stencilWinding(rule::FillRule)
+    (front, back) = rule == fillNonZero ? ("IncrementWrap", "DecrementWrap") : ("Invert", "Invert")
+    return [
+        :stencilFront => stencilFace("Always", front),
+        :stencilBack => stencilFace("Always", back),
+        :stencilReadMask => 0xff,
+        :stencilWriteMask => 0xff,
+    ]
+end
+
+# Covered samples clear the stencil for the next batch, also behind scene geometry.
+const stencilCover = [
+    :stencilFront => stencilFace("NotEqual", "Zero"; depthFailOp="Zero"),
+    :stencilBack => stencilFace("NotEqual", "Zero"; depthFailOp="Zero"),
+    :stencilReadMask => 0xff,
+    :stencilWriteMask => 0xff,
+]
+
+# Stencil corners (x, y, u, v) of a glyph in em units, y up.
+function stencilTriangles(curves)
+    corners = NTuple{4, Float32}[]
+    isempty(curves) && return corners
+    anchor = (curves[1].x0, curves[1].y0)
+    for c in curves
+        (p0, p1, p2) = ((c.x0, c.y0), (c.x1, c.y1), (c.x2, c.y2))
+        push!(corners, (anchor..., 0f0, 1f0), (p0..., 0f0, 1f0), (p2..., 0f0, 1f0))
+        abs(cross2(p2 .- p0, p1 .- p0)) < 1f-7 && continue
+        push!(corners, (p0..., 0f0, 0f0), (p1..., 0.5f0, 0f0), (p2..., 1f0, 1f0))
+    end
+    return corners
+end
+
+# Hull of the control points as (x0, y0, x1, y1) in em units.
+curvesHull(curves) = (
+    minimum(c -> min(c.x0, c.x1, c.x2), curves), minimum(c -> min(c.y0, c.y1, c.y2), curves),
+    maximum(c -> max(c.x0, c.x1, c.x2), curves), maximum(c -> max(c.y0, c.y1, c.y2), curves),
+)
+
+struct StencilGlyph
+    corners::Vector{NTuple{4, Float32}}
+    hull::NTuple{4, Float32}
+end
+
+# Vertices of one batch, stencil triangles first and then the cover quads.
+struct StencilBatch
+    first::Int
+    stencilCount::Int
+    coverCount::Int
+end
+
+mutable struct StencilText
+    renderer::TextRenderer
+    fillRule::FillRule
+    stencilPipeline::FontPipeline
+    coverPipeline::FontPipeline
+    glyphs::IdDict{FontFace, Dict{FT_UInt, StencilGlyph}}
+    # the open batch
+    stencilVertices::Vector{StencilVertex}
+    coverVertices::Vector{StencilVertex}
+    boxes::Vector{NTuple{4, Float32}}
+    vertices::Vector{StencilVertex}
+    batches::Vector{StencilBatch}
+    drawBatches::Vector{StencilBatch}
+    vertexBuffer
+    vertexCapacity::Int
+    uniformBuffer
+    bindGroup
+end
+
+"""
+    StencilText(renderer; fillRule=fillNonZero)
+
+Stencil-then-cover drawing into the targets of `renderer`, which needs a
+depth format with stencil, see stenciltext.jl. `fillEvenOdd` inverts the
+stencil instead of counting windings.
+"""
+function StencilText(renderer::TextRenderer; fillRule::FillRule=fillNonZero)
+    options = renderer.options
+    hasStencil(options.depthFormat) ||
+        throw(ArgumentError("stencil-then-cover needs a depth format with stencil, not $(options.depthFormat)"))
+    stencilPipeline = rendererPipeline(
+        renderer;
+        label="stencil",
+        shaderSource=stencilShaderSource,
+        bindingLayouts=getBindingLayouts(FontFace)[1:1],
+        fragmentEntryPoint="fs_stencil",
+        depthWrite=false,
+        stencil=stencilWinding(fillRule),
+        vertexBuffers=[getVertexBufferLayout(StencilVertex)]
+    )
+    coverPipeline = rendererPipeline(
+        renderer;
+        label="cover",
+        shaderSource=stencilShaderSource,
+        layouts=pipelineLayouts(stencilPipeline),
+        fragmentEntryPoint="fs_cover",
+        stencil=stencilCover,
+        vertexBuffers=[getVertexBufferLayout(StencilVertex)]
+    )
+    return StencilText(
+        renderer, fillRule, stencilPipeline, coverPipeline, IdDict{FontFace, Dict{FT_UInt, StencilGlyph}}(),
+        StencilVertex[], StencilVertex[], NTuple{4, Float32}[], StencilVertex[], StencilBatch[], StencilBatch[],
+        nothing, 0, nothing, nothing
+    )
+end
+
+stencilGlyph(backend::StencilText, pg::PositionedGlyph) =
+    get!(get!(Dict{FT_UInt, StencilGlyph}, backend.glyphs, pg.font), pg.glyph.index) do
+        curves = collect(glyphCurves(pg.font, pg.glyph))
+        StencilGlyph(stencilTriangles(curves), curvesHull(curves))
+    end
+
+function closeBatch!(backend::StencilText)
+    isempty(backend.boxes) && return backend
+    push!(backend.batches, StencilBatch(length(backend.vertices), length(backend.stencilVertices), length(backend.coverVertices)))
+    append!(backend.vertices, backend.stencilVertices, backend.coverVertices)
+    empty!(backend.stencilVertices)
+    empty!(backend.coverVertices)
+    empty!(backend.boxes)
+    return backend
+end
+
+# Adds the glyphs of `layout` for the next frame.
+function queue!(backend::StencilText, layout::TextLayout)
+    for pg in layout.glyphs
+        pg.glyph.curveCount == 0 && continue
+        glyph = stencilGlyph(backend, pg)
+        color = packColor(pg.color)
+        place(x, y) = (pg.x, pg.y) .+ pg.transform*(x*pg.size, -y*pg.size)
+        (x0, y0, x1, y1) = glyph.hull
+        quad = (place(x0, y0), place(x1, y0), place(x1, y1), place(x0, y1))
+        box = boundingBox(quad)
+        any(other -> overlaps(box, other), backend.boxes) && closeBatch!(backend)
+        push!(backend.boxes, box)
+        for (x, y, u, v) in glyph.corners
+            push!(backend.stencilVertices, StencilVertex(place(x, y)..., u, v, color))
+        end
+        for corner in (1, 2, 3, 1, 3, 4)
+            push!(backend.coverVertices, StencilVertex(quad[corner]..., 0f0, 1f0, color))
+        end
+    end
+    return backend
+end
+
+queue!(backend::StencilText, text::TextInput, position, style::TextStyle) =
+    queue!(backend, layoutText(text, style; origin=Float32.(position)))
+
+function prepare!(backend::StencilText, projection::Projection; transform=identityMat4)
+    renderer = backend.renderer
+    device = renderer.device
+    closeBatch!(backend)
+    (backend.drawBatches, backend.batches) = (backend.batches, empty!(backend.drawBatches))
+    isempty(backend.vertices) || @span "upload" begin
+        (backend.vertexBuffer, backend.vertexCapacity) = writeDynamic(
+            device, backend.vertexBuffer, backend.vertexCapacity, backend.vertices, "stencil vertex buffer", ["Vertex", "CopyDst"]
+        )
+    end
+    empty!(backend.vertices)
+    uniforms = [StencilUniforms(projection.matrix, transform, linearizeColors(renderer.options), (0, 0, 0))]
+    if backend.uniformBuffer === nothing
+        (backend.uniformBuffer, _) = WGPUCore.createBufferWithData(device, "stencil uniform buffer", uniforms, ["Uniform", "CopyDst"])
+        backend.bindGroup = WGPUCore.createBindGroup(
+            "stencil bind group", device,
+            backend.stencilPipeline.bindGroupLayout,
+            [WGPUCore.GPUBuffer => [:binding => 0, :buffer => backend.uniformBuffer, :offset => 0, :size => backend.uniformBuffer.size]]
+        )
+    else
+        WGPUCore.writeBuffer(device.queue, backend.uniformBuffer, uniforms)
+    end
+    return backend
+end
+
+prepare!(backend::StencilText, targetSize::Tuple; kwargs...) = prepare!(backend, orthographic(targetSize...); kwargs...)
+
+# The render pass has to clear the stencil, as `colorAttachmentOptions` does with a depth view.
+function draw!(backend::StencilText, renderPass)
+    isempty(backend.drawBatches) && return backend
+    @span "encode" withDebugGroup(renderPass, "stencil then cover") do
+        WGPUCore.setVertexBuffer(renderPass, 0, backend.vertexBuffer)
+        WGPUCore.setBindGroup(renderPass, 0, backend.bindGroup, UInt32[], 0, 99)
+        for batch in backend.drawBatches
+            WGPUCore.setPipeline(renderPass, backend.stencilPipeline.pipeline)
+            WGPUCore.draw(renderPass, batch.stencilCount; instanceCount=1, firstVertex=batch.first, firstInstance=0)
+            WGPUCore.setPipeline(renderPass, backend.coverPipeline.pipeline)
+            WGPUCore.draw(renderPass, batch.coverCount; instanceCount=1, firstVertex=batch.first + batch.stencilCount, firstInstance=0)
+        end
+    end
+    return backend
+end
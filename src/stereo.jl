@@ -0,0 +1,51 @@
+# Stereo rendering for XR.
+# Geometry is built and uploaded once, each eye only gets its own uniform
+# buffer and bind group holding that eye's view projection. Eyes are either
+# drawn side by side into one target through two viewports, or separately into
+# the per eye layers/views handed out by the XR runtime. WebGPU has no
+# multiview extension, so there is no single pass drawing both eyes; these
+# two ways are all that is offered.
+
+struct StereoTextDraw
+    vertexBuffer
+    indexBuffer
+    indexCount::Int
+    eyes::NTuple{2, Tuple}      # (uniformBuffer, bindGroup) per eye
+end
+
+const leftEye = 1
+const rightEye = 2
+
+function prepareStereoText(
+        fp::FontPipeline, fontBuffers::FontBuffers,
+        layout::TextLayout,
+        eyeProjections::NTuple{2, Projection};
+        transform=identityMat4,
+        uniformOptions...
+    )
+    geometry = uploadGeometry(fp.device, layout)
+    geometry === nothing && return nothing
+    (vertexBuffer, indexBuffer, indexCount) = geometry
+    eyes = map(eyeProjections) do projection
+        uniforms = FontUniforms(projection; transform=transform, uniformOptions...)
+        createUniformBindGroup(fp, fontBuffers, uniforms)
+    end
+    return StereoTextDraw(vertexBuffer, indexBuffer, indexCount, eyes)
+end
+
+# Draws one eye into the current pass, for layered targets with one pass per layer.
+function drawEye(renderPass, fp::FontPipeline, stereoDraw::StereoTextDraw, eye)
+    (uniformBuffer, bindGroup) = stereoDraw.eyes[eye]
+    drawText(renderPass, fp, TextDraw(stereoDraw.vertexBuffer, stereoDraw.indexBuffer, stereoDraw.indexCount, uniformBuffer, bindGroup))
+end
+
+# Side by side stereo in a single target twice as wide as one eye.
+function drawStereo(renderPass, fp::FontPipeline, stereoDraw::StereoTextDraw, (eyeWidth, eyeHeight))
+    for eye in (leftEye, rightEye)
+        WGPUCore.setViewport(renderPass, (eye - 1)*eyeWidth, 0, eyeWidth, eyeHeight, 0, 1)
+        drawEye(renderPass, fp, stereoDraw, eye)
+    end
+end
+
+drawEye(renderPass, fp::FontPipeline, ::Nothing, eye) = nothing
+drawStereo(renderPass, fp::FontPipeline, ::Nothing, eyeSize) = nothing
@@ -0,0 +1,206 @@
+@enum FigureStyle figuresDefault figuresLining figuresOldstyle
+@enum FontVariantCaps capsNormal capsSmall capsAllSmall
+@enum VerticalPosition positionBaseline positionSuper positionSub
+@enum TextTransform transformNone transformUppercase transformLowercase transformCapitalize
+@enum ControlPolicy controlStrip controlPictures controlHexBoxes
+@enum WritingMode horizontalTopToBottom verticalRightToLeft
+@enum GridOverflow gridNextLine gridOffGrid
+
+Base.@kwdef struct TextStyle
+    font::FontFace
+    size::Float32 = 32          # pixels per em
+    color::NTuple{4, Float32} = (1, 1, 1, 1)    # straight alpha rgba
+    lineHeight::Float32 = 1     # multiple of the face line spacing
+    # numeric OpenType features, ignored by faces without them
+    tabularFigures::Bool = false                # tnum, equal width digits for columns
+    figureStyle::FigureStyle = figuresDefault   # lnum or onum
+    slashedZero::Bool = false                   # zero
+    fractions::Bool = false                     # frac, digits around a slash as a fraction
+    # smcp, plus c2sc for capsAllSmall, synthesized when the face lacks them
+    fontVariantCaps::FontVariantCaps = capsNormal
+    # sups or subs, synthesized from the OS/2 script metrics when the face lacks them
+    verticalPosition::VerticalPosition = positionBaseline
+    # bit n - 1 enables ssNN, see `stylisticSetMask` and `availableStylisticSets`
+    stylisticSets::UInt32 = 0
+    # calt and rlig, which script and Arabic faces depend on
+    contextualAlternates::Bool = true
+    requiredLigatures::Bool = true
+    # opsz of variable faces in points, follows `size` unless given
+    autoOpticalSize::Bool = true
+    opticalSize::Union{Nothing, Float32} = nothing
+    # synthetic bold, outlines grow by this fraction of the em on each side
+    emboldening::Float32 = 0
+    # synthetic italic, glyphs lean right by `obliqueAngle` degrees
+    oblique::Bool = false
+    obliqueAngle::Float32 = 12
+    # css text-transform, applied before shaping with the casing rules of `language`
+    textTransform::TextTransform = transformNone
+    # BCP 47 tag like "tr" or "sr-Cyrl", selects casing and locl forms
+    language::String = ""
+    # C0, C1 and bidi controls other than tab and line ends: invisible,
+    # control pictures like ␀ or hex boxes
+    controlCharacters::ControlPolicy = controlStrip
+    # characters the font has no glyph for show their code point in a box
+    # instead of the font's .notdef, see `RenderOptions.notdefHexBoxes`
+    notdefHexBoxes::Bool = false
+    # kinsoku rules of wrapped CJK text, see `LineBreakStrictness`
+    lineBreak::LineBreakStrictness = lineBreakNormal
+    # greedy or total fit wrapping, see `LineBreaker`
+    lineBreaker::LineBreaker = greedyBreaking
+    # vertical CJK columns from right to left, see vertical.jl
+    writingMode::WritingMode = horizontalTopToBottom
+    # justified Arabic lines elongate joins with tatweels before widening spaces
+    kashida::Bool = true
+    # css text-align-last: justify, the last line of a paragraph is stretched too
+    justifyLastLine::Bool = false
+    # em added to each word separator of justified lines before letters are
+    # tracked apart by up to `maxLetterSpacing` em per gap
+    maxWordSpacing::Float32 = 0.5
+    maxLetterSpacing::Float32 = 0.05
+    # baselines snap down to multiples of `baselineGrid` pixels past
+    # `baselineGridOffset` in layout space, 0 keeps them where they fall
+    baselineGrid::Float32 = 0
+    baselineGridOffset::Float32 = 0
+    # lines grown by inline objects move to the next grid line or leave the grid
+    gridOverflow::GridOverflow = gridNextLine
+    # em every character cell advances, wide CJK taking two cells, see
+    # monospace.jl; 0 keeps the advances of the font
+    monospaceAdvance::Float32 = 0
+end
+
+TextStyle(font::FontFace; kwargs...) = TextStyle(; font=font, kwargs...)
+
+# `style` with its font at the optical size and synthesized weight of the style.
+function resolvedStyle(style::TextStyle)
+    opsz = something(style.opticalSize, style.autoOpticalSize ? style.size*pointsPerPixel : nothing, Some(nothing))
+    font = opsz === nothing ? style.font : opticalSizeFace(style.font, opsz)
+    font = emboldenedFace(font, style.emboldening)
+    return font === style.font ? style : setfields(style; font=font)
+end
+
+# Shear of synthesized obliques around the pen position, y grows downwards.
+obliqueTransform(style::TextStyle) = style.oblique ? skewing(-deg2rad(style.obliqueAngle)) : identityAffine2
+
+primaryLanguage(tag) = lowercase(first(split(tag, ('-', '_'))))
+
+# Simple case mapping, Turkish and Azerbaijani pair dotted and dotless i.
+function caseChar(chr::Char, mode::Symbol, language)
+    if language in ("tr", "az")
+        mode != :lower && chr == 'i' && return 'İ'
+        mode == :lower && chr == 'I' && return 'ı'
+        mode == :lower && chr == 'İ' && return 'i'
+    end
+    return mode == :upper ? uppercase(chr) : mode == :lower ? lowercase(chr) : titlecase(chr)
+end
+
+# `(idx, chr) -> chr` applying the text transform of `style` to the
+# characters of `text`. Capitalized words start after spaces and punctuation
+# other than apostrophes.
+function caseMapping(style::TextStyle, text::AbstractString)
+    transform = style.textTransform
+    transform == transformNone && return (idx, chr) -> chr
+    language = primaryLanguage(style.language)
+    transform == transformUppercase && return (idx, chr) -> caseChar(chr, :upper, language)
+    transform == transformLowercase && return (idx, chr) -> caseChar(chr, :lower, language)
+    return function (idx, chr)
+        previous = idx > firstindex(text) ? text[prevind(text, idx)] : ' '
+        wordStart = isspace(previous) || (ispunct(previous) && !(previous in ('\'', '’')))
+        return wordStart ? caseChar(chr, :title, language) : chr
+    end
+end
+
+isBidiControl(chr::Char) =
+    chr in ('\u061c', '\u200e', '\u200f') || '\u202a' <= chr <= '\u202e' || '\u2066' <= chr <= '\u2069'
+
+isControlCharacter(chr::Char) = (iscntrl(chr) && !(chr in ('\t', '\n', '\r'))) || isBidiControl(chr)
+
+# Control pictures exist for C0 and delete, other controls show a hex box.
+function controlPicture(font::FontFace, chr::Char)
+    picture = chr <= '\x1f' ? Char(0x2400 + UInt32(chr)) : chr == '\x7f' ? '\u2421' : nothing
+    picture === nothing || glyphIndex(font, picture) == 0 ? hexBoxGlyph(chr) : picture
+end
+
+# Character or glyph index drawn for a control character under the policy of `style`.
+function controlGlyph(style::TextStyle, chr::Char)
+    policy = style.controlCharacters
+    policy == controlStrip && return invisibleGlyph
+    policy == controlHexBoxes && return hexBoxGlyph(chr)
+    return controlPicture(style.font, chr)
+end
+
+# Hex box for characters without a glyph when `style` asks for them,
+# ignorables stay invisible.
+function notdefGlyph(style::TextStyle, chr::Char)
+    style.notdefHexBoxes && !isDefaultIgnorable(chr) && glyphIndex(style.font, chr) == 0 || return chr
+    reportMissing(style.font, chr)
+    return hexBoxGlyph(chr)
+end
+
+stylisticSetMask(sets...) = reduce(|, (UInt32(1) << (n - 1) for n in sets); init=UInt32(0))
+
+stylisticSetNumbers(mask) = [n for n in 1:20 if mask >> (n - 1) & 1 == 1]
+
+# Feature tags applied when shaping text of `style`.
+function styleFeatures(style::TextStyle)
+    # locl only has lookups under the language systems of `style.language`
+    tags = ["locl"]
+    style.requiredLigatures && push!(tags, "rlig")
+    style.contextualAlternates && push!(tags, "calt")
+    style.tabularFigures && push!(tags, "tnum")
+    style.figureStyle == figuresLining && push!(tags, "lnum")
+    style.figureStyle == figuresOldstyle && push!(tags, "onum")
+    style.slashedZero && push!(tags, "zero")
+    style.fractions && push!(tags, "frac")
+    style.fontVariantCaps == capsNormal || push!(tags, "smcp")
+    style.fontVariantCaps == capsAllSmall && push!(tags, "c2sc")
+    style.verticalPosition == positionSuper && push!(tags, "sups")
+    style.verticalPosition == positionSub && push!(tags, "subs")
+    style.writingMode == verticalRightToLeft && push!(tags, "vert")
+    for n in stylisticSetNumbers(style.stylisticSets)
+        push!(tags, "ss" * lpad(n, 2, '0'))
+    end
+    return tags
+end
+
+# Predicate on characters drawn as scaled capitals because the face has no
+# smcp (or c2sc) lookups, `nothing` when the face covers the caps variant.
+function syntheticCaps(style::TextStyle)
+    caps = style.fontVariantCaps
+    caps == capsNormal && return nothing
+    gsub = enableShaping ? gsubTable(style.font) : nothing
+    lower = gsub === nothing || !hasFeature(gsub, "smcp")
+    upper = caps == capsAllSmall && (gsub === nothing || !hasFeature(gsub, "c2sc"))
+    (lower || upper) || return nothing
+    return chr -> (lower && islowercase(chr)) || (upper && isuppercase(chr))
+end
+
+# (size scale, width scale, x offset, y offset) of synthesized super- and
+# subscripts, offsets in font units; identity on the baseline or when the
+# face has the sups or subs lookups.
+function syntheticPosition(style::TextStyle)
+    position = style.verticalPosition
+    position == positionBaseline && return (1f0, 1f0, 0, 0)
+    gsub = enableShaping ? gsubTable(style.font) : nothing
+    gsub !== nothing && hasFeature(gsub, position == positionSuper ? "sups" : "subs") && return (1f0, 1f0, 0, 0)
+    metrics = scriptMetrics(style.font)
+    (xSize, ySize, xOffset, yOffset) = position == positionSuper ? metrics.superscript : metrics.subscript
+    unitsPerEm = style.font.metrics.unitsPerEm
+    return (Float32(ySize/unitsPerEm), Float32(xSize/unitsPerEm), xOffset, yOffset)
+end
+
+# `y` moved down to the next line of the baseline grid of `style`.
+function snapBaseline(style::TextStyle, y)
+    grid = style.baselineGrid
+    grid > 0 || return y
+    offset = style.baselineGridOffset
+    # rounding noise must not skip a whole grid line
+    return offset + ceil((y - offset)/grid - 1f-4)*grid
+end
+
+pixelScale(style::TextStyle) = style.size/style.font.emSize
+
+# Copy of an immutable `x` with the given fields replaced.
+setfields(x::T; kwargs...) where T =
+    T((haskey(kwargs, f) ? kwargs[f] : getfield(x, f) for f in fieldnames(T))...)
+
+restyle(style::TextStyle; kwargs...) = setfields(style; kwargs...)
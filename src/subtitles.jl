@@ -0,0 +1,144 @@
+# Subtitles for media players.
+# A `SubtitleTrack` holds timed cues, each set with one of the track's
+# presets: where the bottom center of the cue sits in the video, its text
+# size relative to the video height, an outline drawn as a halo and a
+# drop shadow. Cues fade in and out, karaoke cues fill their syllables with
+# the preset's karaoke color character by character as they are sung.
+# Cues of one preset shown at the same time stack upwards, later ones on top.
+#
+#     track = SubtitleTrack(SubtitlePreset(style))
+#     addCue!(track, 1.0, 3.5, "Hello there")
+#     queueSubtitles!(renderer, track, playbackTime, (0, 0, 1920, 1080))
+
+struct SubtitlePreset
+    style::TextStyle
+    # bottom center of the cue in fractions of the video size
+    position::NTuple{2, Float32}
+    # text size as a fraction of the video height, 0 keeps the style size
+    relativeSize::Float32
+    # lines wrap at this fraction of the video width
+    maxWidth::Float32
+    outline::Union{Nothing, Halo}
+    # pixels at a relative size of 1/20, scaled with the text
+    shadowOffset::NTuple{2, Float32}
+    shadowColor::Union{Nothing, NTuple{4, Float32}}
+    fadeIn::Float32         # seconds
+    fadeOut::Float32
+    karaokeColor::NTuple{4, Float32}
+end
+
+function SubtitlePreset(
+    style::TextStyle;
+    position=(0.5f0, 0.92f0), relativeSize=1/20, maxWidth=0.8f0,
+    outline=Halo((0f0, 0f0, 0f0, 1f0); width=2), shadowOffset=(2f0, 2f0), shadowColor=(0f0, 0f0, 0f0, 0.6f0),
+    fadeIn=0.1f0, fadeOut=0.1f0, karaokeColor=(1f0, 0.85f0, 0.2f0, 1f0)
+)
+    SubtitlePreset(style, position, relativeSize, maxWidth, outline, shadowOffset, shadowColor, fadeIn, fadeOut, karaokeColor)
+end
+
+# A karaoke syllable, the string range of the cue text sung from `start`
+# seconds after the cue start for `duration` seconds.
+struct KaraokeSyllable
+    range::UnitRange{Int}
+    start::Float32
+    duration::Float32
+end
+
+struct SubtitleCue
+    start::Float64
+    stop::Float64
+    text::String
+    preset::Symbol
+    syllables::Vector{KaraokeSyllable}
+end
+
+mutable struct SubtitleTrack
+    cues::Vector{SubtitleCue}       # by start time
+    presets::Dict{Symbol, SubtitlePreset}
+    longestCue::Float64
+end
+
+SubtitleTrack(preset::SubtitlePreset) = SubtitleTrack(SubtitleCue[], Dict(:default => preset), 0)
+
+setPreset!(track::SubtitleTrack, name::Symbol, preset::SubtitlePreset) = (track.presets[name] = preset; track)
+
+"""
+    addCue!(track, start, stop, text; preset=:default, syllables=KaraokeSyllable[])
+
+Shows `text` from `start` until `stop` seconds of playback with the preset
+named `preset`.
+"""
+function addCue!(track::SubtitleTrack, start, stop, text; preset::Symbol=:default, syllables=KaraokeSyllable[])
+    haskey(track.presets, preset) || throw(ArgumentError("unknown subtitle preset $preset"))
+    cue = SubtitleCue(start, stop, validText(text), preset, syllables)
+    insert!(track.cues, searchsortedlast(track.cues, cue; by=c -> c.start) + 1, cue)
+    track.longestCue = max(track.longestCue, stop - start)
+    return track
+end
+
+# Cues shown at `time`, by start time.
+function activeCues(track::SubtitleTrack, time)
+    startOf(c) = c isa SubtitleCue ? c.start : c
+    from = searchsortedfirst(track.cues, time - track.longestCue; by=startOf)
+    to = searchsortedlast(track.cues, time; by=startOf)
+    return [cue for cue in track.cues[from:to] if cue.stop > time]
+end
+
+function cueOpacity(cue::SubtitleCue, preset::SubtitlePreset, time)
+    fadeIn = preset.fadeIn > 0 ? clamp((time - cue.start)/preset.fadeIn, 0, 1) : 1
+    fadeOut = preset.fadeOut > 0 ? clamp((cue.stop - time)/preset.fadeOut, 0, 1) : 1
+    return Float32(fadeIn*fadeOut)
+end
+
+fade((r, g, b, a), opacity) = (r, g, b, a*opacity)
+
+# Characters of the syllables sung by `time`, each syllable filled evenly
+# over its duration.
+function karaokeSpans(cue::SubtitleCue, color, time)
+    spans = HighlightSpan[]
+    elapsed = time - cue.start
+    for syllable in cue.syllables
+        elapsed < syllable.start && continue
+        indices = collect(eachindex(SubString(cue.text, first(syllable.range), last(syllable.range)))) .+ (first(syllable.range) - 1)
+        sung = syllable.duration > 0 ?
+            ceil(Int, clamp((elapsed - syllable.start)/syllable.duration, 0, 1)*length(indices)) : length(indices)
+        sung > 0 && push!(spans, HighlightSpan(first(indices):last(indices[1:sung]), color))
+    end
+    return spans
+end
+
+"""
+    queueSubtitles!(target, track, time, video=(0, 0, width, height))
+
+Queues the cues of `track` shown at `time` seconds into `video`, the
+(x, y, width, height) of the picture in pixels.
+"""
+function queueSubtitles!(target::TextTarget, track::SubtitleTrack, time, video)
+    (vx, vy, vw, vh) = Float32.(video)
+    bottoms = Dict{Symbol, Float32}()
+    for cue in activeCues(track, time)
+        preset = track.presets[cue.preset]
+        opacity = cueOpacity(cue, preset, time)
+        style = preset.relativeSize > 0 ? setfields(preset.style; size=preset.relativeSize*vh) : preset.style
+        scale = style.size/(vh/20)
+        style = setfields(style; color=fade(style.color, opacity))
+        maxWidth = preset.maxWidth*vw
+        layout = layoutText(cue.text, style; maxWidth=maxWidth, align=alignCenter)
+        bottom = get(bottoms, cue.preset, vy + preset.position[2]*vh)
+        origin = (vx + preset.position[1]*vw - maxWidth/2, bottom - layout.height)
+        bottoms[cue.preset] = origin[2]
+        # the outline grows with the text like the shadow does
+        halo = preset.outline === nothing ? nothing :
+            Halo(fade(preset.outline.color, opacity), preset.outline.width*scale)
+        highlights = karaokeSpans(cue, fade(preset.karaokeColor, opacity), time)
+        if preset.shadowColor !== nothing
+            shadow = fade(preset.shadowColor, opacity)
+            queue!(target, Section(
+                cue.text, origin .+ preset.shadowOffset.*scale, setfields(style; color=shadow);
+                maxWidth=maxWidth, align=alignCenter, halo=halo === nothing ? nothing : Halo(shadow, halo.width)
+            ))
+        end
+        queue!(target, Section(cue.text, origin, style; maxWidth=maxWidth, align=alignCenter, halo=halo, highlights=highlights))
+    end
+    return target
+end
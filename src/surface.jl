@@ -0,0 +1,27 @@
+# Presentation surfaces.
+# The renderer does not care which windowing library owns the canvas, anything
+# WGPUCore can build a presentation context for works (glfw, sdl or a custom
+# embedder implementing `WGPUCore.getContext` for its own canvas type).
+# Canvases that do not have a `size` field should extend `surfaceSize`.
+
+mutable struct TextSurface
+    canvas
+    context
+    format
+    size::NTuple{2, Int}
+end
+
+surfaceSize(canvas) = Tuple(Int.(canvas.size))
+
+function attachSurface(device, canvas; format=WGPUCore.getPreferredFormat(canvas))
+    context = WGPUCore.getContext(canvas)
+    WGPUCore.config(context; device=device, format=format)
+    return TextSurface(canvas, context, format, surfaceSize(canvas))
+end
+
+function currentTextureView(surface::TextSurface)
+    surface.size = surfaceSize(surface.canvas)
+    return WGPUCore.getCurrentTexture(surface.context)
+end
+
+present(surface::TextSurface) = WGPUCore.present(surface.context)
@@ -0,0 +1,104 @@
+# Synthetic styles for families that ship without a bold face.
+# A `SyntheticProvider` wraps the regular provider and offsets every contour
+# outwards by a fraction of the em. End points move along the miter of the
+# tangents meeting there and control points along the miter of their curve's
+# end tangents, a close parallel curve for the gentle quadratics of text.
+
+struct SyntheticProvider{P <: FontProvider} <: FontProvider
+    base::P
+    emboldening::Float32    # outward offset in em
+end
+
+fontMetrics(provider::SyntheticProvider) = fontMetrics(provider.base)
+glyphIndex(provider::SyntheticProvider, chr::Char) = glyphIndex(provider.base, chr)
+hasKerning(provider::SyntheticProvider) = hasKerning(provider.base)
+kerning(provider::SyntheticProvider, left, right) = kerning(provider.base, left, right)
+fontLabel(provider::SyntheticProvider) = fontLabel(provider.base) * " synthetic"
+sfntTable(provider::SyntheticProvider, tag) = sfntTable(provider.base, tag)
+
+# Gray bitmaps would lose the synthesized style, color bitmaps have none anyway.
+function rasterizeGlyph(provider::SyntheticProvider, glyphIdx, pixelSize; color=false)
+    color || return nothing
+    raster = rasterizeGlyph(provider.base, glyphIdx, pixelSize; color=true)
+    return raster !== nothing && eltype(raster[1]) == NTuple{4, UInt8} ? raster : nothing
+end
+
+unitVector((x, y)) = (l = hypot(x, y); l > 0 ? (x/l, y/l) : (0f0, 0f0))
+
+# Offset direction of unit length normals `a` and `b` meeting at a corner,
+# long miters of sharp corners are cut off.
+function miter(a, b)
+    s = a .+ b
+    return s./max(1 + a[1]*b[1] + a[2]*b[2], 0.25f0)
+end
+
+# Closed contours of a glyph, consecutive curves share their end points.
+function contourRanges(curves)
+    ranges = UnitRange{Int}[]
+    start = firstindex(curves)
+    for i in eachindex(curves)
+        closes = i == lastindex(curves) || (curves[i + 1].x0, curves[i + 1].y0) != (curves[i].x2, curves[i].y2)
+        closes && (push!(ranges, start:i); start = i + 1)
+    end
+    return ranges
+end
+
+function emboldenCurves(curves, strength)
+    # outward is right of travel for counter clockwise outlines, holes run the other way
+    area = sum(c -> (c.x0*c.y1 - c.x1*c.y0) + (c.x1*c.y2 - c.x2*c.y1), curves; init=0f0)
+    side = area >= 0 ? 1f0 : -1f0
+    normal((x, y)) = unitVector((side*y, -side*x))
+    startTangent(c) = (c.x1, c.y1) != (c.x0, c.y0) ? (c.x1 - c.x0, c.y1 - c.y0) : (c.x2 - c.x0, c.y2 - c.y0)
+    endTangent(c) = (c.x2, c.y2) != (c.x1, c.y1) ? (c.x2 - c.x1, c.y2 - c.y1) : (c.x2 - c.x0, c.y2 - c.y0)
+
+    result = BufferCurve[]
+    for range in contourRanges(curves)
+        contour = curves[range]
+        n = length(contour)
+        # offsets of the start point of every curve
+        corners = [miter(normal(endTangent(contour[mod1(k - 1, n)])), normal(startTangent(contour[k]))) for k in 1:n]
+        for (k, c) in enumerate(contour)
+            p0 = (c.x0, c.y0) .+ strength.*corners[k]
+            p1 = (c.x1, c.y1) .+ strength.*miter(normal(startTangent(c)), normal(endTangent(c)))
+            p2 = (c.x2, c.y2) .+ strength.*corners[mod1(k + 1, n)]
+            # the left side bearing stays where it was
+            push!(result, BufferCurve((p0 .+ (strength, 0f0))..., (p1 .+ (strength, 0f0))..., (p2 .+ (strength, 0f0))...))
+        end
+    end
+    return result
+end
+
+function loadGlyph!(curves, provider::SyntheticProvider, glyphIdx)
+    start = length(curves)
+    metrics = loadGlyph!(curves, provider.base, glyphIdx)
+    (start == length(curves) || provider.emboldening == 0) && return metrics
+    emboldened = emboldenCurves(curves[(start + 1):end], provider.emboldening)
+    resize!(curves, start)
+    append!(curves, emboldened)
+    d = round(Int, provider.emboldening*fontMetrics(provider.base).unitsPerEm)
+    return GlyphMetrics(metrics.width + 2d, metrics.height + 2d, metrics.bearingX, metrics.bearingY + d, metrics.advance + 2d)
+end
+
+"""
+    emboldenedFace(font, strength)
+
+`font` with every outline grown by `strength` em on each side, for families
+without a bold face; 0.02 to 0.04 em reads as bold for most text faces.
+Registered custom glyphs are emboldened as well. Faces are cached on `font`.
+"""
+function emboldenedFace(font::FontFace, strength)
+    strength = Float32(strength)
+    strength == 0 && return font
+    return lock(ftLock) do
+        get!(font.synthetic, strength) do
+            face = FontFace(SyntheticProvider(font.provider, strength))
+            for (chr, glyphIdx) in sort!(collect(font.customGlyphs); by=last)
+                glyph = font.glyphs[glyphIdx]
+                curves = emboldenCurves(collect(glyphCurves(font, glyph)), strength)
+                registerGlyph!(face, chr, [((c.x0, c.y0), (c.x1, c.y1), (c.x2, c.y2)) for c in curves];
+                    advance=glyph.advance/font.emSize + 2strength)
+            end
+            face
+        end
+    end
+end
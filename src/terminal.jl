@@ -0,0 +1,344 @@
+# Terminal grids, a gpu backend for terminal emulators.
+#
+#     grid = TerminalGrid(renderer, style, 80, 24)
+#     setCell!(grid, 1, 1, '$'; foreground=(0.4f0, 0.9f0, 0.4f0, 1f0))
+#     prepare!(grid, surface.size)
+#     draw!(grid, renderPass)
+#
+# Cells of one monospace style hold a glyph, foreground and background
+# colors and attribute flags, stored row by row in one storage buffer. Only
+# rows changed since the last prepare! are written again, and the whole
+# grid is a single instanced draw of one quad per cell that fills the
+# background and covers the glyph from the curves of the font.
+
+const cellUnderline = UInt32(1)
+const cellStrikethrough = UInt32(2)
+# overstruck one pixel to the right, bold faces would need a second draw
+const cellBold = UInt32(4)
+# foreground at two thirds of its brightness
+const cellDim = UInt32(8)
+# foreground and background swapped
+const cellReverse = UInt32(16)
+const cellBlink = UInt32(32)
+
+@enum CursorShape::UInt32 begin
+    cursorBlock = 0
+    cursorUnderline = 1
+    cursorBar = 2
+    # block outline, e.g. for unfocused windows
+    cursorHollowBlock = 3
+end
+
+struct TerminalCursor
+    column::Int
+    row::Int
+    shape::CursorShape
+    color::UInt32
+    blinking::Bool
+end
+
+# Layout of `Cell` in terminal.wgsl.
+struct TerminalCell
+    bufferIndex::Int32      # -1 for cells without a glyph
+    foreground::UInt32      # rgba8, red in the lowest byte
+    background::UInt32
+    flags::UInt32
+    underlineColor::UInt32  # transparent for the foreground color
+end
+
+Base.zero(::Type{TerminalCell}) = TerminalCell(-1, 0, 0, 0, 0)
+
+# Layout of `GridUniforms` in terminal.wgsl.
+struct GridUniforms
+    projection::Mat4
+    origin::NTuple{2, Float32}
+    cellSize::NTuple{2, Float32}
+    baseline::Float32
+    emPerPixel::Float32
+    columns::UInt32
+    antiAliasingWindowSize::Float32
+    enableSuperSamplingAntiAliasing::UInt32
+    linearizeColors::UInt32
+    underlinePosition::Float32
+    underlineThickness::Float32
+    strikethroughPosition::Float32
+    time::Float32
+    blinkInterval::Float32
+    cursorCell::UInt32
+    cursorShape::UInt32
+    cursorColor::UInt32
+    cursorThickness::Float32
+    cursorBlinks::UInt32
+end
+
+const noCursorCell = typemax(UInt32)
+
+const terminalShaderSource = embedShader("terminal.wgsl")
+
+function getTerminalBindingLayouts(; binding=0)
+    [
+        getBindingLayouts(FontFace; binding=binding)...,
+        WGPUCore.WGPUBufferEntry => [
+            :binding => binding + 3,
+            :visibility => ["Fragment"],
+            :type => "ReadOnlyStorage"
+        ],
+    ]
+end
+
+function getTerminalBindings(fontBuffers::FontBuffers, uniformBuffer, cellBuffer; binding=0)
+    [
+        getBindings(fontBuffers, uniformBuffer; binding=binding)...,
+        WGPUCore.GPUBuffer => [
+            :binding => binding + 3,
+            :buffer  => cellBuffer,
+            :offset  => 0,
+            :size    => cellBuffer.size
+        ],
+    ]
+end
+
+mutable struct TerminalGrid
+    renderer::TextRenderer
+    style::TextStyle
+    columns::Int
+    rows::Int
+    origin::NTuple{2, Float32}
+    cellSize::NTuple{2, Float32}
+    baseline::Float32           # pixels from the top of a cell
+    foreground::UInt32          # colors of cleared cells
+    background::UInt32
+    cells::Vector{TerminalCell} # row major
+    damagedRows::BitVector      # rows changed since the last upload
+    cursor::Union{Nothing, TerminalCursor}
+    # seconds blinking cells and cursors stay visible, and then hidden
+    blinkInterval::Float32
+    pipeline::FontPipeline
+    fontBuffers::Union{Nothing, FontBuffers}
+    cellBuffer
+    uniformBuffer
+    bindGroup
+end
+
+# Cells are as wide as the advance of the style's zero and a line high,
+# both rounded to whole pixels so glyph edges line up across the grid.
+function defaultCellSize(style::TextStyle)
+    font = style.font
+    scale = pixelScale(style)
+    advance = prepareGlyph(font, cmapGlyph(font, '0')).advance
+    return (Float32(max(round(advance*scale), 1)), Float32(max(round(font.metrics.height*scale*style.lineHeight), 1)))
+end
+
+"""
+    TerminalGrid(renderer, style, columns, rows; origin=(0, 0), cellSize, foreground=style.color, background=(0, 0, 0, 0), blinkInterval=0.5)
+
+`columns` × `rows` cells of `style` drawn with the device and color target
+options of `renderer`. `cellSize` defaults to the advance of the style's
+digits by its line height. Blinking cells and cursors switch between shown
+and hidden every `blinkInterval` seconds.
+"""
+function TerminalGrid(
+    renderer::TextRenderer, style::TextStyle, columns, rows;
+    origin=(0f0, 0f0), cellSize=nothing, foreground=style.color, background=(0f0, 0f0, 0f0, 0f0), blinkInterval=0.5f0
+)
+    style = resolvedStyle(style)
+    font = style.font
+    cellSize = Float32.(something(cellSize, defaultCellSize(style)))
+    scale = pixelScale(style)
+    # the em box centered in the cell
+    baseline = round((cellSize[2] - (font.metrics.ascender - font.metrics.descender)*scale)/2 + font.metrics.ascender*scale)
+    pipeline = rendererPipeline(
+        renderer;
+        label="terminal",
+        shaderSource=terminalShaderSource,
+        bindingLayouts=getTerminalBindingLayouts(),
+        vertexBuffers=[]
+    )
+    (foreground, background) = (packColor(foreground), packColor(background))
+    cells = fill(TerminalCell(-1, foreground, background, 0, 0), columns*rows)
+    return TerminalGrid(
+        renderer, style, columns, rows, Float32.(origin), cellSize, baseline, foreground, background,
+        cells, trues(rows), nothing, blinkInterval, pipeline, nothing, nothing, nothing, nothing
+    )
+end
+
+cellIndex(grid::TerminalGrid, column, row) = (row - 1)*grid.columns + column
+
+Base.size(grid::TerminalGrid) = (grid.columns, grid.rows)
+Base.getindex(grid::TerminalGrid, column, row) = grid.cells[cellIndex(grid, column, row)]
+
+function bufferIndexOf(grid::TerminalGrid, chr::Char)
+    chr == ' ' && return Int32(-1)
+    style = grid.style
+    mapped = notdefGlyph(style, chr)
+    glyphIdx = mapped isa Char ? cmapGlyph(style.font, mapped) : FT_UInt(mapped)
+    glyph = prepareGlyph(style.font, glyphIdx)
+    return glyph.curveCount == 0 ? Int32(-1) : glyph.bufferIndex
+end
+
+"""
+    setCell!(grid, column, row, chr; foreground, background, flags=0, underlineColor)
+
+Puts `chr` into the cell at `column` and `row`, counted from 1 at the top
+left. Colors default to those of cleared cells, `flags` combines
+`cellUnderline`, `cellStrikethrough`, `cellBold`, `cellDim`, `cellReverse`
+and `cellBlink`. Underlines take the foreground color unless
+`underlineColor` is given, as set by SGR 58.
+"""
+function setCell!(
+    grid::TerminalGrid, column, row, chr::Char;
+    foreground=nothing, background=nothing, flags=0, underlineColor=nothing
+)
+    (1 <= column <= grid.columns && 1 <= row <= grid.rows) || throw(BoundsError(grid, (column, row)))
+    cell = TerminalCell(
+        bufferIndexOf(grid, chr),
+        foreground === nothing ? grid.foreground : packColor(foreground),
+        background === nothing ? grid.background : packColor(background),
+        UInt32(flags),
+        underlineColor === nothing ? UInt32(0) : packColor(underlineColor)
+    )
+    index = cellIndex(grid, column, row)
+    if grid.cells[index] != cell
+        grid.cells[index] = cell
+        grid.damagedRows[row] = true
+    end
+    return grid
+end
+
+# Characters of `text` in consecutive cells of `row`, clipped at its end.
+function setCells!(grid::TerminalGrid, column, row, text::AbstractString; kwargs...)
+    for (k, chr) in enumerate(validText(text))
+        column + k - 1 > grid.columns && break
+        setCell!(grid, column + k - 1, row, chr; kwargs...)
+    end
+    return grid
+end
+
+clearCell(grid::TerminalGrid) = TerminalCell(-1, grid.foreground, grid.background, 0, 0)
+
+function clear!(grid::TerminalGrid)
+    fill!(grid.cells, clearCell(grid))
+    fill!(grid.damagedRows, true)
+    return grid
+end
+
+# Keeps the cells of the overlapping top left part, the buffer is replaced
+# on the next prepare!.
+function Base.resize!(grid::TerminalGrid, columns, rows)
+    (columns, rows) == size(grid) && return grid
+    cells = fill(clearCell(grid), columns*rows)
+    for row in 1:min(rows, grid.rows), column in 1:min(columns, grid.columns)
+        cells[(row - 1)*columns + column] = grid[column, row]
+    end
+    (grid.columns, grid.rows, grid.cells) = (columns, rows, cells)
+    grid.damagedRows = trues(rows)
+    (grid.cellBuffer, grid.bindGroup) = (nothing, nothing)
+    return grid
+end
+
+"""
+    setCursor!(grid, column, row; shape=cursorBlock, color, blinking=false)
+
+Shows the cursor over the cell at `column` and `row`, in the foreground
+color of cleared cells unless `color` is given. Moving the cursor only
+changes uniforms, no cells are uploaded again.
+"""
+function setCursor!(grid::TerminalGrid, column, row; shape::CursorShape=cursorBlock, color=nothing, blinking=false)
+    color = color === nothing ? grid.foreground : packColor(color)
+    grid.cursor = TerminalCursor(column, row, shape, color, blinking)
+    return grid
+end
+
+hideCursor!(grid::TerminalGrid) = (grid.cursor = nothing; grid)
+
+function cursorCell(grid::TerminalGrid)
+    cursor = grid.cursor
+    cursor === nothing && return noCursorCell
+    (1 <= cursor.column <= grid.columns && 1 <= cursor.row <= grid.rows) || return noCursorCell
+    return UInt32(cellIndex(grid, cursor.column, cursor.row) - 1)
+end
+
+function gridUniforms(grid::TerminalGrid, projection::Projection; time=0f0)
+    style = grid.style
+    options = grid.renderer.options
+    cursor = grid.cursor
+    GridUniforms(
+        projection.matrix,
+        grid.origin,
+        grid.cellSize,
+        grid.baseline,
+        1/style.size,
+        grid.columns,
+        options.antiAliasingWindowSize,
+        options.enableSuperSamplingAntiAliasing,
+        linearizeColors(options),
+        round(0.1f0*style.size),
+        max(round(0.06f0*style.size), 1f0),
+        round(0.28f0*style.size),
+        time,
+        grid.blinkInterval,
+        cursorCell(grid),
+        cursor === nothing ? 0 : UInt32(cursor.shape),
+        cursor === nothing ? 0 : cursor.color,
+        max(round(0.1f0*grid.cellSize[1]), 1f0),
+        cursor !== nothing && cursor.blinking
+    )
+end
+
+# Uploads the damaged rows, consecutive ones with a single write.
+function writeDamage!(grid::TerminalGrid)
+    queue = grid.renderer.device.queue
+    row = findfirst(grid.damagedRows)
+    while row !== nothing
+        stop = something(findnext(!, grid.damagedRows, row), grid.rows + 1) - 1
+        range = cellIndex(grid, 1, row):cellIndex(grid, grid.columns, stop)
+        WGPUCore.writeBuffer(queue, grid.cellBuffer, grid.cells[range]; bufferOffset=(first(range) - 1)*sizeof(TerminalCell))
+        row = findnext(grid.damagedRows, stop + 1)
+    end
+    fill!(grid.damagedRows, false)
+end
+
+# `time` in seconds drives blinking cells and cursors.
+function prepare!(grid::TerminalGrid, projection::Projection; time=0f0)
+    renderer = grid.renderer
+    device = renderer.device
+    # glyphs of new cells are built, so the font buffers may have grown
+    fontBuffers = fontBuffersFor(renderer, grid.style.font)
+    chunkCount(fontBuffers) == 1 || throw(FontRenderError(
+        deviceLimitError,
+        "$(fontLabel(fontBuffers.font)) needs $(chunkCount(fontBuffers)) curve bindings, terminal grids draw from one"
+    ))
+    if grid.cellBuffer === nothing
+        grid.cellBuffer = createStorageBuffer(device, "terminal cell buffer", nonEmpty(grid.cells))
+        fill!(grid.damagedRows, false)
+    else
+        @span "upload" writeDamage!(grid)
+    end
+    uniforms = [gridUniforms(grid, projection; time=time)]
+    if grid.uniformBuffer === nothing
+        (grid.uniformBuffer, _) = WGPUCore.createBufferWithData(device, "terminal uniform buffer", uniforms, ["Uniform", "CopyDst"])
+    else
+        WGPUCore.writeBuffer(device.queue, grid.uniformBuffer, uniforms)
+    end
+    if grid.bindGroup === nothing || fontBuffers !== grid.fontBuffers
+        grid.fontBuffers = fontBuffers
+        grid.bindGroup = WGPUCore.createBindGroup(
+            "terminal bind group", device,
+            grid.pipeline.bindGroupLayout,
+            getTerminalBindings(fontBuffers, grid.uniformBuffer, grid.cellBuffer)
+        )
+    end
+    return grid
+end
+
+prepare!(grid::TerminalGrid, targetSize::Tuple; kwargs...) = prepare!(grid, orthographic(targetSize...); kwargs...)
+
+function draw!(grid::TerminalGrid, renderPass)
+    grid.bindGroup === nothing && return grid
+    @span "encode" withDebugGroup(renderPass, "terminal grid") do
+        WGPUCore.setPipeline(renderPass, grid.pipeline.pipeline)
+        WGPUCore.setBindGroup(renderPass, 0, grid.bindGroup, UInt32[], 0, 99)
+        WGPUCore.draw(renderPass, 6; instanceCount=length(grid.cells), firstVertex=0, firstInstance=0)
+    end
+    return grid
+end
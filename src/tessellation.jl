@@ -0,0 +1,110 @@
+# Tessellated fallback for adapters without adequate storage buffers, such
+# as downlevel gl backends, and for users who prefer plain triangles.
+# Outlines are flattened to polygons within `RenderOptions.tessellationTolerance`
+# em units, holes bridged into their outer contour and ear clipped, once per
+# glyph when it is first drawn. The triangles go through a pipeline without
+# storage bindings and antialias only by multisampling, so pair it with a
+# sample count of 4.
+#
+# `tessellationAuto` picks the fallback when the device can not bind the
+# curve buffers of font.wgsl, `tessellationOn` and `tessellationOff` force
+# either path. The choice is made once when the renderer is built:
+#
+#     renderer = build(TextRendererBuilder(; sampleCount=4, tessellation=tessellationOn), device)
+#
+# Atlas paths of the path policy are unaffected, tessellated glyphs take the
+# place of the curve glyphs. Scenes, label batches, text pools and instanced
+# text read the curve buffers themselves and throw an `adapterError` when
+# built on a tessellating renderer. Outlines are
+# tessellated here rather than with an external tessellator such as lyon,
+# which has no Julia bindings.
+
+@enum TessellationMode tessellationAuto tessellationOn tessellationOff
+
+const tessellatedShaderSource = embedShader("tessellated.wgsl")
+
+# WebGPU guarantees 8 storage buffers per stage, downlevel limits report none.
+function supportsCurveBuffers(device)
+    hasproperty(device, :supportedLimits) || return true
+    limits = device.supportedLimits.limits
+    return limits.maxStorageBuffersPerShaderStage >= 2 && limits.maxStorageBufferBindingSize >= 1 << 20
+end
+
+function resolveTessellation(options, device)
+    options.tessellation == tessellationAuto || return options
+    supportsCurveBuffers(device) && return setfields(options; tessellation=tessellationOff)
+    @info "Adapter lacks storage buffers for the curve shader, drawing tessellated glyphs"
+    return setfields(options; tessellation=tessellationOn)
+end
+
+# Pipeline kind drawing the curve glyphs of `options`.
+curvesKind(options) = options.tessellation == tessellationOn ? :tessellated : :curves
+
+# The tessellated pipeline binds no curve buffers, so `consumer` could neither
+# share its layouts nor its entry points.
+requireCurvesPath(renderer, consumer) = curvesKind(renderer.options) == :curves || throw(FontRenderError(
+    adapterError, "$consumer reads the curve buffers, the renderer draws tessellated glyphs"
+))
+
+tessellatedPipelineOptions() = (
+    label="tessellated",
+    shaderSource=tessellatedShaderSource,
+    bindingLayouts=getBindingLayouts(FontFace)[1:1],
+)
+
+mutable struct GlyphTessellation
+    tolerance::Float32
+    # triangle corners in em units, y up
+    glyphs::IdDict{FontFace, Dict{FT_UInt, Vector{NTuple{2, Float32}}}}
+    bindGroups::IdDict{Any, Any}    # per uniform buffer
+end
+
+GlyphTessellation(tolerance) =
+    GlyphTessellation(tolerance, IdDict{FontFace, Dict{FT_UInt, Vector{NTuple{2, Float32}}}}(), IdDict{Any, Any}())
+
+# A quadratic deviates |p0 - 2p1 + p2|/(8n^2) from its chords over n steps.
+function flattenContour(contour, tolerance)
+    polygon = NTuple{2, Float32}[]
+    for c in contour
+        (p0, p1, p2) = ((c.x0, c.y0), (c.x1, c.y1), (c.x2, c.y2))
+        bend = sqrt(sum(abs2, p0 .- 2 .* p1 .+ p2))
+        steps = max(1, ceil(Int, sqrt(bend/(8*tolerance))))
+        for i in 0:(steps - 1)
+            t = Float32(i/steps)
+            push!(polygon, (1 - t)^2 .* p0 .+ 2*(1 - t)*t .* p1 .+ t^2 .* p2)
+        end
+    end
+    return polygon
+end
+
+function tessellateGlyph(curves, tolerance)
+    corners = NTuple{2, Float32}[]
+    contours = [curves[range] for range in contourRanges(curves)]
+    isempty(contours) && return corners
+    # counter clockwise outer contours as in glyphTriangles
+    inkLeft = sum(contourArea, contours) >= 0
+    polygons = [inkLeft ? polygon : reverse(polygon) for polygon in (flattenContour(c, tolerance) for c in contours) if length(polygon) >= 3]
+    fillPolygons!(polygons) do a, b, c
+        push!(corners, a, b, c)
+    end
+    return corners
+end
+
+glyphTessellation(tessellation::GlyphTessellation, pg::PositionedGlyph) =
+    get!(get!(Dict{FT_UInt, Vector{NTuple{2, Float32}}}, tessellation.glyphs, pg.font), pg.glyph.index) do
+        @span "tessellate" tessellateGlyph(collect(glyphCurves(pg.font, pg.glyph)), tessellation.tolerance)
+    end
+
+function appendTessellatedVertices!(vertices::Vector{BufferVertex}, indices::Vector{UInt32}, layout::TextLayout, tessellation::GlyphTessellation)
+    for pg in layout.glyphs
+        pg.glyph.curveCount == 0 && continue
+        color = packColor(pg.color)
+        anim = pg.animation
+        for (x, y) in glyphTessellation(tessellation, pg)
+            push!(indices, length(vertices))
+            (px, py) = (pg.x, pg.y) .+ pg.transform*(x*pg.size, -y*pg.size)
+            push!(vertices, BufferVertex(px, py, 0, 0, 0, anim.timeOffset, anim.amplitude, anim.flags, color))
+        end
+    end
+    return (vertices, indices)
+end
@@ -0,0 +1,97 @@
+# Laying text out along polylines and bezier curves, e.g. street labels.
+# Curves are flattened into polylines, glyphs are placed by arc length and
+# rotated to the tangent at their horizontal center.
+
+struct TextPath
+    points::Vector{NTuple{2, Float32}}
+    lengths::Vector{Float32}    # cumulative arc length at every point
+end
+
+function TextPath(points)
+    pts = [Float32.((p[1], p[2])) for p in points]
+    @assert length(pts) >= 2 "A text path needs at least two points"
+    lengths = zeros(Float32, length(pts))
+    for i in 2:length(pts)
+        lengths[i] = lengths[i - 1] + hypot((pts[i] .- pts[i - 1])...)
+    end
+    return TextPath(pts, lengths)
+end
+
+function quadraticPath(p0, p1, p2; segments=32)
+    TextPath(
+        [(1 - t)^2 .* p0 .+ 2*(1 - t)*t .* p1 .+ t^2 .* p2 for t in range(0, 1; length=segments + 1)]
+    )
+end
+
+function cubicPath(p0, p1, p2, p3; segments=32)
+    TextPath(
+        [
+            (1 - t)^3 .* p0 .+ 3*(1 - t)^2*t .* p1 .+ 3*(1 - t)*t^2 .* p2 .+ t^3 .* p3
+            for t in range(0, 1; length=segments + 1)
+        ]
+    )
+end
+
+pathLength(path::TextPath) = path.lengths[end]
+
+# Point and unit tangent at arc length `s`, clamped to the ends of the path.
+function pointAt(path::TextPath, s)
+    (pts, lengths) = (path.points, path.lengths)
+    s = clamp(s, 0, pathLength(path))
+    i = clamp(searchsortedlast(lengths, s), 1, length(pts) - 1)
+    segment = lengths[i + 1] - lengths[i]
+    t = segment > 0 ? (s - lengths[i])/segment : 0f0
+    d = pts[i + 1] .- pts[i]
+    n = hypot(d...)
+    tangent = n > 0 ? d./n : (1f0, 0f0)
+    return (pts[i] .+ t.*d, tangent)
+end
+
+wrapAngle(θ) = mod(θ + π, 2π) - π
+
+"""
+    layoutOnPath(text, style, path; startOffset=0, baselineShift=0)
+
+Places `text` along `path`. Where the path bends towards the glyph tops
+(or bottoms) successive glyphs would overlap, so extra arc length
+proportional to the ascent (or descent) and the turn angle is inserted.
+"""
+function layoutOnPath(text::TextInput, style::TextStyle, path::TextPath; startOffset=0f0, baselineShift=0f0)
+    text = validText(text)
+    style = resolvedStyle(style)
+    font = style.font
+    scale = pixelScale(style)
+    ascent = font.metrics.ascender*scale
+    descent = -font.metrics.descender*scale
+    positioned = PositionedGlyph[]
+    s = Float32(startOffset)
+    previousAngle = nothing
+    (minX, minY, maxX, maxY) = (Inf32, Inf32, -Inf32, -Inf32)
+    for (shaped, size, advance, glyphTransform) in shapeStyled(style, text)
+        glyph = prepareGlyph(font, shaped.index)
+        (_, tangent) = pointAt(path, s + advance/2)
+        angle = atan(tangent[2], tangent[1])
+        if previousAngle !== nothing
+            # y grows downwards: a negative turn bends the path towards the glyph tops
+            turn = wrapAngle(angle - previousAngle)
+            extent = turn < 0 ? ascent : descent
+            s += extent*tan(min(abs(turn), 1.2f0))
+            (_, tangent) = pointAt(path, s + advance/2)
+            angle = atan(tangent[2], tangent[1])
+        end
+        s > pathLength(path) && break
+        (center, _) = pointAt(path, s + advance/2)
+        up = (tangent[2], -tangent[1])
+        pen = center .- tangent.*(advance/2) .+ up.*baselineShift
+        push!(
+            positioned,
+            PositionedGlyph(glyph, font, pen..., size, style.color, shaped.cluster, rotation(angle)*glyphTransform)
+        )
+        (minX, minY) = min.((minX, minY), pen)
+        (maxX, maxY) = max.((maxX, maxY), pen)
+        s += advance
+        previousAngle = angle
+    end
+    isempty(positioned) && return TextLayout(positioned, 0, 0)
+    return TextLayout(positioned, maxX - minX, maxY - minY + ascent + descent)
+end
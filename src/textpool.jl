@@ -0,0 +1,187 @@
+# Short lived text for game HUDs, e.g. damage numbers and pickups.
+#
+#     pool = TextPool(renderer, style; capacity=4096)
+#     spawn!(pool, "-42", (x, y), now; velocity=(0, -80), alpha=(1, 0))
+#     prepare!(pool, surface.size; time=now)
+#     draw!(pool, renderPass)
+#
+# Glyphs live in a fixed ring of instance slots on the gpu, a spawn
+# overwrites the oldest slots with the glyphs of its text and the spawn
+# parameters, and the vertex shader moves, scales and fades them against
+# the frame time until their lifetime is over. Strings are shaped once, as
+# for `LabelBatch`, so spawning allocates nothing and only the slots written
+# since the last frame are uploaded.
+
+@enum TextEasing::UInt32 begin
+    easeLinear = 0
+    easeOut = 1
+    easeIn = 2
+end
+
+# Layout of `TransientInstance` in font.wgsl.
+struct TransientInstance
+    x0::Float32
+    y0::Float32
+    x1::Float32
+    y1::Float32
+    u0::Float32
+    v0::Float32
+    u1::Float32
+    v1::Float32
+    bufferIndex::Int32
+    color::UInt32
+    originX::Float32
+    originY::Float32
+    velocityX::Float32
+    velocityY::Float32
+    spawnTime::Float32
+    lifetime::Float32
+    gravity::Float32
+    scaleStart::Float32
+    scaleEnd::Float32
+    alphaStart::Float32
+    alphaEnd::Float32
+    easing::UInt32
+end
+
+# Free slots never show, their lifetime has passed before they were spawned.
+Base.zero(::Type{TransientInstance}) = TransientInstance(ntuple(_ -> 0, 8)..., -1, 0, ntuple(_ -> 0, 4)..., Inf32, -1, ntuple(_ -> 0, 5)..., 0)
+
+function getVertexBufferLayout(::Type{TransientInstance}; offset=0)
+    attribute(location, format, field) = :attribute => [
+        :format => format,
+        :offset => fieldoffset(TransientInstance, field),
+        :shaderLocation => offset + location
+    ]
+    WGPUCore.GPUVertexBufferLayout => [
+        :arrayStride => sizeof(TransientInstance),
+        :stepMode => "Instance",
+        :attributes => [
+            attribute(0, "Float32x4", 1),
+            attribute(1, "Float32x4", 5),
+            attribute(2, "Sint32", 9),
+            attribute(3, "Unorm8x4", 10),
+            attribute(4, "Float32x4", 11),
+            attribute(5, "Float32x3", 15),
+            attribute(6, "Float32x4", 18),
+            attribute(7, "Uint32", 22),
+        ]
+    ]
+end
+
+mutable struct TextPool
+    renderer::TextRenderer
+    style::TextStyle
+    pipeline::FontPipeline
+    templates::Vector{LabelTemplate}
+    templateIndex::Dict{String, Int32}
+    slots::Vector{TransientInstance}
+    next::Int                   # slot the next glyph goes to
+    # slots written since the last upload, in ring order from `dirtyStart`
+    dirtyStart::Int
+    dirtyCount::Int
+    instanceBuffer
+    uniformBuffer
+    bindGroup
+end
+
+"""
+    TextPool(renderer, style; capacity=4096)
+
+Room for `capacity` glyphs of `style` alive at once, drawn with the pipeline
+layout of `renderer`. Spawns beyond it replace the oldest glyphs.
+"""
+function TextPool(renderer::TextRenderer, style::TextStyle; capacity=4096)
+    requireCurvesPath(renderer, "TextPool")
+    pipeline = rendererPipeline(
+        renderer;
+        layouts=pipelineLayouts(renderer.pipeline),
+        label="text pool",
+        vertexEntryPoint="vs_transient",
+        vertexBuffers=[getVertexBufferLayout(TransientInstance)]
+    )
+    return TextPool(
+        renderer, resolvedStyle(style), pipeline, LabelTemplate[], Dict{String, Int32}(),
+        fill(zero(TransientInstance), max(capacity, 1)), 1, 1, 0, nothing, nothing, nothing
+    )
+end
+
+capacity(pool::TextPool) = length(pool.slots)
+
+"""
+    spawn!(pool, text, position, time; lifetime=1, velocity=(0, 0), gravity=0, scale=(1, 1), alpha=(1, 0), easing=easeOut, color=style.color)
+
+Shows `text` centered on `position` from `time` on for `lifetime` seconds,
+moving by `velocity` pixels per second and pulled down by `gravity`.
+`scale` and `alpha` run from their first to their second value over the
+lifetime along `easing`. Times are seconds of the clock passed to `prepare!`.
+"""
+function spawn!(
+    pool::TextPool, text::AbstractString, position, time;
+    lifetime=1, velocity=(0, 0), gravity=0, scale=(1, 1), alpha=(1, 0), easing::TextEasing=easeOut, color=nothing
+)
+    template = pool.templates[templateFor(pool, text)]
+    packed = packColor(something(color, pool.style.color))
+    (cx, cy) = (template.width/2, template.height/2)
+    for q in template.quads
+        pool.slots[pool.next] = TransientInstance(
+            q.x0 - cx, q.y0 - cy, q.x1 - cx, q.y1 - cy, q.u0, q.v0, q.u1, q.v1, q.bufferIndex, packed,
+            position[1], position[2], velocity[1], velocity[2], time, lifetime, gravity,
+            scale[1], scale[2], alpha[1], alpha[2], UInt32(easing)
+        )
+        pool.dirtyCount == 0 && (pool.dirtyStart = pool.next)
+        pool.dirtyCount = min(pool.dirtyCount + 1, capacity(pool))
+        pool.next = mod1(pool.next + 1, capacity(pool))
+    end
+    return pool
+end
+
+# Uploads the written slots, wrapped runs with two writes.
+function writeSlots!(pool::TextPool)
+    pool.dirtyCount == 0 && return
+    queue = pool.renderer.device.queue
+    stop = pool.dirtyStart + pool.dirtyCount - 1
+    runs = stop <= capacity(pool) ? (pool.dirtyStart:stop,) : (pool.dirtyStart:capacity(pool), 1:(stop - capacity(pool)))
+    for run in runs
+        WGPUCore.writeBuffer(queue, pool.instanceBuffer, pool.slots[run]; bufferOffset=(first(run) - 1)*sizeof(TransientInstance))
+    end
+    pool.dirtyCount = 0
+end
+
+# `time` is the current second of the clock the spawns were timed with.
+function prepare!(pool::TextPool, projection::Projection; time, transform=identityMat4, kwargs...)
+    renderer = pool.renderer
+    device = renderer.device
+    fontBuffers = fontBuffersFor(renderer, pool.style.font)
+    chunkCount(fontBuffers) == 1 || throw(FontRenderError(
+        deviceLimitError,
+        "$(fontLabel(fontBuffers.font)) needs $(chunkCount(fontBuffers)) curve bindings, text pools draw from one"
+    ))
+    if pool.instanceBuffer === nothing
+        (pool.instanceBuffer, _) = WGPUCore.createBufferWithData(device, "text pool instance buffer", pool.slots, ["Vertex", "CopyDst"])
+        pool.dirtyCount = 0
+    else
+        @span "upload" writeSlots!(pool)
+    end
+    uniforms = [FontUniforms(projection; transform=transform, time=time, uniformOptions(renderer)..., kwargs...)]
+    if pool.uniformBuffer === nothing
+        (pool.uniformBuffer, _) = WGPUCore.createBufferWithData(device, "text pool uniform buffer", uniforms, ["Uniform", "CopyDst"])
+    else
+        WGPUCore.writeBuffer(device.queue, pool.uniformBuffer, uniforms)
+    end
+    pool.bindGroup = cachedBindGroup(renderer, fontBuffers, pool.uniformBuffer, 1)
+    return pool
+end
+
+prepare!(pool::TextPool, targetSize::Tuple; kwargs...) = prepare!(pool, orthographic(targetSize...); kwargs...)
+
+function draw!(pool::TextPool, renderPass)
+    pool.bindGroup === nothing && return pool
+    @span "encode" withDebugGroup(renderPass, "text pool") do
+        WGPUCore.setPipeline(renderPass, pool.pipeline.pipeline)
+        WGPUCore.setVertexBuffer(renderPass, 0, pool.instanceBuffer)
+        WGPUCore.setBindGroup(renderPass, 0, pool.bindGroup, UInt32[], 0, 99)
+        WGPUCore.draw(renderPass, 6; instanceCount=capacity(pool), firstVertex=0, firstInstance=0)
+    end
+    return pool
+end
@@ -0,0 +1,682 @@
+# High level renderer owning the pipeline and per font gpu buffers.
+#
+#     builder = TextRendererBuilder()
+#     setFormat!(builder, surface.format)
+#     setSampleCount!(builder, 4)
+#     renderer = build(builder, device)
+#
+# Every frame sections are queued immediate mode style, then a single
+# prepare! uploads all of them and draw! records the draw calls.
+#
+#     queue!(renderer, Section("hello", (16, 16), style))
+#     queue!(renderer, Section("world", (16, 48), style))
+#     prepare!(renderer, surface.size)
+#     draw!(renderer, renderPass)
+
+# How style colors are interpreted. sRGB colors are linearized before they
+# are written into sRGB encoded targets so blending happens in linear space.
+@enum ColorSpace begin
+    colorSpaceSRGB
+    colorSpaceLinear
+end
+
+Base.@kwdef struct RenderOptions
+    format = WGPUCore.WGPUTextureFormat_BGRA8UnormSrgb
+    sampleCount::Int = 1
+    antiAliasingWindowSize::Float32 = 1
+    enableSuperSamplingAntiAliasing::Bool = true
+    antiAliasingMode::AntiAliasingMode = antiAliasingIsotropic
+    colorSpace::ColorSpace = colorSpaceSRGB
+    blendMode::BlendMode = blendPremultiplied
+    # copies of the dynamic buffers, frame n+1 is written while the gpu reads frame n
+    framesInFlight::Int = 2
+    # pixels per em below which text is drawn from the distance field atlas, 0 disables it
+    smallTextThreshold::Float32 = 0
+    # generate multi channel atlas fields on the gpu instead of single channel ones on the cpu
+    msdfAtlas::Bool = false
+    # pixels per em below which text is rasterized into the coverage atlas, 0 disables it
+    rasterTextThreshold::Float32 = 0
+    # atlasRGBA8 also keeps color bitmaps, atlasR8 a quarter of the memory
+    atlasFormat::AtlasFormat = atlasR8
+    # picks the render path of every glyph, see pathpolicy.jl
+    pathPolicy = defaultPathPolicy
+    depthFormat = nothing
+    depthConvention::DepthConvention = standardDepth
+    # draw missing glyphs as boxes with their code point in hex, for every queued section
+    notdefHexBoxes::Bool = false
+    # curve glyphs drawn as a debug view instead of their coverage, see DebugView
+    debugView::DebugView = debugViewNone
+    heatmapScale::Float32 = 64
+    # wgsl shading curve glyphs, see shaderhook.jl
+    fragmentHook::Union{Nothing, String} = nothing
+    # curve glyphs as plain triangles, see tessellation.jl
+    tessellation::TessellationMode = tessellationAuto
+    # em units the flattened outlines may deviate from the curves
+    tessellationTolerance::Float32 = 1f-3
+    # group curve glyphs by font and static labels with the atlas quads of
+    # their kind instead of keeping the queue order, fewer draws but
+    # overlapping text of different fonts then stacks per group
+    batchByFont::Bool = false
+end
+
+mutable struct TextRendererBuilder
+    options::RenderOptions
+    adapter::AdapterOptions
+end
+
+TextRendererBuilder(options::RenderOptions) = TextRendererBuilder(options, AdapterOptions())
+TextRendererBuilder(; kwargs...) = TextRendererBuilder(RenderOptions(; kwargs...))
+
+function setOptions!(builder::TextRendererBuilder; kwargs...)
+    builder.options = setfields(builder.options; kwargs...)
+    return builder
+end
+
+setFormat!(builder::TextRendererBuilder, format) = setOptions!(builder; format=format)
+setSampleCount!(builder::TextRendererBuilder, sampleCount) = setOptions!(builder; sampleCount=sampleCount)
+setColorSpace!(builder::TextRendererBuilder, colorSpace::ColorSpace) = setOptions!(builder; colorSpace=colorSpace)
+setBlendMode!(builder::TextRendererBuilder, blendMode::BlendMode) = setOptions!(builder; blendMode=blendMode)
+setDebugView!(builder::TextRendererBuilder, view::DebugView; heatmapScale=builder.options.heatmapScale) =
+    setOptions!(builder; debugView=view, heatmapScale=heatmapScale)
+setFragmentHook!(builder::TextRendererBuilder, hook) = setOptions!(builder; fragmentHook=hook)
+
+function setAntiAliasing!(
+        builder::TextRendererBuilder;
+        windowSize=builder.options.antiAliasingWindowSize,
+        superSampling=builder.options.enableSuperSamplingAntiAliasing,
+        mode=builder.options.antiAliasingMode
+    )
+    setOptions!(
+        builder;
+        antiAliasingWindowSize=windowSize,
+        enableSuperSamplingAntiAliasing=superSampling,
+        antiAliasingMode=mode
+    )
+end
+
+setDepth!(builder::TextRendererBuilder, depthFormat; convention=standardDepth) =
+    setOptions!(builder; depthFormat=depthFormat, depthConvention=convention)
+
+function setAdapter!(builder::TextRendererBuilder; kwargs...)
+    builder.adapter = setfields(builder.adapter; kwargs...)
+    return builder
+end
+
+struct Section
+    text::String
+    position::NTuple{2, Float32}
+    style::TextStyle
+    # scaled by the user, e.g. a zoomable canvas, always drawn from curves
+    zoomable::Bool
+    # lines wrap at this many pixels and align within them
+    maxWidth::Float32
+    align::TextAlign
+    # drawn behind the glyphs, see halo.jl
+    halo::Union{Nothing, Halo}
+    # recolored ranges of the text, see highlight.jl
+    highlights::Vector{HighlightSpan}
+end
+
+Section(text, position, style; zoomable=false, maxWidth=Inf32, align=alignLeft, halo=nothing, highlights=HighlightSpan[]) =
+    Section(validText(text), position, style, zoomable, maxWidth, align, halo, [HighlightSpan(span) for span in highlights])
+
+# Sections sharing a font and curve chunk are merged into one draw call, with
+# `RenderOptions.batchByFont` also when other fonts were queued in between.
+struct DrawRange
+    font::FontFace
+    chunk::Int
+    bindGroup
+    firstIndex::Int
+    indexCount::Int
+end
+
+# One draw call per atlas kind, all atlas quads share one vertex and index buffer.
+# Atlas draws always follow every curve range, static labels first, then the
+# kinds in the order they were first queued and atlas debug pages last. With
+# `RenderOptions.batchByFont` draws of a kind move next to its first draw.
+struct AtlasDraw
+    kind::Symbol
+    bindGroup
+    firstIndex::Int
+    indexCount::Int
+end
+
+# Draws of the last prepared frame, and the curve draws the queue order
+# alone would have taken, see `RenderOptions.batchByFont`.
+mutable struct DrawStats
+    drawCalls::Int
+    pipelineSwitches::Int
+    unbatchedCurveDraws::Int
+end
+
+DrawStats() = DrawStats(0, 0, 0)
+
+function reset!(stats::DrawStats)
+    (stats.drawCalls, stats.pipelineSwitches, stats.unbatchedCurveDraws) = (0, 0, 0)
+    return stats
+end
+
+function Base.show(io::IO, stats::DrawStats)
+    print(io, "DrawStats(drawCalls=$(stats.drawCalls), pipelineSwitches=$(stats.pipelineSwitches), unbatchedCurveDraws=$(stats.unbatchedCurveDraws))")
+end
+
+# Anything that queues sections and owns prepared draws, either a renderer
+# itself or a `SurfaceView` sharing a renderer's fonts and pipelines.
+abstract type TextTarget end
+
+# Dynamic buffers of one frame in flight, kept across frames and only grown.
+mutable struct FrameBuffers
+    vertexBuffer
+    vertexCapacity::Int
+    indexBuffer
+    indexCapacity::Int
+    uniformBuffer
+    # quads of the distance field atlas path
+    atlasVertexBuffer
+    atlasVertexCapacity::Int
+    atlasIndexBuffer
+    atlasIndexCapacity::Int
+end
+
+FrameBuffers() = FrameBuffers(nothing, 0, nothing, 0, nothing, nothing, 0, nothing, 0)
+
+frameRing(options::RenderOptions) = [FrameBuffers() for _ in 1:max(options.framesInFlight, 1)]
+
+# Capacities grow in powers of two, so text changing every frame rarely reallocates.
+function writeDynamic(device, buffer, capacity, data, label, usage)
+    size = sizeof(data)
+    if buffer === nothing || capacity < size
+        capacity = max(nextpow(2, size), 256)
+        buffer = WGPUCore.createBuffer(label, device, capacity, usage, false)
+    end
+    WGPUCore.writeBuffer(device.queue, buffer, data)
+    return (buffer, capacity)
+end
+
+# Empty geometry keeps the previous buffers, only the uniforms are always written.
+function writeFrame!(device, frame::FrameBuffers, vertices, indices, uniforms)
+    if !isempty(indices)
+        (frame.vertexBuffer, frame.vertexCapacity) = writeDynamic(
+            device, frame.vertexBuffer, frame.vertexCapacity, vertices, "text vertex buffer", ["Vertex", "CopyDst"]
+        )
+        (frame.indexBuffer, frame.indexCapacity) = writeDynamic(
+            device, frame.indexBuffer, frame.indexCapacity, indices, "text index buffer", ["Index", "CopyDst"]
+        )
+    end
+    (frame.uniformBuffer, _) = writeDynamic(
+        device, frame.uniformBuffer, sizeof(FontUniforms), uniforms, "text uniform buffer", ["Uniform", "CopyDst"]
+    )
+    return frame
+end
+
+function writeAtlasFrame!(device, frame::FrameBuffers, vertices, indices)
+    (frame.atlasVertexBuffer, frame.atlasVertexCapacity) = writeDynamic(
+        device, frame.atlasVertexBuffer, frame.atlasVertexCapacity, vertices, "text atlas vertex buffer", ["Vertex", "CopyDst"]
+    )
+    (frame.atlasIndexBuffer, frame.atlasIndexCapacity) = writeDynamic(
+        device, frame.atlasIndexBuffer, frame.atlasIndexCapacity, indices, "text atlas index buffer", ["Index", "CopyDst"]
+    )
+    return frame
+end
+
+mutable struct TextRenderer <: TextTarget
+    device
+    options::RenderOptions
+    pipeline::FontPipeline
+    # variants keyed by (kind, format, sampleCount, blendMode, debugView, fragmentHook, depthFormat,
+    # depthConvention), variants of a kind share one bind group layout
+    pipelines::Dict{Tuple{Symbol, Any, Int, BlendMode, DebugView, Union{Nothing, String}, Any, DepthConvention}, FontPipeline}
+    fontBuffers::IdDict{FontFace, FontBuffers}
+    # keyed by (fontBuffers, uniformBuffer, chunk), entries of replaced font buffers are dropped
+    bindGroups::Dict{Tuple{FontBuffers, Any, Int}, Any}
+    sections::Vector{Section}
+    # sections drawn from the label cache with their scale
+    staticLabels::Vector{Tuple{Section, Float32}}
+    # atlas pages queued by `queueAtlasDebug!`, (kind, layer, x, y, size)
+    debugPages::Vector{Tuple{Symbol, Int, Float32, Float32, Float32}}
+    frames::Vector{FrameBuffers}
+    frame::Int                  # slot written by the last prepare!
+    ranges::Vector{DrawRange}
+    atlasDraws::Vector{AtlasDraw}
+    pathStats::PathStats
+    drawStats::DrawStats
+    atlas::Union{Nothing, SDFAtlas}
+    glyphAtlas::Union{Nothing, GlyphAtlas}
+    tessellation::Union{Nothing, GlyphTessellation}
+    labelCache::LabelCache
+end
+
+# `:curves` is the analytic path, `:tessellated` its triangle fallback, `:sdf`
+# and `:msdf` the distance field atlas paths and `:alpha` and `:color` the
+# glyph atlas paths.
+pipelineKey(options::RenderOptions; kind=:curves) =
+    (kind, options.format, options.sampleCount, options.blendMode, options.debugView, options.fragmentHook, options.depthFormat, options.depthConvention)
+
+const debugViewEntryPoints = Dict(debugViewHeatmap => "fs_heatmap", debugViewQuads => "fs_quads", debugViewOverdraw => "fs_overdraw")
+
+# Debug views win over fragment hooks.
+function curvesPipelineOptions(device, options::RenderOptions, layouts)
+    options.debugView == debugViewNone || return (layouts=layouts, fragmentEntryPoint=debugViewEntryPoints[options.debugView])
+    options.fragmentHook === nothing && return (layouts=layouts,)
+    source = hookedShaderSource(options.fragmentHook)
+    # the hook needs its own module, bind groups stay valid with the shared layouts
+    layouts === nothing || (layouts = (compileShader(device, "fragment hook shader", source), layouts[2], layouts[3]))
+    return (layouts=layouts, shaderSource=source, fragmentEntryPoint="fs_hooked")
+end
+
+function createPipelineVariant(device, options::RenderOptions; kind=:curves, layouts=nothing)
+    createFontPipeline(
+        device, options.format;
+        sampleCount=options.sampleCount,
+        # overdraw counts quads by adding them up
+        blendMode=kind == :curves && options.debugView == debugViewOverdraw ? blendAdditive : options.blendMode,
+        depthFormat=options.depthFormat,
+        depthConvention=options.depthConvention,
+        (
+            kind == :curves ? curvesPipelineOptions(device, options, layouts) :
+            kind == :tessellated ? (layouts=layouts, tessellatedPipelineOptions()...) :
+            (layouts=layouts, atlasPipelineOptions(kind)...)
+        )...
+    )
+end
+
+function build(builder::TextRendererBuilder, device)
+    options = resolveTessellation(builder.options, device)
+    pipeline = createPipelineVariant(device, options; kind=curvesKind(options))
+    return TextRenderer(
+        device, options, pipeline,
+        Dict{Tuple{Symbol, Any, Int, BlendMode, DebugView, Union{Nothing, String}, Any, DepthConvention}, FontPipeline}(pipelineKey(options; kind=curvesKind(options)) => pipeline),
+        IdDict{FontFace, FontBuffers}(),
+        Dict{Tuple{FontBuffers, Any, Int}, Any}(),
+        Section[],
+        Tuple{Section, Float32}[],
+        Tuple{Symbol, Int, Float32, Float32, Float32}[],
+        frameRing(options), 0,
+        DrawRange[],
+        AtlasDraw[],
+        PathStats(),
+        DrawStats(),
+        nothing,
+        nothing,
+        nothing,
+        LabelCache()
+    )
+end
+
+# Requests a device following the adapter options of the builder.
+build(builder::TextRendererBuilder; canvas=nothing) =
+    build(builder, requestRenderDevice(builder.adapter; canvas=canvas))
+
+const srgbFormats = (
+    WGPUCore.WGPUTextureFormat_RGBA8UnormSrgb,
+    WGPUCore.WGPUTextureFormat_BGRA8UnormSrgb,
+)
+
+linearizeColors(options::RenderOptions) =
+    options.colorSpace == colorSpaceSRGB && options.format in srgbFormats
+
+sharedRenderer(renderer::TextRenderer) = renderer
+
+function uniformOptions(target::TextTarget)
+    options = target.options
+    (
+        antiAliasingWindowSize=options.antiAliasingWindowSize,
+        enableSuperSamplingAntiAliasing=options.enableSuperSamplingAntiAliasing,
+        antiAliasingMode=options.antiAliasingMode,
+        linearizeColors=linearizeColors(options),
+        heatmapScale=options.heatmapScale,
+    )
+end
+
+# Buffers are uploaded on first use and refreshed whenever a font grew.
+function fontBuffersFor(renderer::TextRenderer, font::FontFace)
+    previous = get(renderer.fontBuffers, font, nothing)
+    fontBuffers = previous === nothing ?
+        uploadFont(renderer.device, font) :
+        updateFont(renderer.device, previous)
+    if previous !== nothing && fontBuffers !== previous
+        filter!(((key, _),) -> key[1] !== previous, renderer.bindGroups)
+    end
+    renderer.fontBuffers[font] = fontBuffers
+    return fontBuffers
+end
+
+function pathKind(target::TextTarget, path::RenderPath)
+    path == pathCurves && return curvesKind(target.options)
+    path == pathBitmap && return atlasKind(target.options.atlasFormat)
+    return atlasKind(sharedRenderer(target))
+end
+
+# Splits a section into one layout per pipeline kind following the path policy.
+function splitByPath(target::TextTarget, layout::TextLayout, zoomable)
+    options = target.options
+    kinds = Pair{Symbol, Vector{PositionedGlyph}}[]
+    for pg in layout.glyphs
+        pixelSize = effectivePixelSize(pg)
+        path = options.pathPolicy(options, pg, pixelSize, zoomable)
+        record!(target.pathStats, path, pixelSize)
+        pushGrouped!(kinds, pathKind(target, path), pg)
+    end
+    length(kinds) == 1 && return [(only(kinds).first, layout)]
+    return [(kind, TextLayout(glyphs, layout.width, layout.height)) for (kind, glyphs) in kinds]
+end
+
+# Adds `item` to the group of `key`, groups keep the order their keys first appeared in.
+function pushGrouped!(groups::Vector{<:Pair}, key, item)
+    index = findfirst(group -> group.first == key, groups)
+    index === nothing ? push!(groups, key => [item]) : push!(groups[index].second, item)
+    return groups
+end
+
+pathStats(target::TextTarget) = target.pathStats
+drawStats(target::TextTarget) = target.drawStats
+
+function atlasFor(renderer::TextRenderer)
+    renderer.atlas === nothing && (renderer.atlas = SDFAtlas(renderer.device; msdf=renderer.options.msdfAtlas))
+    return renderer.atlas
+end
+
+function glyphAtlasFor(renderer::TextRenderer)
+    renderer.glyphAtlas === nothing && (renderer.glyphAtlas = GlyphAtlas(renderer.device; format=renderer.options.atlasFormat))
+    return renderer.glyphAtlas
+end
+
+atlasKind(renderer::TextRenderer) = renderer.options.msdfAtlas ? :msdf : :sdf
+
+function tessellationFor(renderer::TextRenderer)
+    renderer.tessellation === nothing && (renderer.tessellation = GlyphTessellation(renderer.options.tessellationTolerance))
+    return renderer.tessellation
+end
+
+function tessellationBindGroup(target::TextTarget, uniformBuffer)
+    renderer = sharedRenderer(target)
+    get!(tessellationFor(renderer).bindGroups, uniformBuffer) do
+        WGPUCore.createBindGroup(
+            "text tessellated bind group", renderer.device,
+            pipelineVariant(renderer, target.options; kind=:tessellated).bindGroupLayout,
+            [WGPUCore.GPUBuffer => [:binding => 0, :buffer => uniformBuffer, :offset => 0, :size => uniformBuffer.size]]
+        )
+    end
+end
+
+atlasFor(renderer::TextRenderer, kind::Symbol) = kind in (:alpha, :color) ? glyphAtlasFor(renderer) : atlasFor(renderer)
+
+function flushAtlas!(renderer::TextRenderer, kind::Symbol)
+    atlas = atlasFor(renderer, kind)
+    kind == :msdf ? generateMSDF!(renderer, atlas) : flush!(atlas)
+end
+
+function atlasBindGroup(target::TextTarget, kind::Symbol, uniformBuffer)
+    renderer = sharedRenderer(target)
+    atlas = atlasFor(renderer, kind)
+    get!(atlas.bindGroups, uniformBuffer) do
+        WGPUCore.createBindGroup(
+            "text atlas bind group", renderer.device,
+            pipelineVariant(renderer, target.options; kind=kind).bindGroupLayout,
+            getAtlasBindings(atlas, uniformBuffer)
+        )
+    end
+end
+
+# Bind groups are costly to create on some backends, uniform buffers of the
+# frame ring persist, so steady state frames reuse every bind group.
+function cachedBindGroup(renderer::TextRenderer, fontBuffers::FontBuffers, uniformBuffer, chunk)
+    get!(renderer.bindGroups, (fontBuffers, uniformBuffer, chunk)) do
+        WGPUCore.createBindGroup(
+            "text bind group", renderer.device,
+            renderer.pipeline.bindGroupLayout,
+            getBindings(fontBuffers, uniformBuffer; chunk=chunk)
+        )
+    end
+end
+
+# `section` with the styling `options` force on all text.
+function optionSection(options::RenderOptions, section::Section)
+    options.notdefHexBoxes && !section.style.notdefHexBoxes || return section
+    return setfields(section; style=setfields(section.style; notdefHexBoxes=true))
+end
+
+queue!(target::TextTarget, section::Section) =
+    (push!(target.sections, optionSection(target.options, section)); target)
+queue!(target::TextTarget, text::TextInput, position, style::TextStyle) =
+    queue!(target, Section(text, position, style))
+
+# Text that stays the same over many frames, rendered once and drawn as a quad.
+# `scale` is the dpi scale of the target, layout pixels map to `scale` texels.
+queueStatic!(target::TextTarget, section::Section; scale=1) =
+    (push!(target.staticLabels, (optionSection(target.options, section), Float32(scale))); target)
+queueStatic!(target::TextTarget, text::TextInput, position, style::TextStyle; kwargs...) =
+    queueStatic!(target, Section(text, position, style); kwargs...)
+
+layoutSection(section::Section) =
+    @span "layout" layoutText(section.text, section.style; origin=section.position, maxWidth=section.maxWidth, align=section.align)
+
+# Halos build glyphs of grown faces, so they are added once the workers of
+# `mapLayouts` finished.
+function finishLayout(section::Section, layout::TextLayout)
+    isempty(section.highlights) || (layout = highlightLayout(layout, section.highlights))
+    return section.halo === nothing ? layout : haloLayout(layout, section.halo)
+end
+
+layoutSections(sections) =
+    map(finishLayout, sections, mapLayouts(layoutSection, sections, section -> (section.style, section.text)))
+
+# Lays out every queued section into one vertex and one index buffer.
+function prepare!(target::TextTarget, projection::Projection; transform=identityMat4, kwargs...)
+    renderer = sharedRenderer(target)
+    vertices = BufferVertex[]
+    indices = UInt32[]
+    atlasLayouts = Pair{Symbol, Vector{TextLayout}}[]
+    pending = Tuple{FontFace, Int, Int, Int}[]
+    curveLayouts = Tuple{FontFace, Int, TextLayout}[]
+    # fonts are uploaded after all sections built their glyphs
+    layouts = layoutSections(target.sections)
+    reset!(target.pathStats)
+    reset!(target.drawStats)
+    for (section, sectionLayout) in zip(target.sections, layouts), (kind, layout) in splitByPath(target, sectionLayout, section.zoomable)
+        if kind != :curves
+            pushGrouped!(atlasLayouts, kind, layout)
+            continue
+        end
+        for (font, fontLayout) in splitByFont(layout), (chunk, chunkLayout) in splitByChunk(fontLayout, fontBuffersFor(renderer, font))
+            any(pg -> pg.glyph.curveCount > 0, chunkLayout.glyphs) && push!(curveLayouts, (font, chunk, chunkLayout))
+        end
+    end
+    target.drawStats.unbatchedCurveDraws = count(eachindex(curveLayouts)) do i
+        i == 1 || curveLayouts[i - 1][1] !== curveLayouts[i][1] || curveLayouts[i - 1][2] != curveLayouts[i][2]
+    end
+    target.options.batchByFont && sortByFirstAppearance!(curveLayouts, ((font, chunk, _),) -> (font, chunk))
+    for (font, chunk, chunkLayout) in curveLayouts
+        first = length(indices)
+        appendVertices!(vertices, indices, chunkLayout)
+        added = length(indices) - first
+        if !isempty(pending) && pending[end][1] === font && pending[end][2] == chunk
+            (_, _, start, previous) = pending[end]
+            pending[end] = (font, chunk, start, previous + added)
+        else
+            push!(pending, (font, chunk, first, added))
+        end
+    end
+    empty!(target.sections)
+    empty!(target.ranges)
+    empty!(target.atlasDraws)
+    if isempty(indices) && isempty(atlasLayouts) && isempty(target.staticLabels) && isempty(target.debugPages)
+        return target
+    end
+
+    device = renderer.device
+    target.frame = mod1(target.frame + 1, length(target.frames))
+    frame = currentFrame(target)
+    uniforms = [FontUniforms(projection; transform=transform, uniformOptions(target)..., kwargs...)]
+    @span "upload" writeFrame!(device, frame, vertices, indices, uniforms)
+    if !isempty(atlasLayouts) || !isempty(target.staticLabels) || !isempty(target.debugPages)
+        atlasVertices = BufferVertex[]
+        atlasIndices = UInt32[]
+        cache = renderer.labelCache
+        isempty(target.staticLabels) || (cache.frame += 1)
+        for (section, scale) in target.staticLabels
+            label = cachedLabel(target, section, scale)
+            first = length(atlasIndices)
+            appendLabelQuad!(atlasVertices, atlasIndices, label, section.position)
+            push!(target.atlasDraws, AtlasDraw(:color, labelBindGroup(target, label, frame.uniformBuffer), first, 6))
+        end
+        empty!(target.staticLabels)
+        dropStaleLabels!(cache)
+        for (kind, kindLayouts) in atlasLayouts
+            if kind == :tessellated
+                first = length(atlasIndices)
+                for layout in kindLayouts
+                    appendTessellatedVertices!(atlasVertices, atlasIndices, layout, tessellationFor(renderer))
+                end
+                push!(target.atlasDraws, AtlasDraw(
+                    kind, tessellationBindGroup(target, frame.uniformBuffer), first, length(atlasIndices) - first
+                ))
+                continue
+            end
+            atlas = atlasFor(renderer, kind)
+            atlas.frame += 1
+            first = length(atlasIndices)
+            for layout in kindLayouts
+                appendAtlasVertices!(atlasVertices, atlasIndices, layout, atlas)
+            end
+            flushAtlas!(renderer, kind)
+            push!(target.atlasDraws, AtlasDraw(
+                kind, atlasBindGroup(target, kind, frame.uniformBuffer), first, length(atlasIndices) - first
+            ))
+        end
+        appendDebugPages!(target, atlasVertices, atlasIndices, frame.uniformBuffer)
+        @span "upload" writeAtlasFrame!(device, frame, atlasVertices, atlasIndices)
+        # draws keep their index ranges, so reordering them only changes the stacking
+        target.options.batchByFont && sortByFirstAppearance!(target.atlasDraws, draw -> draw.kind)
+    end
+    for (font, chunk, first, indexCount) in pending
+        bindGroup = cachedBindGroup(renderer, renderer.fontBuffers[font], frame.uniformBuffer, chunk)
+        push!(target.ranges, DrawRange(font, chunk, bindGroup, first, indexCount))
+    end
+    stats = target.drawStats
+    stats.drawCalls = length(target.ranges) + length(target.atlasDraws)
+    stats.pipelineSwitches = !isempty(target.ranges) + count(eachindex(target.atlasDraws)) do i
+        i == 1 || target.atlasDraws[i - 1].kind != target.atlasDraws[i].kind
+    end
+    return target
+end
+
+# Stable sort grouping equal keys where the first of them was.
+function sortByFirstAppearance!(items, key)
+    order = Dict{Any, Int}()
+    for item in items
+        get!(order, key(item), length(order))
+    end
+    return sort!(items; by=item -> order[key(item)], alg=MergeSort)
+end
+
+currentFrame(target::TextTarget) = target.frames[target.frame]
+
+prepare!(target::TextTarget, targetSize::Tuple; kwargs...) =
+    prepare!(target, orthographic(targetSize...); kwargs...)
+
+# Records the prepared draw calls into `renderPass`.
+function draw!(target::TextTarget, renderPass)
+    isempty(target.ranges) && isempty(target.atlasDraws) && return target
+    @span "encode" withDebugGroup(renderPass, "text renderer") do
+        frame = currentFrame(target)
+        if !isempty(target.ranges)
+            WGPUCore.setPipeline(renderPass, target.pipeline.pipeline)
+            WGPUCore.setIndexBuffer(renderPass, frame.indexBuffer, "Uint32")
+            WGPUCore.setVertexBuffer(renderPass, 0, frame.vertexBuffer)
+            for range in target.ranges
+                WGPUCore.setBindGroup(renderPass, 0, range.bindGroup, UInt32[], 0, 99)
+                WGPUCore.drawIndexed(
+                    renderPass, range.indexCount;
+                    instanceCount=1, firstIndex=range.firstIndex, baseVertex=0, firstInstance=0
+                )
+            end
+        end
+        isempty(target.atlasDraws) || drawAtlas(renderPass, target, frame)
+    end
+    return target
+end
+
+function drawAtlas(renderPass, target::TextTarget, frame::FrameBuffers)
+    renderer = sharedRenderer(target)
+    WGPUCore.setIndexBuffer(renderPass, frame.atlasIndexBuffer, "Uint32")
+    WGPUCore.setVertexBuffer(renderPass, 0, frame.atlasVertexBuffer)
+    kind = nothing
+    for draw in target.atlasDraws
+        draw.kind === kind || WGPUCore.setPipeline(renderPass, pipelineVariant(renderer, target.options; kind=draw.kind).pipeline)
+        kind = draw.kind
+        WGPUCore.setBindGroup(renderPass, 0, draw.bindGroup, UInt32[], 0, 99)
+        WGPUCore.drawIndexed(
+            renderPass, draw.indexCount;
+            instanceCount=1, firstIndex=draw.firstIndex, baseVertex=0, firstInstance=0
+        )
+    end
+end
+
+# Escape hatches for binding the font data into custom shaders. The layouts
+# match group 0 of font.wgsl, `getShaderCode()` provides `computeCoverage`
+# and the struct declarations to copy from.
+bindGroupLayout(renderer::TextRenderer) = renderer.pipeline.bindGroupLayout
+renderPipeline(renderer::TextRenderer) = renderer.pipeline.pipeline
+pipelineLayout(renderer::TextRenderer) = renderer.pipeline.pipelineLayout
+
+# Uploads `font` if needed, so the buffers are valid until the font grows again.
+fontBuffers(renderer::TextRenderer, font::FontFace) = fontBuffersFor(renderer, font)
+glyphBuffer(fontBuffers::FontBuffers) = fontBuffers.glyphBuffer
+curveBuffer(fontBuffers::FontBuffers, chunk=1) = fontBuffers.curveBuffers[chunk]
+
+# Device loss recovery. Curve data stays cached on the cpu side of every
+# `FontFace`, so after a reset only the pipeline is rebuilt and font buffers
+# are uploaded again lazily on the next prepare!.
+function recreate!(renderer::TextRenderer, device)
+    options = renderer.options
+    renderer.device = device
+    renderer.pipeline = createPipelineVariant(device, options; kind=curvesKind(options))
+    empty!(renderer.pipelines)
+    renderer.pipelines[pipelineKey(options; kind=curvesKind(options))] = renderer.pipeline
+    empty!(renderer.fontBuffers)
+    empty!(renderer.bindGroups)
+    empty!(renderer.ranges)
+    renderer.frames = frameRing(options)
+    renderer.frame = 0
+    empty!(renderer.atlasDraws)
+    # glyph fields and bitmaps are generated again for the new device
+    renderer.atlas = nothing
+    renderer.glyphAtlas = nothing
+    renderer.tessellation = nothing
+    renderer.labelCache = LabelCache()
+    return renderer
+end
+
+# Follows swapchain format or sample count changes, e.g. after the window moved
+# to another monitor. Variants are built on first use and kept for switching back.
+function reconfigure!(target::TextTarget; kwargs...)
+    target.options = setfields(target.options; kwargs...)
+    target.pipeline = pipelineVariant(sharedRenderer(target), target.options; kind=curvesKind(target.options))
+    return target
+end
+
+function pipelineVariant(renderer::TextRenderer, options::RenderOptions; kind=:curves)
+    get!(renderer.pipelines, pipelineKey(options; kind=kind)) do
+        donors = [p for (key, p) in renderer.pipelines if key[1] == kind]
+        layouts = isempty(donors) ? nothing : pipelineLayouts(donors[1])
+        createPipelineVariant(renderer.device, options; kind=kind, layouts=layouts)
+    end
+end
+
+pipelineLayouts(pipeline) = (pipeline.shader, pipeline.bindGroupLayout, pipeline.pipelineLayout)
+
+# Pipelines of the batching and overlay types draw into the targets of
+# `renderer` with its samples, blending and depth state; `kwargs` pick their
+# shader, bindings and vertex buffers and may override the rest.
+function rendererPipeline(renderer::TextRenderer; kwargs...)
+    options = renderer.options
+    return createFontPipeline(
+        renderer.device, options.format;
+        sampleCount=options.sampleCount,
+        blendMode=options.blendMode,
+        depthFormat=options.depthFormat,
+        depthConvention=options.depthConvention,
+        kwargs...
+    )
+end
@@ -0,0 +1,69 @@
+# Total fit line breaking after Knuth and Plass.
+# Glyphs are boxes, word separators glue stretching by half and shrinking by
+# a third of their width, soft hyphens flagged penalties. The breakpoints
+# minimize the demerits summed over the whole paragraph instead of filling
+# each line as far as it goes, so loose and tight lines even out. Words
+# longer than the measure still get a line of their own, at the worst badness.
+
+@enum LineBreaker greedyBreaking optimalBreaking
+
+const glueStretch = 0.5f0
+const glueShrink = 1/3f0
+const hyphenPenalty = 50
+# two hyphenated lines in a row, and lines of fitness classes apart
+const flaggedDemerits = 3000
+const fitnessDemerits = 3000
+const maxBadness = 10_000f0
+
+# Tight, normal, loose and very loose lines by adjustment ratio.
+fitnessClass(r) = r < -0.5f0 ? 0 : r <= 0.5f0 ? 1 : r <= 1 ? 2 : 3
+
+# Starts of the lines after the first, paired with true where the break is
+# mandatory, for `n` glyphs with the break opportunities `breaks` of
+# `wrapLines`. The closures give pixel advances, spaces dropped at line
+# ends, glue, and the extra width of a hyphen where a line breaks after `k`.
+function optimalBreaks(n, breaks::Dict{Int, Bool}, maxWidth; advanceOf, isSpace, isGlue, hyphenAdvance)
+    starts = [1; sort!([k for k in keys(breaks) if 1 < k <= n]); n + 1]
+    widths = cumsum([0f0; [advanceOf(k) for k in 1:n]])
+    stretches = cumsum([0f0; [isGlue(k) ? glueStretch*advanceOf(k) : 0f0 for k in 1:n]])
+    shrinks = cumsum([0f0; [isGlue(k) ? glueShrink*advanceOf(k) : 0f0 for k in 1:n]])
+    visibleEnd = accumulate((last, k) -> isSpace(k) ? last : k, 1:n; init=0)
+    hyphenated(k) = 1 < k <= n && hyphenAdvance(k - 1) > 0
+
+    total = fill(Inf32, length(starts))
+    previous = zeros(Int, length(starts))
+    fitness = ones(Int, length(starts))
+    total[1] = 0
+    for j in 2:length(starts)
+        b = starts[j]
+        # the last line and lines ended by a mandatory break are set at their natural width
+        ragged = b == n + 1 || breaks[b]
+        for i in (j - 1):-1:1
+            a = starts[i]
+            stop = b > 1 ? max(visibleEnd[b - 1], a - 1) : 0
+            natural = widths[stop + 1] - widths[a] + (b <= n ? hyphenAdvance(b - 1) : 0f0)
+            slack = maxWidth - natural
+            give = slack >= 0 ? stretches[stop + 1] - stretches[a] : shrinks[stop + 1] - shrinks[a]
+            r = slack >= 0 && ragged ? 0f0 : give > 0 ? slack/give : copysign(Inf32, slack)
+            # earlier starts only make the line longer
+            r < -1 && i < j - 1 && break
+            badness = r < -1 || !isfinite(r) ? maxBadness : min(100*abs(r)^3, maxBadness)
+            demerits = (1 + badness + (hyphenated(b) ? hyphenPenalty : 0))^2
+            hyphenated(b) && hyphenated(a) && (demerits += flaggedDemerits)
+            class = fitnessClass(clamp(r, -1f0, 2f0))
+            abs(class - fitness[i]) > 1 && (demerits += fitnessDemerits)
+            if total[i] + demerits < total[j]
+                (total[j], previous[j], fitness[j]) = (total[i] + demerits, i, class)
+            end
+            i > 1 && breaks[a] && break
+        end
+    end
+
+    result = Tuple{Int, Bool}[]
+    j = previous[end]
+    while j > 1
+        push!(result, (starts[j], breaks[starts[j]]))
+        j = previous[j]
+    end
+    return reverse!(result)
+end
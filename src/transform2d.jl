@@ -0,0 +1,42 @@
+# 2x3 affine transforms in pixel space, y grows downwards.
+#   | a  c  tx |
+#   | b  d  ty |
+
+struct Affine2
+    a::Float32
+    b::Float32
+    c::Float32
+    d::Float32
+    tx::Float32
+    ty::Float32
+end
+
+const identityAffine2 = Affine2(1, 0, 0, 1, 0, 0)
+
+Base.:*(m::Affine2, n::Affine2) = Affine2(
+    m.a*n.a + m.c*n.b,
+    m.b*n.a + m.d*n.b,
+    m.a*n.c + m.c*n.d,
+    m.b*n.c + m.d*n.d,
+    m.a*n.tx + m.c*n.ty + m.tx,
+    m.b*n.tx + m.d*n.ty + m.ty,
+)
+
+Base.:*(m::Affine2, (x, y)::Tuple{Real, Real}) = (m.a*x + m.c*y + m.tx, m.b*x + m.d*y + m.ty)
+
+function Base.inv(m::Affine2)
+    det = m.a*m.d - m.b*m.c
+    (a, b, c, d) = (m.d/det, -m.b/det, -m.c/det, m.a/det)
+    return Affine2(a, b, c, d, -(a*m.tx + c*m.ty), -(b*m.tx + d*m.ty))
+end
+
+linearPart(m::Affine2) = Affine2(m.a, m.b, m.c, m.d, 0, 0)
+
+translation(tx, ty) = Affine2(1, 0, 0, 1, tx, ty)
+scaling(sx, sy=sx) = Affine2(sx, 0, 0, sy, 0, 0)
+# positive angles turn clockwise on screen since y points down
+rotation(θ) = Affine2(cos(θ), sin(θ), -sin(θ), cos(θ), 0, 0)
+skewing(αx, αy=0) = Affine2(1, tan(αy), tan(αx), 1, 0, 0)
+
+# Applies `m` around `(x, y)` instead of the origin.
+about(m::Affine2, (x, y)) = translation(x, y)*m*translation(-x, -y)
@@ -0,0 +1,105 @@
+# Vertical writing for Japanese and Chinese, css vertical-rl.
+# Columns run top to bottom and stack from right to left. Ideographs, kana
+# and fullwidth forms stand upright in em cells advancing by the vmtx
+# heights of the face, the vert alternates turn brackets and dashes and move
+# small kana and 、。 to the upper right of their cells. Faces without those
+# alternates get brackets and dashes rotated and the small forms shifted
+# instead. Latin and other horizontal scripts run sideways, rotated a
+# quarter turn clockwise on the middle of the column.
+
+# Upright characters of UAX #50, everything else runs sideways.
+function isUpright(chr::Char)
+    c = UInt32(chr)
+    return 0x1100 <= c <= 0x11ff || 0x2e80 <= c <= 0xa4cf || 0xa960 <= c <= 0xa97f ||
+        0xac00 <= c <= 0xd7ff || 0xf900 <= c <= 0xfaff || 0xfe10 <= c <= 0xfe1f ||
+        0xfe30 <= c <= 0xfe4f || 0xff01 <= c <= 0xffef || 0x1f000 <= c <= 0x1faff ||
+        0x20000 <= c <= 0x3fffd || c in (0xa7, 0xa9, 0xae, 0xb1, 0xbc, 0xbd, 0xbe, 0xd7, 0xf7)
+end
+
+# Upright characters that turn with the line (UAX #50 Tr), rotated where
+# the face has no vertical alternate for them.
+const turnedInVertical = Set("〈〉《》「」『』【】〔〕〖〗〘〙〚〛〜〰ー（）［］｛｝｟｠＜＞－＝＿｜～￣…‥")
+
+# Offsets in em of forms set in the upper right of vertical cells (UAX #50 Tu).
+const verticalShifts = merge(
+    Dict(chr => (0.1f0, 0.1f0) for chr in "ぁぃぅぇぉっゃゅょゎゕゖァィゥェォッャュョヮヵヶㇰㇱㇲㇳㇴㇵㇶㇷㇸㇹㇺㇻㇼㇽㇾㇿ"),
+    Dict(chr => (0.6f0, 0.6f0) for chr in "、。，．"),
+)
+
+# Whether the glyph shaped for `chr` stands upright, and whether shaping
+# already replaced it with a vertical alternate.
+function verticalForm(font::FontFace, chr::Char, glyphIdx)
+    isUpright(chr) || return (false, false)
+    alternate = glyphIdx != cmapGlyph(font, chr)
+    return (alternate || !(chr in turnedInVertical), alternate)
+end
+
+# Separation of the ascender and descender within cells of one em.
+emBoxAscent(font::FontFace) =
+    Float32(font.metrics.ascender*font.emSize/(font.metrics.ascender - font.metrics.descender))
+
+# `shapeStyled` with the vert alternates of the face and advances along the column.
+function shapeVertical(style::TextStyle, text::AbstractString; clusterOffset=0)
+    font = style.font
+    metrics = verticalMetrics(font)
+    return map(shapeStyled(style, text; clusterOffset=clusterOffset)) do (s, size, advance, glyphTransform)
+        chr = text[s.cluster - clusterOffset]
+        (upright, alternate) = verticalForm(font, chr, s.index)
+        upright || return (s, size, advance, rotation(π/2)*glyphTransform)
+        cell = metrics === nothing ? font.emSize : verticalAdvance(metrics, s.index)
+        if !alternate && haskey(verticalShifts, chr)
+            (dx, dy) = verticalShifts[chr]
+            s = ShapedGlyph(s.index, s.cluster, s.xAdvance, s.xOffset + round(FT_Pos, dx*font.emSize), s.yOffset + round(FT_Pos, dy*font.emSize))
+        end
+        (s, size, isInvisibleGlyph(s.index) ? 0f0 : cell*size/font.emSize, glyphTransform)
+    end
+end
+
+# Pushes a column of vertically shaped glyphs centered on `x`, starting
+# at `y`; returns the pen position after the column.
+function placeColumn!(positioned, style::TextStyle, column, text, x, y; clusterOffset=0)
+    font = style.font
+    metrics = verticalMetrics(font)
+    for (s, size, advance, glyphTransform) in column
+        glyph = prepareGlyph(font, s.index)
+        k = size/font.emSize
+        (upright, _) = verticalForm(font, text[s.cluster - clusterOffset], s.index)
+        if upright
+            bearing = metrics === nothing ? nothing : topBearing(metrics, s.index)
+            ascent = bearing === nothing ? emBoxAscent(font) : bearing + glyph.bearingY
+            (px, py) = (x - glyph.advance*k/2 + s.xOffset*k, y + ascent*k - s.yOffset*k)
+        else
+            # the em box of the rotated line centered on the column
+            (ox, oy) = glyphTransform*(s.xOffset*k, -s.yOffset*k)
+            (px, py) = (x - (font.metrics.ascender + font.metrics.descender)*k/2 + ox, y + oy)
+        end
+        push!(positioned, PositionedGlyph(glyph, font, px, py, size, style.color, s.cluster, glyphTransform))
+        y += advance
+    end
+    return y
+end
+
+# `layoutText` of vertical styles: `maxWidth` is the length of the columns
+# and `align` places the glyphs along them.
+function layoutVertical(text::String, style::TextStyle; origin, transform, maxWidth, align)
+    columnAdvance = style.font.metrics.height*pixelScale(style)*style.lineHeight
+    columns = []
+    for paragraph in eachsplit(text, '\n')
+        shaped = @span "shaping" shapeVertical(style, paragraph; clusterOffset=paragraph.offset)
+        lines = enableLayout && isfinite(maxWidth) ?
+            wrapLines(shaped, style, paragraph, maxWidth; clusterOffset=paragraph.offset) : [(shaped, false)]
+        append!(columns, (paragraph, line, wrapped) for (line, wrapped) in lines)
+    end
+    positioned = PositionedGlyph[]
+    (x0, y0) = origin
+    width = length(columns)*columnAdvance
+    height = 0f0
+    for (c, (paragraph, column, wrapped)) in enumerate(columns)
+        (offset, column) = alignLine(column, style, paragraph, maxWidth, align, wrapped; clusterOffset=paragraph.offset)
+        x = x0 + width - (c - 0.5f0)*columnAdvance
+        y = placeColumn!(positioned, style, column, paragraph, x, y0 + offset; clusterOffset=paragraph.offset)
+        height = max(height, y - y0)
+    end
+    layout = TextLayout(positioned, width, height)
+    return transform === nothing ? layout : transformLayout(layout, about(transform, origin))
+end
@@ -0,0 +1,344 @@
+using Test
+using WGPUFontRenderer
+
+const font = loadFont()
+const style = TextStyle(font; size=32f0)
+
+breakIndices(text; kwargs...) = Set(first.(lineBreaks(text; kwargs...)))
+
+glyphAt(layout, idx) = layout.glyphs[findfirst(pg -> pg.cluster == idx, layout.glyphs)]
+
+# Pen distance between the glyphs of the characters at `a` and `b`.
+distance(layout, a, b) = glyphAt(layout, b).x - glyphAt(layout, a).x
+
+# Halfway between the first line alone and everything on one line, so the
+# last word wraps and leaves slack on the first line.
+wrapWidth(firstLine, text) = (layoutText(firstLine, style).width + layoutText(text, style).width)/2
+
+@testset "French punctuation spacing" begin
+    @testset "narrow no break space before ;" begin
+        text = "mot\u202f; mot"
+        narrow = findfirst('\u202f', text)
+        semicolon = findfirst(';', text)
+        @test !(narrow in breakIndices(text))
+        @test !(semicolon in breakIndices(text))
+        # too narrow for the second word, the semicolon stays on the first line
+        layout = layoutText(text, style; maxWidth=layoutText("mot\u202f;", style).width + 1)
+        @test glyphAt(layout, narrow).y == layout.glyphs[1].y
+        @test glyphAt(layout, semicolon).y == layout.glyphs[1].y
+        @test layout.glyphs[end].y > layout.glyphs[1].y
+
+        text = "mot\u202f; mot mot"
+        (t, narrow, semicolon, space, m) = (3, 4, 7, 8, 9)
+        @test text[t] == 't' && text[narrow] == '\u202f' && text[semicolon] == ';' && text[space] == ' ' && text[m] == 'm'
+        maxWidth = wrapWidth("mot\u202f; mot", text)
+        left = layoutText(text, style; maxWidth=maxWidth)
+        justified = layoutText(text, style; maxWidth=maxWidth, align=alignJustify)
+        @test glyphAt(justified, m).y == justified.glyphs[1].y
+        @test distance(justified, t, narrow) == distance(left, t, narrow)
+        @test distance(justified, narrow, semicolon) == distance(left, narrow, semicolon)
+        # the word space takes the slack instead
+        @test distance(justified, space, m) > distance(left, space, m)
+    end
+
+    @testset "no break space stretches without breaking" begin
+        text = "mot\u00a0mot mot"
+        (nbsp, m) = (4, 6)
+        @test text[nbsp] == '\u00a0' && text[m] == 'm'
+        @test !(nbsp in breakIndices(text))
+        @test !(m in breakIndices(text))
+        maxWidth = wrapWidth("mot\u00a0mot", text)
+        left = layoutText(text, style; maxWidth=maxWidth)
+        justified = layoutText(text, style; maxWidth=maxWidth, align=alignJustify)
+        @test glyphAt(justified, m).y == glyphAt(justified, nbsp).y
+        @test distance(justified, nbsp, m) > distance(left, nbsp, m)
+    end
+
+    @testset "word joiner blocks a break" begin
+        @test 5 in breakIndices("mot mot")
+        text = "mot \u2060mot"
+        joiner = findfirst('\u2060', text)
+        @test !(joiner in breakIndices(text))
+        @test !(nextind(text, joiner) in breakIndices(text))
+        layout = layoutText(text, style; maxWidth=layoutText("mot", style).width + 1)
+        @test layout.glyphs[end].y == layout.glyphs[1].y
+    end
+end
+
+# Shaders are only checked by naga when it is installed, CI has to have it.
+@testset "shader validation" begin
+    naga = Sys.which("naga")
+    haskey(ENV, "CI") && @test naga !== nothing
+    if naga === nothing
+        @test_skip "naga not found"
+    else
+        shaderDir = WGPUFontRenderer.shaderDir
+        # fragments appended to font.wgsl, see embedShader
+        appended = ["computeraster.wgsl", "instanced.wgsl"]
+        fontSource = read(joinpath(shaderDir, "font.wgsl"), String)
+        for name in filter(endswith(".wgsl"), readdir(shaderDir))
+            if name in appended
+                mktempdir() do dir
+                    combined = joinpath(dir, name)
+                    write(combined, fontSource * read(joinpath(shaderDir, name), String))
+                    @test_nowarn WGPUFontRenderer.validateShader(combined)
+                end
+            else
+                @test_nowarn WGPUFontRenderer.validateShader(joinpath(shaderDir, name))
+            end
+        end
+    end
+end
+
+# Offscreen rendering needs an adapter, CI has to have one.
+@testset "headless readback" begin
+    device = try
+        WGPUFontRenderer.WGPUCore.getDefaultDevice()
+    catch
+        nothing
+    end
+    haskey(ENV, "CI") && @test device !== nothing
+    if device === nothing
+        @test_skip "no adapter"
+    else
+        (width, height) = (96, 48)
+        pixels = renderToTexture("Hi", style, (width, height); device=device, origin=(8f0, 8f0))
+        @test length(pixels) == 4*width*height
+        # alpha of the top left pixel and somewhere across the glyphs
+        @test pixels[4] == 0
+        @test any(>(0), pixels[4:4:end])
+        cache = WGPUFontRenderer.headlessCache(device)
+        @test renderToTexture("Hi", style, (width, height); device=device, origin=(8f0, 8f0)) == pixels
+        @test WGPUFontRenderer.headlessCache(device) === cache
+        @test all(==(0), renderToTexture("", style, (width, height); device=device))
+    end
+end
+
+@testset "line breaking" begin
+    strict(text) = breakIndices(text; strictness=lineBreakStrict)
+    normal(text) = breakIndices(text; strictness=lineBreakNormal)
+    loose(text) = breakIndices(text; strictness=lineBreakLoose)
+
+    @testset "Japanese kinsoku" begin
+        # small kana and the prolonged sound mark only start lines outside strict mode
+        text = "あっあ"
+        smallKana = nextind(text, 1)
+        @test !(smallKana in strict(text))
+        @test smallKana in normal(text)
+        @test smallKana in loose(text)
+        text = "カー"
+        @test !(nextind(text, 1) in strict(text))
+        @test nextind(text, 1) in normal(text)
+        # iteration marks only in loose mode
+        text = "人々人"
+        mark = nextind(text, 1)
+        @test !(mark in strict(text))
+        @test !(mark in normal(text))
+        @test mark in loose(text)
+        @test nextind(text, mark) in strict(text)
+    end
+
+    @testset "CJK" begin
+        # ideographs break anywhere but before closing punctuation
+        text = "漢字。漢字"
+        @test breakIndices(text) == Set([4, 10, 13])
+        # nor after opening brackets or before closing ones
+        text = "漢「字」"
+        @test breakIndices(text) == Set([4])
+        @test WGPUFontRenderer.lineBreakClass('」') == :CL
+        @test WGPUFontRenderer.lineBreakClass('（') == :OP
+    end
+
+    @testset "numbers and punctuation" begin
+        for text in ("3.14", "\$100", "50%", "1,000", "end!", "(a)", "x/2")
+            @test isempty(lineBreaks(text))
+        end
+        # after hyphens, but not between a minus sign and its number
+        @test breakIndices("well-known") == Set([6])
+        @test breakIndices("x -5") == Set([3])
+        # opening brackets stick to what follows
+        @test breakIndices("word (paren)") == Set([6])
+        @test lineBreaks("a\nb") == [(3, true)]
+    end
+end
+
+# Glyphs of one unit, spaces are glue and `~` a soft hyphen drawing a hyphen
+# of one unit where a line breaks after it.
+function totalFitStarts(text, maxWidth)
+    chars = collect(text)
+    n = length(chars)
+    breaks = Dict{Int, Bool}(k + 1 => false for k in 1:(n - 1) if chars[k] in (' ', '~'))
+    starts = WGPUFontRenderer.optimalBreaks(
+        n, breaks, Float32(maxWidth);
+        advanceOf=k -> chars[k] == '~' ? 0f0 : 1f0,
+        isSpace=k -> chars[k] == ' ',
+        isGlue=k -> chars[k] == ' ',
+        hyphenAdvance=k -> chars[k] == '~' ? 1f0 : 0f0
+    )
+    return first.(starts)
+end
+
+lineStarts(layout) = [pg.cluster for (i, pg) in enumerate(layout.glyphs) if i > 1 && pg.y > layout.glyphs[i - 1].y]
+
+@testset "total fit line breaking" begin
+    @testset "differs from greedy" begin
+        # greedy fills "aaaa bb" and leaves "cccccc" alone on a loose line,
+        # total fit moves "bb" down where it fills the second line exactly
+        text = "aaaa bb cccccc dddd"
+        cell = layoutText("a", style).width
+        greedy = layoutText(text, style; maxWidth=9.5f0*cell)
+        optimal = layoutText(text, TextStyle(font; size=32f0, lineBreaker=optimalBreaking); maxWidth=9.5f0*cell)
+        @test lineStarts(greedy) == [9, 16]
+        @test lineStarts(optimal) == [6, 16]
+        @test totalFitStarts(text, 9.5) == [6, 16]
+    end
+
+    @testset "over-long words" begin
+        # a word wider than the measure still gets a line of its own
+        @test totalFitStarts("aa bbbbbbbbbbbb cc", 6) == [4, 17]
+        @test totalFitStarts("bbbbbbbbbbbb", 6) == Int[]
+    end
+
+    @testset "hyphens" begin
+        text = "aa bbb~ccc dd ee"
+        # the hyphen has to fit, "aa bbb-" is seven units
+        @test totalFitStarts(text, 6) == [4, 12]
+        @test totalFitStarts(text, 7) == [8, 15]
+        # between equally bad lines the penalty avoids hyphenating
+        @test totalFitStarts("aa bb~ccccc dddd", 9) == [4, 13]
+    end
+end
+
+@testset "text flow" begin
+    cell = layoutText("a", style).width
+    lineAdvance = flowText("a", style, [TextContainer(100cell, 100cell)])[1][1].height
+    # two words of three cells per line of 7.5 cells
+    paragraph(lines) = join(fill("aaa", 2lines), ' ')
+    column(lines) = TextContainer((0f0, 0f0), 7.5f0*cell, (lines + 0.5f0)*lineAdvance)
+    tall = TextContainer((0f0, 0f0), 7.5f0*cell, 100lineAdvance)
+
+    @testset "orphans and widows" begin
+        # a single line would be left behind, the paragraph moves on
+        text = "bbb\n" * paragraph(5)
+        (layouts, overflow, boxes) = flowText(text, style, [column(2), tall])
+        @test overflow === nothing
+        @test length(boxes[1]) == 1
+        @test length(boxes[2]) == 5
+        @test layouts[2].glyphs[1].cluster == 5
+        # a single line would continue alone, one more goes along
+        (_, _, boxes) = flowText(paragraph(5), style, [column(4), tall])
+        @test length.(boxes) == [3, 2]
+        (_, _, boxes) = flowText(paragraph(5), style, [column(4), tall]; orphans=1, widows=1)
+        @test length.(boxes) == [4, 1]
+        # what does not fit the last container overflows
+        (_, overflow, boxes) = flowText(paragraph(5), style, [column(4)]; widows=1)
+        @test length.(boxes) == [4]
+        @test overflow == 4*8 + 1
+    end
+
+    @testset "exclusions" begin
+        # a figure in the top left corner over two lines
+        figure = Exclusion(0f0, 0f0, 3cell, 2lineAdvance - 1)
+        container = TextContainer((0f0, 0f0), 11.5f0*cell, 100lineAdvance, [figure])
+        (_, _, boxes) = flowText(paragraph(4), style, [container])
+        @test all(box -> box.x >= 3cell, boxes[1][1:2])
+        @test boxes[1][3].x == 0
+        # lines beside the figure hold two words, the others three
+        @test length(boxes[1][1].glyphs) == 7
+        @test length(boxes[1][3].glyphs) == 11
+        # a band blocked across its whole width is skipped
+        bar = Exclusion(0f0, lineAdvance + 1, 11.5f0*cell, lineAdvance - 2)
+        (_, _, boxes) = flowText(paragraph(2), style, [TextContainer((0f0, 0f0), 7.5f0*cell, 100lineAdvance, [bar])])
+        @test [box.y for box in boxes[1]] ≈ [0, 2lineAdvance]
+    end
+
+    @testset "column balancing" begin
+        columns = ColumnLayout(2, 7.5f0*cell; balance=true)
+        (layouts, overflow, boxes) = layoutColumns([paragraph(5)], style, columns)
+        @test overflow === nothing
+        @test length.(boxes) == [3, 2]
+        @test layouts[2].glyphs[1].x ≈ 7.5f0*cell + 16
+        # the second paragraph has no room left in the first column
+        (_, _, boxes) = layoutColumns([paragraph(3), paragraph(3)], style, columns)
+        @test length.(boxes) == [3, 3]
+        # unbalanced columns of a fixed height fill one by one
+        (_, _, boxes) = layoutColumns([paragraph(5)], style, ColumnLayout(2, 7.5f0*cell; height=4.5f0*lineAdvance); widows=1)
+        @test length.(boxes) == [4, 1]
+    end
+
+    @testset "pagination" begin
+        text = join([paragraph(3), paragraph(5), paragraph(2)], '\n')
+        pageSize = (7.5f0*cell + 20, 4.5f0*lineAdvance + 20)
+        pages = paginate(text, style, pageSize; margin=10)
+        @test first(pages[1].range) == 1
+        @test last(pages[end].range) == ncodeunits(text)
+        @test all(k -> first(pages[k + 1].range) == last(pages[k].range) + 1, 1:(length(pages) - 1))
+        @test all(page -> !isempty(page.lineBoxes), pages)
+        for page in pages[2:end]
+            @test page.layout.glyphs[1].cluster == first(page.range)
+        end
+        # the same text, style and size always breaks at the same indices
+        @test [page.range for page in paginate(text, style, pageSize; margin=10)] == [page.range for page in pages]
+        # and text appended after the last page leaves the earlier pages alone
+        longer = paginate(text * "\n" * paragraph(6), style, pageSize; margin=10)
+        @test [page.range for page in longer[1:(length(pages) - 1)]] == [page.range for page in pages[1:(end - 1)]]
+    end
+end
+
+# Big endian u16 words, strings as their four tag bytes.
+gsubBytes(words...) = reduce(vcat, (w isa String ? Vector{UInt8}(w) : UInt8[w >> 8, w & 0xff] for w in words); init=UInt8[])
+
+# DFLT enables "liga" (lookup 2, 1 2 => 30) and "calt" (lookup 3, a 10
+# between 5 and 6 runs lookup 1, 10 => 20). Offsets are to the start of the
+# enclosing list or table.
+const gsubFixture = gsubBytes(
+    1, 0, 10, 32, 58,
+    # script list, script and default language system with features 0 and 1
+    1, "DFLT", 8,  4, 0,  0, 0xffff, 2, 0, 1,
+    # feature list
+    2, "liga", 14, "calt", 20,  0, 1, 1,  0, 1, 2,
+    # lookup list
+    3, 8, 30, 62,
+    # single substitution format 2 and its coverage
+    1, 0, 1, 8,  2, 8, 1, 20,  1, 1, 10,
+    # ligature substitution, coverage, ligature set and ligature
+    4, 0, 1, 8,  1, 8, 1, 14,  1, 1, 1,  1, 4,  30, 2, 2,
+    # chained context format 3 with backtrack, input and lookahead coverages
+    6, 0, 1, 8,  3, 1, 20, 1, 26, 1, 32, 1, 0, 0,  1, 1, 5,  1, 1, 10,  1, 1, 6)
+
+# Glyph run with the clusters 1, 2, ...
+glyphRun(glyphs...) = [(WGPUFontRenderer.FT_UInt(g), k) for (k, g) in enumerate(glyphs)]
+
+@testset "GSUB lookups" begin
+    gsub = WGPUFontRenderer.parseGSUB(gsubFixture)
+    @test [lookup.type for lookup in gsub.lookups] == [1, 4, 6]
+    @test WGPUFontRenderer.hasFeature(gsub, "liga")
+    @test !WGPUFontRenderer.hasFeature(gsub, "smcp")
+    apply(run, tags...) = WGPUFontRenderer.applyFeatures!(run, gsub, tags)
+
+    @testset "single" begin
+        run = WGPUFontRenderer.applyLookup!(glyphRun(10, 3, 10), gsub, gsub.lookups[1])
+        @test run == glyphRun(20, 3, 20)
+    end
+
+    @testset "ligature" begin
+        # the ligature keeps the cluster of its first component
+        @test apply(glyphRun(3, 1, 2, 4), "liga") == [(3, 1), (30, 2), (4, 4)]
+        @test apply(glyphRun(1, 3, 2), "liga") == glyphRun(1, 3, 2)
+    end
+
+    @testset "chained context" begin
+        @test apply(glyphRun(5, 10, 6), "calt") == glyphRun(5, 20, 6)
+        # backtrack and lookahead have to match
+        @test apply(glyphRun(4, 10, 6), "calt") == glyphRun(4, 10, 6)
+        @test apply(glyphRun(5, 10, 7), "calt") == glyphRun(5, 10, 7)
+        # the nested lookup runs at the matched input only
+        @test apply(glyphRun(10, 5, 10, 6, 10), "calt") == glyphRun(10, 5, 20, 6, 10)
+    end
+
+    @testset "clusters across features" begin
+        run = apply(glyphRun(1, 2, 5, 10, 6), "liga", "calt")
+        @test run == [(30, 1), (5, 3), (20, 4), (6, 5)]
+        @test issorted(last.(run))
+    end
+end